@@ -1,11 +1,12 @@
 use cosmwasm_schema::write_api;
 
-use coin_flip::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+use coin_flip::msg::{ChainSudoMsg, ExecuteMsg, InstantiateMsg, QueryMsg};
 
 fn main() {
     write_api! {
         instantiate: InstantiateMsg,
         execute: ExecuteMsg,
         query: QueryMsg,
+        sudo: ChainSudoMsg,
     }
 }
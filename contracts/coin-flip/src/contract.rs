@@ -1,20 +1,27 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
-use cosmwasm_std::{ensure_eq, Binary, Deps, DepsMut, Env, MessageInfo, StdResult, Uint128};
+use cosmwasm_std::{Binary, Deps, DepsMut, Env, MessageInfo, Reply, StdResult, Uint128};
 use cw2::set_contract_version;
+use cw_utils::Duration;
 use sg_std::Response;
 
 use crate::error::ContractError;
-use crate::helpers::{ensure_admin, ensure_not_paused};
-use crate::msg::{ExecuteMsg, FlipExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, SudoMsg};
-use crate::state::{CONFIG, FEES, FLIPS, TODO_FLIPS};
-use crate::types::{Config, Wallets};
-
-use crate::sudo;
+use crate::helpers::{ensure_admin, ensure_not_paused, ensure_role, ensure_role_manager};
+use crate::msg::{
+    ChainSudoMsg, ExecuteMsg, FlipExecuteMsg, InstantiateMsg, IntegratorExecuteMsg, MigrateMsg,
+    MultisigExecuteMsg, QueryMsg, ReferralExecuteMsg, SudoMsg, VaultExecuteMsg,
+};
+use crate::state::{CONFIG, FEES, FLIPS, MULTISIG_MEMBERS, TODO_FLIPS};
+use crate::types::{Config, ConfigUpdate, PauseScope, PauseScopes, Role};
+
+use crate::{
+    integrator, migrations, multisig, ownership, referral, refill, schedule, staking, sudo,
+    timelock, vault,
+};
 
 // version info for migration info
-const CONTRACT_NAME: &str = "cosmos-coin-flip";
-const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+pub const CONTRACT_NAME: &str = "cosmos-coin-flip";
+pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 /// Minimum amount of tokens we need to have in the contract
 pub const MIN_BANK_AMOUNT: Uint128 = Uint128::new(30_000_000_000);
@@ -24,43 +31,65 @@ pub const MIN_BET: Uint128 = Uint128::new(5_000_000);
 /// Max bet people are allow to bet
 pub const MAX_BET: Uint128 = Uint128::new(25_000_000);
 
+/// Default time LPs wait before they can withdraw from the vault (7 days)
+pub const DEFAULT_VAULT_COOLDOWN: Duration = Duration::Time(7 * 24 * 60 * 60);
+/// Default min delay of config changes (24 hours)
+pub const DEFAULT_TIMELOCK_DELAY: u64 = 24 * 60 * 60;
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
     _env: Env,
-    info: MessageInfo,
+    _info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
-    // Verify the wallets are correct.
-    deps.api.addr_validate(&msg.wallets.team)?;
-    deps.api.addr_validate(&msg.wallets.reserve)?;
+    msg.fees.validate(deps.api)?;
 
     let sg721_addr = match msg.sg721_addr {
         Some(addr) => Some(deps.api.addr_validate(&addr)?),
         None => None,
     };
 
-    // Save config
-    CONFIG.save(
-        deps.storage,
-        &Config {
-            admin: info.sender.to_string(),
-            denoms: msg.denoms,
-            bank_limit: msg.bank_limit.unwrap_or(MIN_BANK_AMOUNT),
-            min_bet_limit: msg.min_bet_limit.unwrap_or(MIN_BET),
-            max_bet_limit: msg.max_bet_limit.unwrap_or(MAX_BET),
-            flips_per_block_limit: msg.flips_per_block_limit.unwrap_or(10), // 10 flips per block
-            wallets: Wallets {
-                team: msg.wallets.team,
-                reserve: msg.wallets.reserve,
-            },
-            fees: msg.fees,
-            sg721_addr,
-            is_paused: false,
-        },
-    )?;
+    if let Some(schedule) = &msg.distribution_schedule {
+        schedule.validate()?;
+    }
+
+    let vault_cooldown = msg.vault_cooldown.unwrap_or(DEFAULT_VAULT_COOLDOWN);
+    sudo::validate_vault_cooldown(vault_cooldown)?;
+    sudo::validate_max_bet_bank_bps(msg.max_bet_bank_bps)?;
+
+    if let Some(treasury) = &msg.treasury {
+        treasury.validate(deps.api)?;
+    }
+
+    let staking_addr = match msg.staking_addr {
+        Some(addr) => Some(deps.api.addr_validate(&addr)?),
+        None => None,
+    };
+
+    let config = Config {
+        admin: Some(deps.api.addr_validate(&msg.admin)?),
+        denoms: msg.denoms,
+        bank_limit: msg.bank_limit.unwrap_or(MIN_BANK_AMOUNT),
+        min_bet_limit: msg.min_bet_limit.unwrap_or(MIN_BET),
+        max_bet_limit: msg.max_bet_limit.unwrap_or(MAX_BET),
+        max_bet_bank_bps: msg.max_bet_bank_bps,
+        flips_per_block_limit: msg.flips_per_block_limit.unwrap_or(10), // 10 flips per block
+        fees: msg.fees,
+        sg721_addr,
+        staking_addr,
+        min_holder_payout: msg.min_holder_payout.unwrap_or_default(),
+        max_integrator_bps: msg.max_integrator_bps.unwrap_or_default(),
+        distribution_schedule: msg.distribution_schedule,
+        vault_cooldown,
+        treasury: msg.treasury,
+        timelock_delay: msg.timelock_delay.unwrap_or(DEFAULT_TIMELOCK_DELAY),
+        paused: PauseScopes::default(),
+    };
+    config.validate()?;
+    CONFIG.save(deps.storage, &config)?;
 
     // Init fees to be 0
     FEES.save(deps.storage, &Uint128::zero())?;
@@ -80,51 +109,160 @@ pub fn execute(
     let config = CONFIG.load(deps.storage)?;
 
     match msg {
-        ExecuteMsg::Flip(FlipExecuteMsg::StartFlip { pick, amount }) => {
-            ensure_not_paused(&config)?;
-            flip_execute::execute_start_flip(deps, env, info, &config, pick, amount)
+        ExecuteMsg::Flip(FlipExecuteMsg::StartFlip {
+            pick,
+            amount,
+            integrator,
+        }) => {
+            ensure_not_paused(&config, PauseScope::NewFlips)?;
+            flip_execute::execute_start_flip(deps, env, info, &config, pick, amount, integrator)
         }
         ExecuteMsg::Flip(FlipExecuteMsg::DoFlips {}) => {
-            ensure_not_paused(&config)?;
+            ensure_not_paused(&config, PauseScope::Settlement)?;
             flip_execute::execute_do_flips(deps, env, &config)
         }
+        ExecuteMsg::Referral(ReferralExecuteMsg::RegisterReferrer { referrer }) => {
+            referral::register_referrer(deps, info, referrer)
+        }
+        ExecuteMsg::Referral(ReferralExecuteMsg::ClaimReferralRewards {}) => {
+            ensure_not_paused(&config, PauseScope::Withdrawals)?;
+            referral::claim_referral_rewards(deps, info, &config)
+        }
+        ExecuteMsg::Integrator(IntegratorExecuteMsg::ClaimIntegratorFees {}) => {
+            ensure_not_paused(&config, PauseScope::Withdrawals)?;
+            integrator::claim_integrator_fees(deps, info, &config)
+        }
+        ExecuteMsg::Vault(VaultExecuteMsg::Deposit {}) => {
+            ensure_not_paused(&config, PauseScope::NewFlips)?;
+            vault::deposit(deps, info, &config)
+        }
+        ExecuteMsg::Vault(VaultExecuteMsg::RequestWithdraw { shares }) => {
+            vault::request_withdraw(deps, env, info, &config, shares)
+        }
+        ExecuteMsg::Vault(VaultExecuteMsg::Withdraw {}) => {
+            ensure_not_paused(&config, PauseScope::Withdrawals)?;
+            vault::withdraw(deps, env, info, &config)
+        }
+        ExecuteMsg::Vault(VaultExecuteMsg::CancelWithdraw {}) => vault::cancel_withdraw(deps, info),
+        ExecuteMsg::Multisig(MultisigExecuteMsg::Propose { msg }) => {
+            multisig::propose(deps, env, info, msg)
+        }
+        ExecuteMsg::Multisig(MultisigExecuteMsg::Vote { id, approve }) => {
+            multisig::vote(deps, env, info, id, approve)
+        }
+        ExecuteMsg::Multisig(MultisigExecuteMsg::Execute { id }) => {
+            multisig::execute(deps, env, id)
+        }
+        ExecuteMsg::AcceptAdmin {} => ownership::accept_admin(deps, env, info, config),
+        ExecuteMsg::Distribute {} => {
+            ensure_not_paused(&config, PauseScope::Distribution)?;
+            schedule::trigger_distribution(deps, env, info, &config)
+        }
+        ExecuteMsg::RefillBank {} => refill::refill_bank(deps, env, &config),
+        ExecuteMsg::ExecutePending { id } => timelock::execute_pending(deps, env, config, id),
+        ExecuteMsg::StakeChangeHook(msg) => staking::stake_changed(deps, info, &config, msg),
+        ExecuteMsg::SyncStaker { address } => staking::sync_staker(deps, &config, address),
         ExecuteMsg::Sudo(SudoMsg::Distribute {}) => {
-            ensure_admin(&config, &info)?;
-            sudo::distribute(deps, env, &config)
+            ensure_role(deps.as_ref(), &config, &info, Role::Distributor)?;
+            ensure_not_paused(&config, PauseScope::Distribution)?;
+            sudo::distribute(deps, env, &config, None)
         }
-        ExecuteMsg::Sudo(SudoMsg::UpdateFees { fees }) => {
-            ensure_admin(&config, &info)?;
-            sudo::update_fees(deps, config, fees)
+        ExecuteMsg::Sudo(SudoMsg::StartDistribution {}) => {
+            ensure_role(deps.as_ref(), &config, &info, Role::Distributor)?;
+            ensure_not_paused(&config, PauseScope::Distribution)?;
+            sudo::start_distribution(deps, env, &config)
+        }
+        ExecuteMsg::Sudo(SudoMsg::ContinueDistribution { limit }) => {
+            ensure_role(deps.as_ref(), &config, &info, Role::Distributor)?;
+            ensure_not_paused(&config, PauseScope::Distribution)?;
+            sudo::continue_distribution(deps, env, limit)
         }
-        ExecuteMsg::Sudo(SudoMsg::UpdateBankLimit { limit }) => {
+        ExecuteMsg::Sudo(SudoMsg::ProposeAdmin { admin, expires }) => {
             ensure_admin(&config, &info)?;
-            sudo::update_bank_limit(deps, config, limit)
+            ownership::propose_admin(deps, env, admin, expires)
         }
-        ExecuteMsg::Sudo(SudoMsg::UpdateSg721 { addr }) => {
+        ExecuteMsg::Sudo(SudoMsg::RenounceAdmin {}) => {
             ensure_admin(&config, &info)?;
-            sudo::update_sg721(deps, config, addr)
+            ownership::renounce_admin(deps, config)
+        }
+        ExecuteMsg::Sudo(SudoMsg::FundBank {}) => {
+            // Proposals can't send funds, multisig members fund the bank directly
+            if !MULTISIG_MEMBERS.has(deps.storage, &info.sender) {
+                ensure_role(deps.as_ref(), &config, &info, Role::SuperAdmin)?;
+            }
+            sudo::fund_bank(deps, info, &config)
+        }
+        ExecuteMsg::Sudo(SudoMsg::WithdrawBank { amount, denom }) => {
+            ensure_role(deps.as_ref(), &config, &info, Role::SuperAdmin)?;
+            sudo::withdraw_bank(deps, env, info, &config, amount, denom)
+        }
+        ExecuteMsg::Sudo(SudoMsg::UpdateExcludedHolders { add, remove }) => {
+            ensure_role(deps.as_ref(), &config, &info, Role::FeeManager)?;
+            sudo::update_excluded_holders(deps, add, remove)
         }
         ExecuteMsg::Sudo(SudoMsg::UpdatePause(is_paused)) => {
-            ensure_admin(&config, &info)?;
-            sudo::update_pause(deps, config, is_paused)
+            let role = if is_paused {
+                Role::Pauser
+            } else {
+                Role::SuperAdmin
+            };
+            ensure_role(deps.as_ref(), &config, &info, role)?;
+            sudo::update_pause(deps, config, PauseScopes::all(is_paused))
         }
-        ExecuteMsg::Sudo(SudoMsg::UpdateBetLimit { min_bet, max_bet }) => {
+        ExecuteMsg::Sudo(SudoMsg::UpdatePauseScopes { scopes }) => {
+            // Pausers can only pause more
+            let role = if scopes.includes(&config.paused) {
+                Role::Pauser
+            } else {
+                Role::SuperAdmin
+            };
+            ensure_role(deps.as_ref(), &config, &info, role)?;
+            sudo::update_pause(deps, config, scopes)
+        }
+        ExecuteMsg::Sudo(SudoMsg::RefundPendingFlips {}) => {
+            ensure_role(deps.as_ref(), &config, &info, Role::SuperAdmin)?;
+            sudo::refund_pending_flips(deps)
+        }
+        ExecuteMsg::Sudo(SudoMsg::ProposeConfigChange {
+            change,
+            effective_at,
+        }) => timelock::propose_config_change(deps, env, info, &config, change, effective_at),
+        ExecuteMsg::Sudo(SudoMsg::CancelConfigChange { id }) => {
+            timelock::cancel_config_change(deps, info, &config, id)
+        }
+        ExecuteMsg::Sudo(SudoMsg::UpdateMultisig {
+            members,
+            threshold,
+            voting_period,
+        }) => {
             ensure_admin(&config, &info)?;
-            sudo::update_bet_limit(deps, config, min_bet, max_bet)
+            multisig::update_multisig(deps, env, config, members, threshold, voting_period)
+        }
+        ExecuteMsg::Sudo(SudoMsg::GrantRole { role, address }) => {
+            ensure_role_manager(deps.as_ref(), &config, &info, role)?;
+            ownership::grant_role(deps, role, address)
+        }
+        ExecuteMsg::Sudo(SudoMsg::RevokeRole { role, address }) => {
+            ensure_role_manager(deps.as_ref(), &config, &info, role)?;
+            ownership::revoke_role(deps, role, address)
         }
     }
 }
 
 mod flip_execute {
 
-    use cw_utils::must_pay;
-
     use cosmwasm_std::{coin, ensure, BankMsg, Event, Uint128};
     use sha256::Sha256Digest;
 
-    use crate::helpers::ensure_correct_funds;
-    use crate::state::{get_next_flip_id, FEES, FLIPS, FLIP_ID, SCORES};
-    use crate::types::{Flip, FlipScore, PickTypes, TodoFlip};
+    use crate::helpers::{
+        ensure_correct_funds, ensure_solvent, get_available_bank, get_effective_limits,
+    };
+    use crate::msg::FlipQuoteResponse;
+    use crate::state::{
+        add_liability, get_liabilities, get_next_flip_id, remove_liability, update_house_ledger,
+        FEES, FLIPS, FLIP_ID, SCORES, VAULT,
+    };
+    use crate::types::{Flip, FlipScore, IntegratorFee, PickTypes, TodoFlip};
 
     use super::*;
 
@@ -135,14 +273,8 @@ mod flip_execute {
         config: &Config,
         pick: PickTypes,
         amount: Uint128,
+        integrator: Option<IntegratorFee>,
     ) -> Result<Response, ContractError> {
-        // Make sure that the sent amount is not above our max
-        ensure!(
-            amount <= config.max_bet_limit,
-            ContractError::OverTheLimitBet {
-                max_limit: (config.max_bet_limit / Uint128::new(1000000)).to_string()
-            }
-        );
         ensure!(
             amount >= config.min_bet_limit,
             ContractError::UnderTheLimitBet {
@@ -174,37 +306,67 @@ mod flip_execute {
 
         let funds = info.funds[0].clone();
         // Verify the sent funds is in supported denom.
-        let denom = if config.denoms.clone().into_iter().any(|x| *x == funds.denom) {
+        // TODO: Currently we only accept 1 denom, fees and LPs capital are in the first one,
+        // so the flips settlement and solvency checks only handle that one.
+        let denom = if funds.denom == config.denoms[0] {
             funds.denom
         } else {
             return Err(ContractError::WrongDenom { denom: funds.denom });
         };
 
-        // Make sure the paid amount is correct (funds sent is the amount + fee)
-        let fee_amount = ensure_correct_funds(funds.amount, amount, config.fees.flip_bps)?;
-        let should_pay_amount = amount.checked_add(fee_amount)?;
-        let paid_amount = must_pay(&info, &denom)?;
-
-        ensure_eq!(
-            should_pay_amount,
-            paid_amount,
-            ContractError::WrongPaidAmount
+        // Make sure that the sent amount is not above our max,
+        // the bank we check against is without the funds sent with this flip.
+        let available_bank =
+            get_available_bank(deps.as_ref(), &env, &denom)?.saturating_sub(funds.amount);
+        let limits = get_effective_limits(config, denom.clone(), available_bank);
+        ensure!(
+            amount <= limits.max_bet,
+            ContractError::OverTheLimitBet {
+                max_limit: (limits.max_bet / Uint128::new(1000000)).to_string()
+            }
         );
 
-        // Make sure we have funds to pay for the flip
-        let mut fees = FEES.load(deps.storage)?;
+        let integrator = match integrator {
+            Some(IntegratorFee { addr, bps }) => Some((deps.api.addr_validate(&addr)?, bps)),
+            None => None,
+        };
+
+        // Make sure the paid amount is correct (funds sent is the amount + fees)
+        let FlipQuoteResponse {
+            fee: fee_amount,
+            integrator_fee,
+            ..
+        } = ensure_correct_funds(
+            deps.as_ref(),
+            config,
+            &info,
+            &denom,
+            amount,
+            integrator.as_ref().map_or(0, |(_, bps)| *bps),
+        )?;
+
+        // Make sure we have funds to pay for this flip and all the pending ones
+        let fees = FEES
+            .load(deps.storage)?
+            .checked_add(fee_amount)?
+            .checked_add(integrator_fee)?;
+        let liabilities = add_liability(deps.storage, &denom, amount * Uint128::new(2))?;
         let balance = deps
             .querier
             .query_balance(&env.contract.address, denom.clone())?;
-        ensure!(
-            balance.amount - fees >= amount * Uint128::new(2),
-            ContractError::ContractMissingFunds
-        );
+        ensure_solvent(balance.amount, fees, liabilities)?;
 
-        // Save fees
-        fees = fees.checked_add(fee_amount)?;
+        // Save fees, integrator fee is kept in FEES until claimed
         FEES.save(deps.storage, &fees)?;
 
+        if let Some((integrator_addr, _)) = &integrator {
+            integrator::credit_integrator(deps.storage, integrator_addr, amount, integrator_fee)?;
+        }
+
+        // Referrer share is kept in FEES until claimed
+        let referral_reward =
+            referral::credit_referrer(deps.storage, config, &info.sender, amount, fee_amount)?;
+
         let id = get_next_flip_id(deps.storage);
         FLIP_ID.save(deps.storage, &id)?;
 
@@ -219,8 +381,12 @@ mod flip_execute {
         });
         TODO_FLIPS.save(deps.storage, &todo_flips)?;
 
-        Ok(Response::default()
-            .add_event(Event::new("start_flip").add_attribute("id", id.to_string())))
+        Ok(Response::default().add_event(
+            Event::new("start_flip")
+                .add_attribute("id", id.to_string())
+                .add_attribute("referral_reward", referral_reward)
+                .add_attribute("integrator_fee", integrator_fee),
+        ))
     }
 
     pub(crate) fn execute_do_flips(
@@ -238,10 +404,20 @@ mod flip_execute {
         let balance = deps
             .querier
             .query_balance(&env.contract.address, config.denoms[0].clone())?;
-        ensure!(
-            balance.amount - fees >= total_amount_to_pay * Uint128::new(2),
-            ContractError::ContractMissingFunds
-        );
+        ensure_solvent(
+            balance.amount,
+            fees,
+            get_liabilities(deps.storage, &config.denoms[0])?,
+        )?;
+
+        // Bank before the flips, without the amounts flippers sent
+        let bank = balance
+            .amount
+            .checked_sub(fees)?
+            .checked_sub(total_amount_to_pay)?;
+        let mut house_profit = Uint128::zero();
+        let mut house_loss = Uint128::zero();
+        let mut settled_flips = vec![];
 
         let mut msgs = vec![];
         let mut response = Response::default();
@@ -281,13 +457,18 @@ mod flip_execute {
                     }
                     last_flips.push(flip);
 
+                    settled_flips.push(todo_flip.amount.clone());
+
                     // Send funds if they won
                     if flip_result {
+                        house_loss += todo_flip.amount.amount;
                         let pay = todo_flip.amount.amount * Uint128::new(2); // double the amount
                         msgs.push(BankMsg::Send {
                             to_address: todo_flip.wallet.to_string(),
                             amount: vec![coin(pay.u128(), todo_flip.amount.denom.clone())],
                         });
+                    } else {
+                        house_profit += todo_flip.amount.amount;
                     }
 
                     response = response.clone().add_event(
@@ -307,9 +488,40 @@ mod flip_execute {
         FLIPS.save(deps.storage, &last_flips).unwrap();
         TODO_FLIPS.save(deps.storage, &save_todo_flips)?;
 
+        // Settled flips are paid (or lost), we don't owe them anymore
+        for settled in settled_flips {
+            remove_liability(
+                deps.storage,
+                &settled.denom,
+                settled.amount * Uint128::new(2),
+            )?;
+        }
+
+        // LPs take their share of the house PnL
+        if let Some(mut vault) = VAULT.may_load(deps.storage)? {
+            let (vault_profit, vault_loss) = vault.apply_pnl(bank, house_profit, house_loss)?;
+            VAULT.save(deps.storage, &vault)?;
+            house_profit = house_profit.checked_sub(vault_profit)?;
+            house_loss = house_loss.checked_sub(vault_loss)?;
+        }
+        update_house_ledger(deps.storage, &config.denoms[0], |ledger| {
+            ledger.flip_profit = ledger.flip_profit.checked_add(house_profit)?;
+            ledger.flip_loss = ledger.flip_loss.checked_add(house_loss)?;
+            Ok(())
+        })?;
+
+        let refill = refill::auto_refill(
+            deps,
+            &env,
+            config,
+            refill::outgoing_amount(&msgs, &config.denoms[0]),
+        )?;
+
         Ok(response
             .add_attribute("flip_action", "do_flips")
-            .add_messages(msgs))
+            .add_messages(msgs)
+            .add_submessages(refill.messages)
+            .add_events(refill.events))
     }
 
     fn get_random(env: &Env) -> u64 {
@@ -336,7 +548,7 @@ mod flip_execute {
             .iter()
             .fold(0, |acc, x| acc + *x as u64);
 
-        let flip_result = (rand + er) % 2 == 0;
+        let flip_result = (rand + er).is_multiple_of(2);
 
         // if picked heads and flip_result is true, he won
         let won_heads = todo_flip.pick == PickTypes::Heads && flip_result;
@@ -348,6 +560,36 @@ mod flip_execute {
     }
 }
 
+/// Privileged msgs from chain governance, they bypass the admin checks
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn sudo(deps: DepsMut, _env: Env, msg: ChainSudoMsg) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    let res = match msg {
+        ChainSudoMsg::UpdatePause(is_paused) => {
+            sudo::update_pause(deps, config, PauseScopes::all(is_paused))
+        }
+        ChainSudoMsg::UpdateFees { fees } => sudo::update_config(
+            deps,
+            config,
+            ConfigUpdate {
+                fees: Some(fees),
+                ..Default::default()
+            },
+        ),
+        ChainSudoMsg::ResetAdmin { admin } => ownership::reset_admin(deps, config, admin),
+    }?;
+    Ok(res.add_attribute("caller", "governance"))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    match msg.id {
+        refill::AUTO_REFILL_REPLY_ID => refill::auto_refill_reply(deps, msg),
+        id => Err(ContractError::UnknownReplyId { id }),
+    }
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
@@ -356,20 +598,98 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::GetScore { address } => query::get_score(deps, address),
         QueryMsg::GetConfig {} => query::get_config(deps),
         QueryMsg::ShouldDoFlips {} => query::should_do_flips(deps, env),
+        QueryMsg::GetFlipQuote {
+            amount,
+            address,
+            integrator_bps,
+        } => query::get_flip_quote(deps, amount, address, integrator_bps),
+        QueryMsg::GetOwnership {} => query::get_ownership(deps),
+        QueryMsg::HasRole { role, address } => query::has_role(deps, role, address),
+        QueryMsg::GetRoleMembers {
+            role,
+            start_after,
+            limit,
+        } => query::get_role_members(deps, role, start_after, limit),
+        QueryMsg::GetPendingChanges { start_after, limit } => {
+            query::get_pending_changes(deps, start_after, limit)
+        }
+        QueryMsg::GetMultisig {} => query::get_multisig(deps),
+        QueryMsg::ListMultisigMembers { start_after, limit } => {
+            query::list_multisig_members(deps, start_after, limit)
+        }
+        QueryMsg::GetProposal { id } => query::get_proposal(deps, env, id),
+        QueryMsg::ListProposals { start_after, limit } => {
+            query::list_proposals(deps, env, start_after, limit)
+        }
+        QueryMsg::ListVotes {
+            id,
+            start_after,
+            limit,
+        } => query::list_votes(deps, id, start_after, limit),
+        QueryMsg::GetHouseLedger { denom } => query::get_house_ledger(deps, denom),
+        QueryMsg::GetRefillStatus {} => query::get_refill_status(deps, env),
+        QueryMsg::GetSolvency { denom } => query::get_solvency(deps, env, denom),
+        QueryMsg::GetEffectiveLimits { denom } => query::get_effective_limits(deps, env, denom),
         QueryMsg::DryDistribution {} => query::dry_distribution(deps, env),
+        QueryMsg::DryDistributionHolders { start_after, limit } => {
+            query::dry_distribution_holders(deps, env, start_after, limit)
+        }
+        QueryMsg::GetExcludedHolders { start_after, limit } => {
+            query::get_excluded_holders(deps, start_after, limit)
+        }
+        QueryMsg::GetDistributionStatus {} => query::get_distribution_status(deps),
+        QueryMsg::NextDistribution {} => query::next_distribution(deps, env),
+        QueryMsg::GetDistributions { start_after, limit } => {
+            query::get_distributions(deps, start_after, limit)
+        }
+        QueryMsg::GetHolderEarnings { address } => query::get_holder_earnings(deps, address),
+        QueryMsg::GetReferrer { address } => query::get_referrer(deps, address),
+        QueryMsg::GetReferralStats { address } => query::get_referral_stats(deps, address),
+        QueryMsg::GetReferredWallets {
+            referrer,
+            start_after,
+            limit,
+        } => query::get_referred_wallets(deps, referrer, start_after, limit),
+        QueryMsg::GetIntegratorStats { address } => query::get_integrator_stats(deps, address),
+        QueryMsg::ListIntegrators { start_after, limit } => {
+            query::list_integrators(deps, start_after, limit)
+        }
+        QueryMsg::GetVault {} => query::get_vault(deps),
+        QueryMsg::GetLpPosition { address } => query::get_lp_position(deps, address),
     }
 }
 
 mod query {
-    use cosmwasm_std::{to_binary, Binary, Decimal, Deps, Env, StdError, StdResult, Uint128};
+    use cosmwasm_std::{to_binary, Addr, Binary, Deps, Env, Order, StdError, StdResult, Uint128};
+    use cw_storage_plus::Bound;
 
     use crate::{
-        msg::DryDistributionResponse,
-        state::{CONFIG, FEES, FLIPS, SCORES, TODO_FLIPS},
-        sudo::{calculate_fees_to_pay, get_holders_list, verify_contract_balance},
-        types::FeesToPay,
+        error::ContractError,
+        helpers,
+        msg::{
+            DryDistributionResponse, IntegratorStatsResponse, LpPositionResponse,
+            OwnershipResponse, ReferralStatsResponse, SolvencyResponse, VaultResponse,
+            VoteResponse,
+        },
+        refill, schedule,
+        state::{
+            get_liabilities, CONFIG, DISTRIBUTIONS, DISTRIBUTION_RUN, EXCLUDED_HOLDERS, FEES,
+            FLIPS, HOLDER_EARNINGS, HOUSE_LEDGER, INTEGRATOR_STATS, LP_POSITIONS, MULTISIG,
+            MULTISIG_MEMBERS, PENDING_ADMIN, PENDING_CHANGES, PROPOSALS, REFERRAL_STATS,
+            REFERRED_WALLETS, REFERRERS, ROLES, SCORES, TODO_FLIPS, VAULT, VOTES,
+        },
+        sudo::calculate_distribution,
+        types::{
+            Distribution, DistributionRecord, HolderPayout, IntegratorStats, PendingChange,
+            Proposal, Role,
+        },
     };
 
+    /// Default amount of items we return in paginated queries
+    const DEFAULT_LIMIT: u32 = 10;
+    /// Max amount of items we return in paginated queries
+    const MAX_LIMIT: u32 = 30;
+
     pub fn get_fees(deps: Deps) -> StdResult<Binary> {
         to_binary(&FEES.load(deps.storage)?)
     }
@@ -392,6 +712,198 @@ mod query {
         to_binary(&res)
     }
 
+    pub fn get_flip_quote(
+        deps: Deps,
+        amount: Uint128,
+        address: Option<String>,
+        integrator_bps: Option<u64>,
+    ) -> StdResult<Binary> {
+        let config = CONFIG.load(deps.storage)?;
+        let address = address
+            .map(|addr| deps.api.addr_validate(&addr))
+            .transpose()?;
+
+        let quote = helpers::get_flip_quote(
+            deps,
+            &config,
+            address.as_ref(),
+            amount,
+            integrator_bps.unwrap_or_default(),
+        )
+        .map_err(|x| StdError::generic_err(x.to_string()))?;
+        to_binary(&quote)
+    }
+
+    pub fn get_ownership(deps: Deps) -> StdResult<Binary> {
+        let config = CONFIG.load(deps.storage)?;
+        to_binary(&OwnershipResponse {
+            admin: config.admin,
+            pending_admin: PENDING_ADMIN.may_load(deps.storage)?,
+        })
+    }
+
+    pub fn has_role(deps: Deps, role: Role, address: String) -> StdResult<Binary> {
+        let config = CONFIG.load(deps.storage)?;
+        let address = deps.api.addr_validate(&address)?;
+        to_binary(&helpers::has_role(deps, &config, &address, role)?)
+    }
+
+    pub fn get_role_members(
+        deps: Deps,
+        role: Role,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<Binary> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start_after = start_after
+            .map(|addr| deps.api.addr_validate(&addr))
+            .transpose()?;
+        let start = start_after.as_ref().map(Bound::exclusive);
+
+        let members = ROLES
+            .prefix(role.as_str())
+            .keys(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<Vec<Addr>>>()?;
+        to_binary(&members)
+    }
+
+    pub fn get_pending_changes(
+        deps: Deps,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<Binary> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start = start_after.map(Bound::exclusive);
+
+        let changes = PENDING_CHANGES
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| item.map(|(_, change)| change))
+            .collect::<StdResult<Vec<PendingChange>>>()?;
+        to_binary(&changes)
+    }
+
+    pub fn get_multisig(deps: Deps) -> StdResult<Binary> {
+        to_binary(&MULTISIG.may_load(deps.storage)?)
+    }
+
+    pub fn list_multisig_members(
+        deps: Deps,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<Binary> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start_after = start_after
+            .map(|addr| deps.api.addr_validate(&addr))
+            .transpose()?;
+        let start = start_after.as_ref().map(Bound::exclusive);
+
+        let members = MULTISIG_MEMBERS
+            .keys(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<Vec<Addr>>>()?;
+        to_binary(&members)
+    }
+
+    /// Stored status is not updated when a proposal expires, so we return the current one
+    fn with_current_status(env: &Env, mut proposal: Proposal) -> Proposal {
+        proposal.status = proposal.current_status(&env.block);
+        proposal
+    }
+
+    pub fn get_proposal(deps: Deps, env: Env, id: u64) -> StdResult<Binary> {
+        let proposal = PROPOSALS.load(deps.storage, id)?;
+        to_binary(&with_current_status(&env, proposal))
+    }
+
+    pub fn list_proposals(
+        deps: Deps,
+        env: Env,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<Binary> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start = start_after.map(Bound::exclusive);
+
+        let proposals = PROPOSALS
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| item.map(|(_, proposal)| with_current_status(&env, proposal)))
+            .collect::<StdResult<Vec<Proposal>>>()?;
+        to_binary(&proposals)
+    }
+
+    pub fn list_votes(
+        deps: Deps,
+        id: u64,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<Binary> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start_after = start_after
+            .map(|addr| deps.api.addr_validate(&addr))
+            .transpose()?;
+        let start = start_after.as_ref().map(Bound::exclusive);
+
+        let votes = VOTES
+            .prefix(id)
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| item.map(|(voter, approve)| VoteResponse { voter, approve }))
+            .collect::<StdResult<Vec<VoteResponse>>>()?;
+        to_binary(&votes)
+    }
+
+    pub fn get_house_ledger(deps: Deps, denom: String) -> StdResult<Binary> {
+        to_binary(
+            &HOUSE_LEDGER
+                .may_load(deps.storage, &denom)?
+                .unwrap_or_default(),
+        )
+    }
+
+    pub fn get_refill_status(deps: Deps, env: Env) -> StdResult<Binary> {
+        let config = CONFIG.load(deps.storage)?;
+        let status = refill::get_refill_status(deps, &env, &config)
+            .map_err(|x| StdError::generic_err(x.to_string()))?;
+        to_binary(&status)
+    }
+
+    pub fn get_solvency(deps: Deps, env: Env, denom: String) -> StdResult<Binary> {
+        let balance = deps
+            .querier
+            .query_balance(&env.contract.address, denom.clone())?
+            .amount;
+        let fees = FEES.load(deps.storage)?;
+        let liabilities = get_liabilities(deps.storage, &denom)?;
+
+        to_binary(&SolvencyResponse {
+            is_solvent: helpers::ensure_solvent(balance, fees, liabilities).is_ok(),
+            denom,
+            balance,
+            fees,
+            liabilities,
+        })
+    }
+
+    pub fn get_effective_limits(deps: Deps, env: Env, denom: String) -> StdResult<Binary> {
+        let config = CONFIG.load(deps.storage)?;
+        if !config.denoms.contains(&denom) {
+            return Err(StdError::generic_err(
+                ContractError::WrongDenom { denom }.to_string(),
+            ));
+        }
+
+        let available_bank = helpers::get_available_bank(deps, &env, &denom)
+            .map_err(|x| StdError::generic_err(x.to_string()))?;
+        to_binary(&helpers::get_effective_limits(
+            &config,
+            denom,
+            available_bank,
+        ))
+    }
+
     pub fn get_last_5(deps: Deps) -> StdResult<Binary> {
         let flips = FLIPS.load(deps.storage)?;
 
@@ -400,70 +912,233 @@ mod query {
 
     pub fn dry_distribution(deps: Deps, env: Env) -> StdResult<Binary> {
         let config = CONFIG.load(deps.storage)?;
-        let total_fees = FEES.load(deps.storage).unwrap_or_default();
-
-        // TODO: Currently we only accept 1 denom
-        let denom = config.denoms[0].clone();
-        let (
-            sg721_addr,
-            FeesToPay {
-                team: team_fees_to_send,
-                holders: holders_fees_to_send,
-                reserve: reserve_fees,
-            },
-        ) = calculate_fees_to_pay(&config, total_fees)
+
+        let Distribution {
+            total_fees,
+            recipients,
+            holders_total_fee,
+            total_shares,
+            fees_per_token,
+            holders_payouts,
+            pay_to_holders,
+            holders_pending,
+            rounding_remainder,
+            released_pending,
+            excluded_holders,
+            ..
+        } = calculate_distribution(deps, env, &config, 0)
             .map_err(|x| StdError::generic_err(x.to_string()))?;
 
-        let reserve_fees_to_send = verify_contract_balance(
-            deps,
-            env,
-            denom,
+        to_binary(&DryDistributionResponse {
             total_fees,
-            reserve_fees,
-            config.bank_limit,
+            recipients,
+            holders_total_fee,
+            holders_total_shares: total_shares,
+            fees_per_token,
+            pay_to_holders,
+            number_of_holders: holders_payouts.len() as u64,
+            number_of_excluded_holders: excluded_holders,
+            holders_pending,
+            holders_released: released_pending,
+            rounding_remainder,
+        })
+    }
+
+    pub fn dry_distribution_holders(
+        deps: Deps,
+        env: Env,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<Binary> {
+        let config = CONFIG.load(deps.storage)?;
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+        let Distribution {
+            holders_payouts, ..
+        } = calculate_distribution(deps, env, &config, 0)
+            .map_err(|x| StdError::generic_err(x.to_string()))?;
+
+        // Payouts are sorted by address
+        let payouts: Vec<HolderPayout> = holders_payouts
+            .into_iter()
+            .filter(|payout| match &start_after {
+                Some(start_after) => payout.address > *start_after,
+                None => true,
+            })
+            .take(limit)
+            .collect();
+        to_binary(&payouts)
+    }
+
+    pub fn get_excluded_holders(
+        deps: Deps,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<Binary> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start_after = start_after
+            .map(|addr| deps.api.addr_validate(&addr))
+            .transpose()?;
+        let start = start_after.as_ref().map(Bound::exclusive);
+
+        let excluded = EXCLUDED_HOLDERS
+            .keys(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<Vec<Addr>>>()?;
+        to_binary(&excluded)
+    }
+
+    pub fn get_distribution_status(deps: Deps) -> StdResult<Binary> {
+        to_binary(&DISTRIBUTION_RUN.may_load(deps.storage)?)
+    }
+
+    pub fn next_distribution(deps: Deps, env: Env) -> StdResult<Binary> {
+        let config = CONFIG.load(deps.storage)?;
+        let next = schedule::get_next_distribution(deps, &env, &config)
+            .map_err(|x| StdError::generic_err(x.to_string()))?;
+        to_binary(&next)
+    }
+
+    pub fn get_distributions(
+        deps: Deps,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<Binary> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start = start_after.map(Bound::exclusive);
+
+        let distributions = DISTRIBUTIONS
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| item.map(|(_, record)| record))
+            .collect::<StdResult<Vec<DistributionRecord>>>()?;
+        to_binary(&distributions)
+    }
+
+    pub fn get_holder_earnings(deps: Deps, address: String) -> StdResult<Binary> {
+        let address = deps.api.addr_validate(&address)?;
+        to_binary(
+            &HOLDER_EARNINGS
+                .may_load(deps.storage, &address)?
+                .unwrap_or_default(),
         )
-        .map_err(|x| StdError::generic_err(x.to_string()))?;
+    }
+
+    pub fn get_referrer(deps: Deps, address: String) -> StdResult<Binary> {
+        let address = deps.api.addr_validate(&address)?;
+        to_binary(&REFERRERS.may_load(deps.storage, &address)?)
+    }
+
+    pub fn get_referral_stats(deps: Deps, address: String) -> StdResult<Binary> {
+        let address = deps.api.addr_validate(&address)?;
+        let stats = REFERRAL_STATS
+            .may_load(deps.storage, &address)?
+            .unwrap_or_default();
+        let claimable = stats
+            .claimable()
+            .map_err(|x| StdError::generic_err(x.to_string()))?;
 
-        let mut paid_to_holders = Uint128::zero();
-        let mut total_shares = Decimal::zero();
-        let mut fees_per_token = Decimal::zero();
-        let mut number_of_holders: u64 = 0;
+        to_binary(&ReferralStatsResponse {
+            referred_wallets: stats.referred_wallets,
+            volume: stats.volume,
+            earned: stats.earned,
+            claimed: stats.claimed,
+            claimable,
+        })
+    }
 
-        if !holders_fees_to_send.is_zero() {
-            let (calculated_total_shares, holders_list) = get_holders_list(deps, sg721_addr)
-                .map_err(|x| StdError::generic_err(x.to_string()))?;
-            total_shares = calculated_total_shares;
-            number_of_holders = holders_list.len() as u64;
+    pub fn get_referred_wallets(
+        deps: Deps,
+        referrer: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<Binary> {
+        let referrer = deps.api.addr_validate(&referrer)?;
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start_after = start_after
+            .map(|addr| deps.api.addr_validate(&addr))
+            .transpose()?;
+        let start = start_after.as_ref().map(Bound::exclusive);
+
+        let wallets = REFERRED_WALLETS
+            .prefix(&referrer)
+            .keys(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<Vec<Addr>>>()?;
+        to_binary(&wallets)
+    }
 
-            fees_per_token = Decimal::from_atomics(holders_fees_to_send, 0)
-                .map_err(|x| StdError::generic_err(x.to_string()))?
-                .checked_div(total_shares)
-                .map_err(|x| StdError::generic_err(x.to_string()))?;
+    fn to_integrator_stats_response(
+        address: Addr,
+        stats: IntegratorStats,
+    ) -> StdResult<IntegratorStatsResponse> {
+        let claimable = stats
+            .claimable()
+            .map_err(|x| StdError::generic_err(x.to_string()))?;
 
-            for (_, num) in holders_list {
-                let amount = fees_per_token.checked_mul(num)?.to_uint_floor();
+        Ok(IntegratorStatsResponse {
+            address,
+            flips: stats.flips,
+            volume: stats.volume,
+            earned: stats.earned,
+            claimed: stats.claimed,
+            claimable,
+        })
+    }
 
-                if !amount.is_zero() {
-                    paid_to_holders = paid_to_holders.checked_add(amount)?;
-                }
-            }
-        }
+    pub fn get_integrator_stats(deps: Deps, address: String) -> StdResult<Binary> {
+        let address = deps.api.addr_validate(&address)?;
+        let stats = INTEGRATOR_STATS
+            .may_load(deps.storage, &address)?
+            .unwrap_or_default();
 
-        to_binary(&DryDistributionResponse {
-            total_fees,
-            team_total_fee: team_fees_to_send,
-            reserve_total_fee: reserve_fees_to_send,
-            holders_total_fee: holders_fees_to_send,
-            holders_total_shares: total_shares,
-            fees_per_token,
-            pay_to_holders: paid_to_holders,
-            number_of_holders,
+        to_binary(&to_integrator_stats_response(address, stats)?)
+    }
+
+    pub fn list_integrators(
+        deps: Deps,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<Binary> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start_after = start_after
+            .map(|addr| deps.api.addr_validate(&addr))
+            .transpose()?;
+        let start = start_after.as_ref().map(Bound::exclusive);
+
+        let integrators = INTEGRATOR_STATS
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| item.and_then(|(addr, stats)| to_integrator_stats_response(addr, stats)))
+            .collect::<StdResult<Vec<IntegratorStatsResponse>>>()?;
+        to_binary(&integrators)
+    }
+
+    pub fn get_vault(deps: Deps) -> StdResult<Binary> {
+        let config = CONFIG.load(deps.storage)?;
+        let vault = VAULT.may_load(deps.storage)?.unwrap_or_default();
+        to_binary(&VaultResponse {
+            total_shares: vault.total_shares,
+            assets: vault.assets,
+            cooldown: config.vault_cooldown,
+        })
+    }
+
+    pub fn get_lp_position(deps: Deps, address: String) -> StdResult<Binary> {
+        let address = deps.api.addr_validate(&address)?;
+        let vault = VAULT.may_load(deps.storage)?.unwrap_or_default();
+        let position = LP_POSITIONS
+            .may_load(deps.storage, &address)?
+            .unwrap_or_default();
+        to_binary(&LpPositionResponse {
+            shares: position.shares,
+            value: vault.value_of(position.shares),
+            withdraw_request: position.withdraw_request,
         })
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
-    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
-    Ok(Response::default())
+pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    migrations::migrate(deps, env, msg)
 }
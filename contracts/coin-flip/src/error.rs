@@ -2,8 +2,9 @@ use std::convert::Infallible;
 
 use cosmwasm_std::{
     CheckedFromRatioError, DecimalRangeExceeded, DivideByZeroError, OverflowError, StdError,
+    Timestamp, Uint128,
 };
-use cw_utils::PaymentError;
+use cw_utils::{Expiration, PaymentError};
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq)]
@@ -32,9 +33,174 @@ pub enum ContractError {
     #[error("Unauthorized")]
     Unauthorized,
 
+    #[error("Fee recipients must add up to 10000 bps, got {sum}")]
+    InvalidFeesSplit { sum: u64 },
+
+    #[error("Fee recipient name must be unique and not empty, got \"{name}\"")]
+    InvalidFeeRecipientName { name: String },
+
+    #[error("There can only be 1 holders and 1 reserve fee recipient")]
+    DuplicateFeeRecipientKind,
+
+    #[error("Remainder recipient must be a fee recipient that is not holders, got \"{name}\"")]
+    InvalidRemainderRecipient { name: String },
+
+    #[error("Flip fee cannot be more then 10000 bps, got {flip_bps}")]
+    InvalidFlipFee { flip_bps: u64 },
+
+    #[error("Flip fee tiers must be sorted by min amount, without duplicates")]
+    InvalidFlipFeeTiers,
+
+    #[error("Holder discount cannot be more then 10000 bps, got {discount_bps}")]
+    InvalidHolderDiscount { discount_bps: u64 },
+
+    #[error("Referral fee cannot be more then 10000 bps, got {referral_bps}")]
+    InvalidReferralFee { referral_bps: u64 },
+
+    #[error("Integrator fee {bps} bps is above the max of {max_bps} bps")]
+    IntegratorFeeTooHigh { bps: u64, max_bps: u64 },
+
+    #[error("There are no integrator fees to claim.")]
+    NoIntegratorFees,
+
+    #[error("You cannot refer yourself.")]
+    SelfReferral,
+
+    #[error("You already registered a referrer.")]
+    ReferrerAlreadySet,
+
+    #[error("There are no referral rewards to claim.")]
+    NoReferralRewards,
+
+    #[error("Distribution interval cannot be 0")]
+    InvalidDistributionInterval,
+
+    #[error("Caller tip cannot be more then 10000 bps, got {caller_tip_bps}")]
+    InvalidCallerTip { caller_tip_bps: u64 },
+
+    #[error("Distribution is not scheduled, only admin can distribute.")]
+    DistributionNotScheduled,
+
+    #[error("Distribution is not due yet.")]
+    DistributionNotDue,
+
+    #[error("Not enough fees to distribute, min is {min_fees}")]
+    NotEnoughFeesToDistribute { min_fees: Uint128 },
+
+    #[error("Max bet share of the bank must be between 1 and 10000 bps, got {bps}")]
+    InvalidMaxBetBankBps { bps: u64 },
+
+    #[error("There is no pending admin.")]
+    NoPendingAdmin,
+
+    #[error("Admin proposal expired.")]
+    AdminProposalExpired,
+
+    #[error("Can't migrate from another contract = {name}")]
+    WrongContract { name: String },
+
+    #[error("Can't migrate from {stored} to older version {new}")]
+    CannotDowngrade { stored: String, new: String },
+
+    #[error("Invalid contract version = {version}")]
+    InvalidVersion { version: String },
+
+    #[error("We need at least 1 denom.")]
+    NoDenoms,
+
+    #[error("Denom {denom} is still used by pending flips, fees or LPs")]
+    DenomInUse { denom: String },
+
+    #[error("Multisig is not enabled.")]
+    MultisigNotEnabled,
+
+    #[error("Threshold must be between 1 and the members count = {members}")]
+    InvalidThreshold { members: u64 },
+
+    #[error("There is no proposal with id = {id}")]
+    NoProposal { id: u64 },
+
+    #[error("Proposal is not open for votes.")]
+    ProposalNotOpen,
+
+    #[error("You already voted on this proposal.")]
+    AlreadyVoted,
+
+    #[error("Proposal didn't pass, it can't be executed.")]
+    ProposalNotPassed,
+
+    #[error("Proposals can't send funds, multisig members can fund the bank directly.")]
+    ProposalWithFunds,
+
+    #[error("Config change can't be effective before {min_effective_at}")]
+    ChangeTooSoon { min_effective_at: Timestamp },
+
+    #[error("Config change is not effective until {effective_at}")]
+    ChangeNotEffective { effective_at: Timestamp },
+
+    #[error("There is no pending config change with id = {id}")]
+    NoPendingChange { id: u64 },
+
+    #[error("Cannot withdraw more then the free bank = {available}")]
+    WithdrawAboveAvailable { available: Uint128 },
+
+    #[error("Treasury is not set.")]
+    TreasuryNotSet,
+
+    #[error("Refill period cannot be 0")]
+    InvalidRefillPeriod,
+
+    #[error("Bank is not under the bank limit, nothing to refill.")]
+    BankAboveLimit,
+
+    #[error("Refill cap of this period is reached, next period starts at {period_ends}")]
+    RefillCapReached { period_ends: Expiration },
+
+    #[error("Unknown reply id: {id}")]
+    UnknownReplyId { id: u64 },
+
+    #[error("Vault cooldown cannot be 0")]
+    InvalidVaultCooldown,
+
+    #[error("Min bet limit {min} is over the max bet limit {max}")]
+    InvalidBetLimits { min: Uint128, max: Uint128 },
+
+    #[error("Flips per block limit cannot be 0")]
+    InvalidFlipsPerBlockLimit,
+
+    #[error("Timelock delay cannot be 0")]
+    InvalidTimelockDelay,
+
+    #[error("Deposit is too small to get any vault shares")]
+    VaultDepositTooSmall,
+
+    #[error("Vault lost all its assets, deposits are disabled")]
+    VaultInsolvent,
+
+    #[error("You don't have enough vault shares, you have {shares}")]
+    NotEnoughVaultShares { shares: Uint128 },
+
+    #[error("You already requested a withdraw.")]
+    WithdrawAlreadyRequested,
+
+    #[error("You didn't request a withdraw.")]
+    NoWithdrawRequest,
+
+    #[error("Withdraw is still in cooldown, ready at {release_at}")]
+    WithdrawNotReady { release_at: Expiration },
+
+    #[error("Withdraw request expired at {expires_at}, cancel it and request again")]
+    WithdrawRequestExpired { expires_at: Expiration },
+
     #[error("Fees to be paid is 0")]
     NoFeesToPay,
 
+    #[error("A distribution is already in progress, please finish it first.")]
+    DistributionInProgress,
+
+    #[error("There is no distribution in progress.")]
+    NoDistributionInProgress,
+
     #[error("Fees amount to distribute is more then the contract balance")]
     NotEnoughFundsToPayFees,
 
@@ -56,6 +222,12 @@ pub enum ContractError {
     #[error("NFT contract is not set.")]
     Sg721NotSet,
 
+    #[error("Token id is not a valid number = {token_id}")]
+    InvalidTokenId { token_id: String },
+
+    #[error("No staking contract is set.")]
+    NoStakingContract,
+
     #[error("Contract doesn't have enough funds to pay for the bet")]
     ContractMissingFunds,
 
@@ -67,4 +239,7 @@ pub enum ContractError {
 
     #[error("Operation is paused at this moment! Please try again later.")]
     Paused,
+
+    #[error("There are no pending flips.")]
+    NoPendingFlips,
 }
@@ -1,14 +1,66 @@
-use cosmwasm_std::{ensure_eq, Decimal, MessageInfo, Uint128};
+use cosmwasm_std::{ensure, ensure_eq, Addr, Decimal, Deps, Env, MessageInfo, StdResult, Uint128};
+use cw_utils::must_pay;
 
-use crate::{error::ContractError, types::Config};
+use crate::{
+    error::ContractError,
+    msg::{EffectiveLimitsResponse, FlipQuoteResponse, StakingQueryMsg},
+    state::{get_liabilities, FEES, MULTISIG, ROLES},
+    types::{Config, PauseScope, Role},
+};
+
+/// 100% in bps
+pub const MAX_BPS: u64 = 10_000;
 
 pub fn ensure_admin(config: &Config, info: &MessageInfo) -> Result<(), ContractError> {
-    ensure_eq!(config.admin, info.sender, ContractError::Unauthorized);
+    ensure!(
+        config.admin.as_ref() == Some(&info.sender),
+        ContractError::Unauthorized
+    );
+    Ok(())
+}
+
+/// The admin and super admins have every role.
+///
+/// Granted roles are ignored while a multisig is the admin, so only its proposals can act.
+pub fn has_role(deps: Deps, config: &Config, addr: &Addr, role: Role) -> StdResult<bool> {
+    if config.admin.as_ref() == Some(addr) {
+        return Ok(true);
+    }
+    if MULTISIG.may_load(deps.storage)?.is_some() {
+        return Ok(false);
+    }
+    Ok(ROLES.has(deps.storage, (role.as_str(), addr))
+        || ROLES.has(deps.storage, (Role::SuperAdmin.as_str(), addr)))
+}
+
+pub fn ensure_role(
+    deps: Deps,
+    config: &Config,
+    info: &MessageInfo,
+    role: Role,
+) -> Result<(), ContractError> {
+    ensure!(
+        has_role(deps, config, &info.sender, role)?,
+        ContractError::Unauthorized
+    );
     Ok(())
 }
 
-pub fn ensure_not_paused(config: &Config) -> Result<(), ContractError> {
-    ensure_eq!(config.is_paused, false, ContractError::Paused);
+/// Super admins can grant and revoke every role, except super admin that only the admin can
+pub fn ensure_role_manager(
+    deps: Deps,
+    config: &Config,
+    info: &MessageInfo,
+    role: Role,
+) -> Result<(), ContractError> {
+    match role {
+        Role::SuperAdmin => ensure_admin(config, info),
+        _ => ensure_role(deps, config, info, Role::SuperAdmin),
+    }
+}
+
+pub fn ensure_not_paused(config: &Config, scope: PauseScope) -> Result<(), ContractError> {
+    ensure!(!config.paused.is_paused(scope), ContractError::Paused);
     Ok(())
 }
 
@@ -24,27 +76,244 @@ pub fn calc_flip_fee(amount: Decimal, fee: Decimal) -> Result<Uint128, ContractE
     Ok(f)
 }
 
-/// Function to ensure flipper paid the right amount with fees
+/// Function to ensure flipper paid the right amount with fees, returns the flip quote
 pub fn ensure_correct_funds(
-    funds: Uint128,
+    deps: Deps,
+    config: &Config,
+    info: &MessageInfo,
+    denom: &str,
+    amount: Uint128,
+    integrator_bps: u64,
+) -> Result<FlipQuoteResponse, ContractError> {
+    let quote = get_flip_quote(deps, config, Some(&info.sender), amount, integrator_bps)?;
+    let paid_amount = must_pay(info, denom)?;
+    ensure_eq!(quote.total, paid_amount, ContractError::WrongPaidAmount);
+    Ok(quote)
+}
+
+/// Check if the address holds (or staked) an NFT of our collection
+pub fn is_holder(deps: Deps, config: &Config, address: &Addr) -> Result<bool, ContractError> {
+    let Some(sg721_addr) = config.sg721_addr.clone() else {
+        return Ok(false);
+    };
+
+    let res: cw721::TokensResponse = deps.querier.query_wasm_smart(
+        sg721_addr,
+        &cw721::Cw721QueryMsg::Tokens {
+            owner: address.to_string(),
+            start_after: None,
+            limit: Some(1),
+        },
+    )?;
+    if !res.tokens.is_empty() {
+        return Ok(true);
+    }
+
+    if let Some(staking_addr) = config.staking_addr.clone() {
+        let staked: Vec<String> = deps.querier.query_wasm_smart(
+            staking_addr,
+            &StakingQueryMsg::StakedNfts {
+                address: address.to_string(),
+                start_after: None,
+                limit: Some(1),
+            },
+        )?;
+        return Ok(!staked.is_empty());
+    }
+    Ok(false)
+}
+
+/// Get the flip fee we charge for this bet.
+///
+/// Used by both `StartFlip` and the quote query, so frontends know exactly how much to send.
+pub fn get_flip_quote(
+    deps: Deps,
+    config: &Config,
+    address: Option<&Addr>,
     amount: Uint128,
-    fee_bps: u64,
-) -> Result<Uint128, ContractError> {
-    let fee = bps_to_decimal(fee_bps);
-    let fee_to_pay = calc_flip_fee(Decimal::from_atomics(amount, 0)?, fee)?;
-    let total_amount = amount.checked_add(fee_to_pay).unwrap();
-    if funds != total_amount {
-        return Err(ContractError::WrongPaidAmount {});
+    integrator_bps: u64,
+) -> Result<FlipQuoteResponse, ContractError> {
+    ensure!(
+        integrator_bps <= config.max_integrator_bps,
+        ContractError::IntegratorFeeTooHigh {
+            bps: integrator_bps,
+            max_bps: config.max_integrator_bps
+        }
+    );
+
+    // Holding is only checked when there is a discount, and a failing NFT query
+    // only costs the discount, so flips never depend on the NFT contracts.
+    let is_holder = match address {
+        Some(address) if config.fees.holder_discount_bps > 0 => {
+            is_holder(deps, config, address).unwrap_or(false)
+        }
+        _ => false,
+    };
+    let fee_bps = config.fees.flip_fee_bps(amount, is_holder);
+    let fee = calc_flip_fee(Decimal::from_atomics(amount, 0)?, bps_to_decimal(fee_bps))?;
+    let integrator_fee = calc_flip_fee(
+        Decimal::from_atomics(amount, 0)?,
+        bps_to_decimal(integrator_bps),
+    )?;
+
+    Ok(FlipQuoteResponse {
+        amount,
+        is_holder,
+        fee_bps,
+        fee,
+        integrator_fee,
+        total: amount.checked_add(fee)?.checked_add(integrator_fee)?,
+    })
+}
+
+/// Bank we can still risk, balance without fees and what we owe to pending flips
+pub fn get_available_bank(deps: Deps, env: &Env, denom: &str) -> Result<Uint128, ContractError> {
+    let balance = deps.querier.query_balance(&env.contract.address, denom)?;
+    let fees = FEES.load(deps.storage)?;
+    let liabilities = get_liabilities(deps.storage, denom)?;
+
+    Ok(balance
+        .amount
+        .saturating_sub(fees)
+        .saturating_sub(liabilities))
+}
+
+/// Make sure the bank (balance without fees) can pay all the liabilities
+pub fn ensure_solvent(
+    balance: Uint128,
+    fees: Uint128,
+    liabilities: Uint128,
+) -> Result<(), ContractError> {
+    let bank = balance
+        .checked_sub(fees)
+        .map_err(|_| ContractError::ContractMissingFunds)?;
+    ensure!(bank >= liabilities, ContractError::ContractMissingFunds);
+    Ok(())
+}
+
+/// Get the bet limits for the given available bank.
+///
+/// If `max_bet_bank_bps` is set, max bet is that share of the bank, capped by the static limit.
+pub fn get_effective_limits(
+    config: &Config,
+    denom: String,
+    available_bank: Uint128,
+) -> EffectiveLimitsResponse {
+    let max_bet = match config.max_bet_bank_bps {
+        Some(bps) => config
+            .max_bet_limit
+            .min(available_bank.multiply_ratio(bps, MAX_BPS)),
+        None => config.max_bet_limit,
+    };
+
+    EffectiveLimitsResponse {
+        denom,
+        min_bet: config.min_bet_limit,
+        max_bet,
+        available_bank,
+        max_bet_bank_bps: config.max_bet_bank_bps,
     }
-    Ok(fee_to_pay)
 }
 
 pub fn bps_to_decimal(bps: u64) -> Decimal {
     Decimal::percent(bps) / Uint128::from(100u128)
 }
 
-#[test]
-fn test() {
-    let res = ensure_correct_funds(Uint128::new(103_500), Uint128::new(100_000), 350).unwrap();
-    assert_eq!(res, Uint128::new(3_500));
+#[cfg(test)]
+mod test {
+    use cosmwasm_std::{
+        coins,
+        testing::{mock_dependencies, mock_info},
+        Uint128,
+    };
+
+    use crate::{
+        error::ContractError,
+        types::{Config, FeeRecipient, FeeRecipientKind, Fees, FlipFeeTier, PauseScopes},
+    };
+
+    use super::ensure_correct_funds;
+
+    fn test_config() -> Config {
+        Config {
+            admin: None,
+            denoms: vec!["ustars".to_string()],
+            bank_limit: Uint128::zero(),
+            min_bet_limit: Uint128::zero(),
+            max_bet_limit: Uint128::MAX,
+            max_bet_bank_bps: None,
+            flips_per_block_limit: 10,
+            fees: Fees {
+                recipients: vec![FeeRecipient {
+                    name: "team".to_string(),
+                    kind: FeeRecipientKind::Wallet {
+                        address: "team".to_string(),
+                    },
+                    bps: 10000,
+                }],
+                flip_bps: 350,
+                flip_fee_tiers: vec![FlipFeeTier {
+                    min_amount: Uint128::new(1_000_000),
+                    flip_bps: 200,
+                }],
+                // Holder discount is not checked without a collection
+                holder_discount_bps: 5000,
+                referral_bps: 0,
+                remainder_recipient: "team".to_string(),
+            },
+            sg721_addr: None,
+            staking_addr: None,
+            min_holder_payout: Uint128::zero(),
+            max_integrator_bps: 100,
+            distribution_schedule: None,
+            vault_cooldown: cw_utils::Duration::Time(1),
+            treasury: None,
+            timelock_delay: 1,
+            paused: PauseScopes::default(),
+        }
+    }
+
+    #[test]
+    fn test() {
+        let deps = mock_dependencies();
+        let config = test_config();
+
+        let info = mock_info("flipper", &coins(103_500, "ustars"));
+        let quote = ensure_correct_funds(
+            deps.as_ref(),
+            &config,
+            &info,
+            "ustars",
+            Uint128::new(100_000),
+            0,
+        )
+        .unwrap();
+        assert_eq!(quote.fee, Uint128::new(3_500));
+
+        // Fee tier and integrator fee are applied
+        let info = mock_info("flipper", &coins(1_030_000, "ustars"));
+        let quote = ensure_correct_funds(
+            deps.as_ref(),
+            &config,
+            &info,
+            "ustars",
+            Uint128::new(1_000_000),
+            100,
+        )
+        .unwrap();
+        assert_eq!(quote.fee, Uint128::new(20_000));
+        assert_eq!(quote.integrator_fee, Uint128::new(10_000));
+
+        let info = mock_info("flipper", &coins(103_499, "ustars"));
+        let err = ensure_correct_funds(
+            deps.as_ref(),
+            &config,
+            &info,
+            "ustars",
+            Uint128::new(100_000),
+            0,
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::WrongPaidAmount);
+    }
 }
@@ -0,0 +1,64 @@
+use cosmwasm_std::{coins, ensure, Addr, BankMsg, DepsMut, MessageInfo, Storage, Uint128};
+use sg_std::Response;
+
+use crate::error::ContractError;
+use crate::state::{FEES, INTEGRATOR_CLAIMABLE_TOTAL, INTEGRATOR_STATS};
+use crate::types::Config;
+
+/// Credit the integrator with the fee it charged on a flip.
+///
+/// The fee stays in FEES until claimed, so it is never part of a distribution.
+pub fn credit_integrator(
+    storage: &mut dyn Storage,
+    integrator: &Addr,
+    amount: Uint128,
+    integrator_fee: Uint128,
+) -> Result<(), ContractError> {
+    let mut stats = INTEGRATOR_STATS
+        .may_load(storage, integrator)?
+        .unwrap_or_default();
+    stats.flips += 1;
+    stats.volume = stats.volume.checked_add(amount)?;
+    stats.earned = stats.earned.checked_add(integrator_fee)?;
+    INTEGRATOR_STATS.save(storage, integrator, &stats)?;
+
+    let claimable_total = INTEGRATOR_CLAIMABLE_TOTAL
+        .may_load(storage)?
+        .unwrap_or_default()
+        .checked_add(integrator_fee)?;
+    INTEGRATOR_CLAIMABLE_TOTAL.save(storage, &claimable_total)?;
+    Ok(())
+}
+
+/// Send the sender all his unclaimed integrator fees
+pub fn claim_integrator_fees(
+    deps: DepsMut,
+    info: MessageInfo,
+    config: &Config,
+) -> Result<Response, ContractError> {
+    let mut stats = INTEGRATOR_STATS
+        .may_load(deps.storage, &info.sender)?
+        .unwrap_or_default();
+    let claimable = stats.claimable()?;
+    ensure!(!claimable.is_zero(), ContractError::NoIntegratorFees);
+
+    stats.claimed = stats.earned;
+    INTEGRATOR_STATS.save(deps.storage, &info.sender, &stats)?;
+
+    let claimable_total = INTEGRATOR_CLAIMABLE_TOTAL
+        .load(deps.storage)?
+        .checked_sub(claimable)?;
+    INTEGRATOR_CLAIMABLE_TOTAL.save(deps.storage, &claimable_total)?;
+
+    let fees = FEES.load(deps.storage)?.checked_sub(claimable)?;
+    FEES.save(deps.storage, &fees)?;
+
+    // TODO: Currently we only accept 1 denom
+    Ok(Response::default()
+        .add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: coins(claimable.into(), config.denoms[0].clone()),
+        })
+        .add_attribute("method", "claim_integrator_fees")
+        .add_attribute("claimed", claimable))
+}
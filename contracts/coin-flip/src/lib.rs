@@ -1,10 +1,20 @@
 pub mod contract;
 pub mod error;
 pub mod helpers;
+pub mod integrator;
+pub mod migrations;
 pub mod msg;
+pub mod multisig;
+pub mod ownership;
+pub mod referral;
+pub mod refill;
+pub mod schedule;
+pub mod staking;
 pub mod state;
 pub mod sudo;
+pub mod timelock;
 pub mod types;
+pub mod vault;
 // pub mod test;
 
 #[cfg(test)]
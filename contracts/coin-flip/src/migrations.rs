@@ -0,0 +1,190 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{ensure, Addr, DepsMut, Env, Uint128};
+use cw2::{get_contract_version, set_contract_version};
+use cw_storage_plus::Item;
+use semver::Version;
+use sg_std::Response;
+
+use crate::contract::{
+    CONTRACT_NAME, CONTRACT_VERSION, DEFAULT_TIMELOCK_DELAY, DEFAULT_VAULT_COOLDOWN,
+};
+use crate::error::ContractError;
+use crate::helpers::MAX_BPS;
+use crate::msg::MigrateMsg;
+use crate::state::{add_liability, CONFIG, LIABILITIES, TODO_FLIPS};
+use crate::sudo;
+use crate::types::{Config, FeeRecipient, FeeRecipientKind, Fees, PauseScopes};
+
+/// State transform that brings the state to the layout of `version`
+type MigrationStep = fn(DepsMut, &Env) -> Result<(), ContractError>;
+
+/// Migration steps sorted by version, we run every step newer than the stored version
+const MIGRATIONS: &[(&str, MigrationStep)] = &[("0.8.0", migrate_v0_8_0)];
+
+fn parse_version(version: &str) -> Result<Version, ContractError> {
+    version.parse().map_err(|_| ContractError::InvalidVersion {
+        version: version.to_string(),
+    })
+}
+
+pub fn migrate(mut deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    let stored = get_contract_version(deps.storage)?;
+    ensure!(
+        stored.contract == CONTRACT_NAME,
+        ContractError::WrongContract {
+            name: stored.contract
+        }
+    );
+    let stored_version = parse_version(&stored.version)?;
+    let new_version = parse_version(CONTRACT_VERSION)?;
+    ensure!(
+        stored_version <= new_version,
+        ContractError::CannotDowngrade {
+            stored: stored.version,
+            new: CONTRACT_VERSION.to_string()
+        }
+    );
+
+    let mut res = Response::default()
+        .add_attribute("method", "migrate")
+        .add_attribute("from_version", stored_version.to_string())
+        .add_attribute("to_version", CONTRACT_VERSION);
+    for (version, step) in MIGRATIONS {
+        let version = parse_version(version)?;
+        if stored_version < version && version <= new_version {
+            step(deps.branch(), &env)?;
+            res = res.add_attribute("migration_step", version.to_string());
+        }
+    }
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    match msg {
+        MigrateMsg::Basic {} => Ok(res),
+        MigrateMsg::WithConfig { update } => {
+            let config = CONFIG.load(deps.storage)?;
+            let update_res = sudo::update_config(deps, config, *update)?;
+            Ok(res.add_events(update_res.events))
+        }
+    }
+}
+
+/// Config as stored up to v0.7
+#[cw_serde]
+pub(crate) struct ConfigV0_7 {
+    pub admin: String,
+    pub denoms: Vec<String>,
+    pub bank_limit: Uint128,
+    pub min_bet_limit: Uint128,
+    pub max_bet_limit: Uint128,
+    pub flips_per_block_limit: u64,
+    pub wallets: WalletsV0_7,
+    pub fees: FeesV0_7,
+    pub sg721_addr: Option<Addr>,
+    pub is_paused: bool,
+}
+
+#[cw_serde]
+pub(crate) struct WalletsV0_7 {
+    pub team: String,
+    pub reserve: String,
+}
+
+#[cw_serde]
+pub(crate) struct FeesV0_7 {
+    pub team_bps: u64,
+    pub holders_bps: u64,
+    pub reserve_bps: u64,
+    pub flip_bps: u64,
+}
+
+pub(crate) const CONFIG_V0_7: Item<ConfigV0_7> = Item::new("config");
+
+impl FeesV0_7 {
+    /// v0.7 didn't check that the split adds up to 100%, so we scale it to `MAX_BPS`.
+    /// The rounding goes to the team, and an empty split goes all to the team.
+    fn repaired_split(&self) -> (u64, u64, u64) {
+        let sum = self.team_bps as u128 + self.holders_bps as u128 + self.reserve_bps as u128;
+        if sum == 0 {
+            return (MAX_BPS, 0, 0);
+        }
+        let scale = |bps: u64| (bps as u128 * MAX_BPS as u128 / sum) as u64;
+        let holders_bps = scale(self.holders_bps);
+        let reserve_bps = scale(self.reserve_bps);
+        (
+            MAX_BPS - holders_bps - reserve_bps,
+            holders_bps,
+            reserve_bps,
+        )
+    }
+}
+
+/// Fees split between named recipients, single admin that can be renounced, and pending flips
+/// liabilities. Invalid v0.7 fees are repaired instead of failing the migration.
+fn migrate_v0_8_0(deps: DepsMut, _env: &Env) -> Result<(), ContractError> {
+    let old = CONFIG_V0_7.load(deps.storage)?;
+    let (team_bps, holders_bps, reserve_bps) = old.fees.repaired_split();
+    let fees = Fees {
+        recipients: vec![
+            FeeRecipient {
+                name: "team".to_string(),
+                kind: FeeRecipientKind::Wallet {
+                    address: old.wallets.team,
+                },
+                bps: team_bps,
+            },
+            FeeRecipient {
+                name: "holders".to_string(),
+                kind: FeeRecipientKind::Holders,
+                bps: holders_bps,
+            },
+            FeeRecipient {
+                name: "reserve".to_string(),
+                kind: FeeRecipientKind::Reserve {
+                    address: old.wallets.reserve,
+                },
+                bps: reserve_bps,
+            },
+        ],
+        flip_bps: old.fees.flip_bps.min(MAX_BPS),
+        flip_fee_tiers: vec![],
+        holder_discount_bps: 0,
+        referral_bps: 0,
+        remainder_recipient: "team".to_string(),
+    };
+    fees.validate(deps.api)?;
+
+    CONFIG.save(
+        deps.storage,
+        &Config {
+            admin: Some(deps.api.addr_validate(&old.admin)?),
+            denoms: old.denoms,
+            bank_limit: old.bank_limit,
+            min_bet_limit: old.min_bet_limit,
+            max_bet_limit: old.max_bet_limit,
+            max_bet_bank_bps: None,
+            flips_per_block_limit: old.flips_per_block_limit,
+            fees,
+            sg721_addr: old.sg721_addr,
+            staking_addr: None,
+            min_holder_payout: Uint128::zero(),
+            max_integrator_bps: 0,
+            distribution_schedule: None,
+            vault_cooldown: DEFAULT_VAULT_COOLDOWN,
+            treasury: None,
+            timelock_delay: DEFAULT_TIMELOCK_DELAY,
+            paused: PauseScopes::all(old.is_paused),
+        },
+    )?;
+
+    // Liabilities were not tracked before, build them from the pending flips
+    if LIABILITIES.is_empty(deps.storage) {
+        for todo_flip in TODO_FLIPS.load(deps.storage)? {
+            add_liability(
+                deps.storage,
+                &todo_flip.amount.denom,
+                todo_flip.amount.amount * Uint128::new(2),
+            )?;
+        }
+    }
+    Ok(())
+}
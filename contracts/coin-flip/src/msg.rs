@@ -1,26 +1,62 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Decimal, Uint128};
+use cosmwasm_std::{Addr, Coin, Decimal, Timestamp, Uint128};
+use cw_utils::{Duration, Expiration};
 
-use crate::types::{Config, Fees, Flip, FlipScore, PickTypes, Wallets};
+use crate::types::{
+    Config, ConfigChange, ConfigUpdate, DistributionRecord, DistributionRun, DistributionSchedule,
+    Fees, Flip, FlipScore, HolderPayout, HouseLedger, IntegratorFee, LastDistribution, Multisig,
+    PauseScopes, PendingAdmin, PendingChange, PickTypes, Proposal, RecipientPayout, RefillPeriod,
+    Role, Treasury, WithdrawRequest,
+};
 
 #[cw_serde]
 pub struct InstantiateMsg {
     pub admin: String,
     pub denoms: Vec<String>,
-    pub wallets: Wallets,
     pub fees: Fees,
     pub bank_limit: Option<Uint128>,
     pub min_bet_limit: Option<Uint128>,
     pub max_bet_limit: Option<Uint128>,
+    pub max_bet_bank_bps: Option<u64>,
     pub flips_per_block_limit: Option<u64>,
     pub sg721_addr: Option<String>,
+    pub staking_addr: Option<String>,
+    pub min_holder_payout: Option<Uint128>,
+    pub max_integrator_bps: Option<u64>,
+    pub distribution_schedule: Option<DistributionSchedule>,
+    pub vault_cooldown: Option<Duration>,
+    pub treasury: Option<Treasury>,
+    pub timelock_delay: Option<u64>,
 }
 
 #[cw_serde]
 pub enum ExecuteMsg {
     /// Flip msgs
     Flip(FlipExecuteMsg),
-    /// Only call-able by admin (mutlisig)
+    /// Referral program msgs
+    Referral(ReferralExecuteMsg),
+    /// Integrators (third-party frontends) msgs
+    Integrator(IntegratorExecuteMsg),
+    /// Bankroll vault (LPs) msgs
+    Vault(VaultExecuteMsg),
+    /// Built-in multisig msgs, only call-able by members (except execute)
+    Multisig(MultisigExecuteMsg),
+    /// Accept the admin role, only call-able by the proposed admin
+    AcceptAdmin {},
+    /// Distribute fees when it's due, call-able by anyone if distribution is scheduled
+    Distribute {},
+    /// Request funds from the treasury if the bank is under the bank limit, call-able by anyone.
+    /// Flips and distributions already do it, this is for when the treasury refused.
+    RefillBank {},
+    /// Apply a queued config change once its delay is over, call-able by anyone.
+    /// The proposer must still have the role of the change.
+    ExecutePending { id: u64 },
+    /// Sent by the staking contract when NFTs are staked or unstaked
+    StakeChangeHook(StakeChangedHookMsg),
+    /// Set the staked NFTs of an address from the staking contract, call-able by anyone.
+    /// Needed for NFTs staked before this contract was added as a stake hook.
+    SyncStaker { address: String },
+    /// Only call-able by admin (multisig)
     Sudo(SudoMsg),
 }
 
@@ -43,39 +79,411 @@ pub enum QueryMsg {
     /// this is to prevent sending unnecessary txs
     #[returns(bool)]
     ShouldDoFlips {},
+    /// Get the flip fee and the total amount to send for a bet
+    #[returns(FlipQuoteResponse)]
+    GetFlipQuote {
+        amount: Uint128,
+        address: Option<String>,
+        integrator_bps: Option<u64>,
+    },
+    /// Get balance, fees and what we owe to pending flips
+    #[returns(SolvencyResponse)]
+    GetSolvency { denom: String },
+    /// Get the admin and the proposed admin, if any
+    #[returns(OwnershipResponse)]
+    GetOwnership {},
+    /// Get if an address has a role (the admin has all of them).
+    /// Granted roles are ignored while a multisig is the admin.
+    #[returns(bool)]
+    HasRole { role: Role, address: String },
+    /// Get addresses that were granted a role
+    #[returns(Vec<Addr>)]
+    GetRoleMembers {
+        role: Role,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Get config changes waiting for their delay
+    #[returns(Vec<PendingChange>)]
+    GetPendingChanges {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Get the multisig threshold and voting period, None if not enabled
+    #[returns(Option<Multisig>)]
+    GetMultisig {},
+    #[returns(Vec<Addr>)]
+    ListMultisigMembers {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    #[returns(Proposal)]
+    GetProposal { id: u64 },
+    #[returns(Vec<Proposal>)]
+    ListProposals {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Get votes of a proposal
+    #[returns(Vec<VoteResponse>)]
+    ListVotes {
+        id: u64,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Get house deposits, withdrawals and flips PnL of a denom
+    #[returns(HouseLedger)]
+    GetHouseLedger { denom: String },
+    /// Get if (and how much) we can refill the bank from the treasury
+    #[returns(RefillStatusResponse)]
+    GetRefillStatus {},
+    /// Get the bet limits for the current bank
+    #[returns(EffectiveLimitsResponse)]
+    GetEffectiveLimits { denom: String },
     #[returns(DryDistributionResponse)]
     DryDistribution {},
+    /// Get how much each holder would be paid if we distribute now
+    #[returns(Vec<HolderPayout>)]
+    DryDistributionHolders {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Get addresses that are excluded from holders distribution
+    #[returns(Vec<Addr>)]
+    GetExcludedHolders {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Get the distribution that is currently in progress, if any
+    #[returns(Option<DistributionRun>)]
+    GetDistributionStatus {},
+    /// Get when the next scheduled distribution can be triggered
+    #[returns(NextDistributionResponse)]
+    NextDistribution {},
+    /// Get history of finished distributions
+    #[returns(Vec<DistributionRecord>)]
+    GetDistributions {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Get total fees an address earned as a holder
+    #[returns(Uint128)]
+    GetHolderEarnings { address: String },
+    /// Get the referrer of a wallet, if any
+    #[returns(Option<Addr>)]
+    GetReferrer { address: String },
+    /// Get referral stats of a referrer
+    #[returns(ReferralStatsResponse)]
+    GetReferralStats { address: String },
+    /// Get wallets referred by a referrer
+    #[returns(Vec<Addr>)]
+    GetReferredWallets {
+        referrer: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Get stats of an integrator
+    #[returns(IntegratorStatsResponse)]
+    GetIntegratorStats { address: String },
+    /// List integrators with their stats
+    #[returns(Vec<IntegratorStatsResponse>)]
+    ListIntegrators {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Get the bankroll vault totals
+    #[returns(VaultResponse)]
+    GetVault {},
+    /// Get the vault shares of an LP and their current value
+    #[returns(LpPositionResponse)]
+    GetLpPosition { address: String },
 }
 
 #[cw_serde]
 pub enum FlipExecuteMsg {
-    StartFlip { pick: PickTypes, amount: Uint128 },
+    StartFlip {
+        pick: PickTypes,
+        amount: Uint128,
+        /// Fee charged by the frontend on top of our flip fee
+        integrator: Option<IntegratorFee>,
+    },
     DoFlips {},
 }
 
+#[cw_serde]
+pub enum IntegratorExecuteMsg {
+    /// Claim the integrator fees you earned
+    ClaimIntegratorFees {},
+}
+
+#[cw_serde]
+pub enum VaultExecuteMsg {
+    /// Deposit into the bank and get vault shares
+    Deposit {},
+    /// Start the cooldown to withdraw `shares`
+    RequestWithdraw { shares: Uint128 },
+    /// Withdraw the requested shares once the cooldown is over, before the request expires
+    Withdraw {},
+    /// Cancel the withdraw request, to request again once it expired
+    CancelWithdraw {},
+}
+
+#[cw_serde]
+pub enum MultisigExecuteMsg {
+    /// Propose a sudo msg, the proposer votes yes
+    Propose {
+        msg: SudoMsg,
+    },
+    Vote {
+        id: u64,
+        approve: bool,
+    },
+    /// Send the sudo msg of a passed proposal, call-able by anyone
+    Execute {
+        id: u64,
+    },
+}
+
+#[cw_serde]
+pub enum ReferralExecuteMsg {
+    /// Register who referred you, can only be done once
+    RegisterReferrer { referrer: String },
+    /// Claim the referral rewards you earned
+    ClaimReferralRewards {},
+}
+
 #[cw_serde]
 pub enum SudoMsg {
     Distribute {},
-    UpdateFees { fees: Fees },
-    UpdateSg721 { addr: String },
-    UpdateBankLimit { limit: Uint128 },
-    UpdateBetLimit { min_bet: Uint128, max_bet: Uint128 },
+    /// Start a distribution that pays holders over multiple txs
+    StartDistribution {},
+    /// Pay the next `limit` holders of the started distribution
+    ContinueDistribution {
+        limit: Option<u32>,
+    },
+    /// Add or remove addresses that are excluded from holders distribution
+    UpdateExcludedHolders {
+        add: Vec<String>,
+        remove: Vec<String>,
+    },
+    /// Propose a new admin, it becomes admin once it accepts
+    ProposeAdmin {
+        admin: String,
+        expires: Option<Expiration>,
+    },
+    /// Remove the admin, the multisig and every role grant, sudo msgs can't be called anymore
+    RenounceAdmin {},
+    /// Add house capital to the bank, multisig members can send it directly
+    FundBank {},
+    /// Remove house capital from the bank, can't touch fees, LPs capital and pending flips
+    WithdrawBank {
+        amount: Uint128,
+        denom: String,
+    },
+    /// Queue a config change, it can be applied once `effective_at` is reached
+    ProposeConfigChange {
+        change: ConfigChange,
+        effective_at: Timestamp,
+    },
+    /// Cancel a queued config change before it's applied
+    CancelConfigChange {
+        id: u64,
+    },
+    /// Pause (or unpause) everything.
+    /// Pausing needs the pauser role, unpausing the super admin role.
     UpdatePause(bool),
+    /// Set which parts are paused, unpausing any of them needs the super admin role
+    UpdatePauseScopes {
+        scopes: PauseScopes,
+    },
+    /// Send back the bets of all pending flips, flip fees are not refunded.
+    ///
+    /// Fees are not stored per flip, and their referral and integrator shares are credited
+    /// when the flip starts and may already be claimed, so they are kept as they are.
+    RefundPendingFlips {},
+    /// Set the multisig members and threshold, the multisig becomes the admin
+    UpdateMultisig {
+        members: Vec<String>,
+        threshold: u64,
+        voting_period: Duration,
+    },
+    /// Give a role to an address, only the admin can grant super admin
+    GrantRole {
+        role: Role,
+        address: String,
+    },
+    /// Take a role back from an address, only the admin can revoke super admin
+    RevokeRole {
+        role: Role,
+        address: String,
+    },
+}
+
+/// Msgs chain governance can send to the native sudo entry point, no admin check is done
+#[cw_serde]
+pub enum ChainSudoMsg {
+    UpdatePause(bool),
+    /// Change fees right away, without the timelock delay
+    UpdateFees {
+        fees: Fees,
+    },
+    /// Set a new admin, in case the admin key is lost or compromised.
+    /// Pending admin proposal and multisig are removed.
+    ResetAdmin {
+        admin: String,
+    },
 }
 
 #[cw_serde]
 pub enum MigrateMsg {
     Basic {},
+    /// Migrate and update config fields in the same tx
+    WithConfig {
+        update: Box<ConfigUpdate>,
+    },
+}
+
+#[cw_serde]
+pub struct FlipQuoteResponse {
+    pub amount: Uint128,
+    /// If the holder discount is applied
+    pub is_holder: bool,
+    pub fee_bps: u64,
+    pub fee: Uint128,
+    pub integrator_fee: Uint128,
+    /// Amount + fees, what should be sent with `StartFlip`
+    pub total: Uint128,
+}
+
+#[cw_serde]
+pub struct VoteResponse {
+    pub voter: Addr,
+    pub approve: bool,
+}
+
+#[cw_serde]
+pub struct OwnershipResponse {
+    pub admin: Option<Addr>,
+    pub pending_admin: Option<PendingAdmin>,
+}
+
+#[cw_serde]
+pub struct RefillStatusResponse {
+    pub treasury: Option<Treasury>,
+    /// House bank, balance without fees, LPs capital and what we owe to pending flips
+    pub bank: Uint128,
+    pub bank_limit: Uint128,
+    /// Current period, None if we never refilled or it ended
+    pub period: Option<RefillPeriod>,
+    /// What `RefillBank` would request now
+    pub refill_amount: Uint128,
+}
+
+#[cw_serde]
+pub struct SolvencyResponse {
+    pub denom: String,
+    pub balance: Uint128,
+    pub fees: Uint128,
+    /// What we owe to pending flips if they all win
+    pub liabilities: Uint128,
+    /// If balance covers both fees and liabilities
+    pub is_solvent: bool,
+}
+
+#[cw_serde]
+pub struct EffectiveLimitsResponse {
+    pub denom: String,
+    pub min_bet: Uint128,
+    /// Static max bet, or the bank share if lower
+    pub max_bet: Uint128,
+    /// Balance without fees and what we owe to pending flips
+    pub available_bank: Uint128,
+    pub max_bet_bank_bps: Option<u64>,
+}
+
+#[cw_serde]
+pub struct NextDistributionResponse {
+    pub schedule: Option<DistributionSchedule>,
+    pub last_distribution: Option<LastDistribution>,
+    /// None if never distributed, so it can be triggered once we have the min fees
+    pub next_distribution: Option<Expiration>,
+    pub distributable_fees: Uint128,
+    /// If anyone can trigger a distribution now
+    pub is_due: bool,
+}
+
+#[cw_serde]
+pub struct IntegratorStatsResponse {
+    pub address: Addr,
+    pub flips: u64,
+    pub volume: Uint128,
+    pub earned: Uint128,
+    pub claimed: Uint128,
+    pub claimable: Uint128,
+}
+
+#[cw_serde]
+pub struct VaultResponse {
+    pub total_shares: Uint128,
+    pub assets: Uint128,
+    pub cooldown: Duration,
+}
+
+#[cw_serde]
+pub struct LpPositionResponse {
+    pub shares: Uint128,
+    /// Current value of all the shares
+    pub value: Uint128,
+    pub withdraw_request: Option<WithdrawRequest>,
+}
+
+#[cw_serde]
+pub struct ReferralStatsResponse {
+    pub referred_wallets: u64,
+    pub volume: Uint128,
+    pub earned: Uint128,
+    pub claimed: Uint128,
+    pub claimable: Uint128,
 }
 
 #[cw_serde]
 pub struct DryDistributionResponse {
     pub total_fees: Uint128,
-    pub team_total_fee: Uint128,
-    pub reserve_total_fee: Uint128,
+    /// Every fee recipient except holders
+    pub recipients: Vec<RecipientPayout>,
     pub holders_total_fee: Uint128,
     pub holders_total_shares: Decimal,
     pub fees_per_token: Decimal,
     pub pay_to_holders: Uint128,
     pub number_of_holders: u64,
+    pub number_of_excluded_holders: u64,
+    pub holders_pending: Uint128,
+    /// Pending amounts of holders that left, given to the remainder recipient
+    pub holders_released: Uint128,
+    pub rounding_remainder: Uint128,
+}
+
+/// Msgs we expect the reserve treasury contract to support
+#[cw_serde]
+pub enum TreasuryExecuteMsg {
+    /// Send `amount` to the sender
+    Refill { amount: Coin },
+}
+
+/// Query of the DAO DAO cw721-staked voting module we use
+#[cw_serde]
+pub enum StakingQueryMsg {
+    /// Get the token ids staked by an address
+    StakedNfts {
+        address: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+}
+
+/// Stake hook of the DAO DAO cw721-staked voting module
+#[cw_serde]
+pub enum StakeChangedHookMsg {
+    Stake { addr: Addr, token_id: String },
+    Unstake { addr: Addr, token_ids: Vec<String> },
 }
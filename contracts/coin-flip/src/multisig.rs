@@ -0,0 +1,172 @@
+use cosmwasm_std::{ensure, to_binary, Addr, DepsMut, Empty, Env, MessageInfo, Order, WasmMsg};
+use cw_utils::Duration;
+use sg_std::Response;
+
+use crate::error::ContractError;
+use crate::msg::{ExecuteMsg, SudoMsg};
+use crate::state::{
+    CONFIG, MULTISIG, MULTISIG_MEMBERS, PENDING_ADMIN, PROPOSALS, PROPOSAL_ID, VOTES,
+};
+use crate::types::{Config, Multisig, Proposal, ProposalStatus};
+
+/// Replace the multisig members and threshold.
+///
+/// The contract itself becomes the admin, so sudo msgs can only be sent by executing proposals.
+/// Proposals and votes of the previous multisig are dropped.
+pub fn update_multisig(
+    deps: DepsMut,
+    env: Env,
+    mut config: Config,
+    members: Vec<String>,
+    threshold: u64,
+    voting_period: Duration,
+) -> Result<Response, ContractError> {
+    let mut members = members
+        .iter()
+        .map(|member| deps.api.addr_validate(member))
+        .collect::<Result<Vec<Addr>, _>>()?;
+    members.sort();
+    members.dedup();
+    ensure!(
+        threshold > 0 && threshold <= members.len() as u64,
+        ContractError::InvalidThreshold {
+            members: members.len() as u64
+        }
+    );
+
+    // Open proposals were made for the old members and threshold
+    MULTISIG_MEMBERS.clear(deps.storage);
+    PROPOSALS.clear(deps.storage);
+    VOTES.clear(deps.storage);
+    for member in &members {
+        MULTISIG_MEMBERS.save(deps.storage, member, &Empty {})?;
+    }
+    MULTISIG.save(
+        deps.storage,
+        &Multisig {
+            threshold,
+            voting_period,
+        },
+    )?;
+
+    PENDING_ADMIN.remove(deps.storage);
+    config.admin = Some(env.contract.address);
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::default()
+        .add_attribute("method", "update_multisig")
+        .add_attribute("members", members.len().to_string())
+        .add_attribute("threshold", threshold.to_string()))
+}
+
+fn ensure_member(deps: &DepsMut, info: &MessageInfo) -> Result<Multisig, ContractError> {
+    let multisig = MULTISIG
+        .may_load(deps.storage)?
+        .ok_or(ContractError::MultisigNotEnabled)?;
+    ensure!(
+        MULTISIG_MEMBERS.has(deps.storage, &info.sender),
+        ContractError::Unauthorized
+    );
+    Ok(multisig)
+}
+
+/// Propose a sudo msg, the proposer votes yes.
+///
+/// Proposals are executed without funds, so `FundBank` can't be proposed, members send it directly.
+pub fn propose(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: SudoMsg,
+) -> Result<Response, ContractError> {
+    let multisig = ensure_member(&deps, &info)?;
+    ensure!(
+        !matches!(msg, SudoMsg::FundBank {}),
+        ContractError::ProposalWithFunds
+    );
+    let members = MULTISIG_MEMBERS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .count() as u64;
+
+    let id = PROPOSAL_ID.may_load(deps.storage)?.unwrap_or_default() + 1;
+    PROPOSAL_ID.save(deps.storage, &id)?;
+
+    let mut proposal = Proposal {
+        id,
+        proposer: info.sender.clone(),
+        msg,
+        threshold: multisig.threshold,
+        members,
+        yes: 1,
+        no: 0,
+        status: ProposalStatus::Open,
+        expires: multisig.voting_period.after(&env.block),
+    };
+    proposal.update_status();
+    PROPOSALS.save(deps.storage, id, &proposal)?;
+    VOTES.save(deps.storage, (id, &info.sender), &true)?;
+
+    Ok(Response::default()
+        .add_attribute("method", "propose")
+        .add_attribute("proposal_id", id.to_string())
+        .add_attribute("proposer", info.sender))
+}
+
+pub fn vote(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: u64,
+    approve: bool,
+) -> Result<Response, ContractError> {
+    ensure_member(&deps, &info)?;
+    let mut proposal = PROPOSALS
+        .may_load(deps.storage, id)?
+        .ok_or(ContractError::NoProposal { id })?;
+    ensure!(
+        proposal.current_status(&env.block) == ProposalStatus::Open,
+        ContractError::ProposalNotOpen
+    );
+    ensure!(
+        !VOTES.has(deps.storage, (id, &info.sender)),
+        ContractError::AlreadyVoted
+    );
+
+    VOTES.save(deps.storage, (id, &info.sender), &approve)?;
+    if approve {
+        proposal.yes += 1;
+    } else {
+        proposal.no += 1;
+    }
+    proposal.update_status();
+    PROPOSALS.save(deps.storage, id, &proposal)?;
+
+    Ok(Response::default()
+        .add_attribute("method", "vote")
+        .add_attribute("proposal_id", id.to_string())
+        .add_attribute("voter", info.sender)
+        .add_attribute("approve", approve.to_string()))
+}
+
+/// Send the sudo msg of a passed proposal to ourselves, as the admin
+pub fn execute(deps: DepsMut, env: Env, id: u64) -> Result<Response, ContractError> {
+    let mut proposal = PROPOSALS
+        .may_load(deps.storage, id)?
+        .ok_or(ContractError::NoProposal { id })?;
+    ensure!(
+        proposal.current_status(&env.block) == ProposalStatus::Passed,
+        ContractError::ProposalNotPassed
+    );
+
+    proposal.status = ProposalStatus::Executed;
+    PROPOSALS.save(deps.storage, id, &proposal)?;
+
+    Ok(Response::default()
+        .add_message(WasmMsg::Execute {
+            contract_addr: env.contract.address.to_string(),
+            msg: to_binary(&ExecuteMsg::Sudo(proposal.msg))?,
+            funds: vec![],
+        })
+        .add_attribute("method", "execute_proposal")
+        .add_attribute("proposal_id", id.to_string()))
+}
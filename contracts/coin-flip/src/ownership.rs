@@ -0,0 +1,116 @@
+use cosmwasm_std::{ensure, DepsMut, Empty, Env, MessageInfo, Storage};
+use cw_utils::Expiration;
+use sg_std::Response;
+
+use crate::error::ContractError;
+use crate::state::{
+    CONFIG, MULTISIG, MULTISIG_MEMBERS, PENDING_ADMIN, PENDING_CHANGES, PROPOSALS, ROLES, VOTES,
+};
+use crate::types::{Config, PendingAdmin, Role};
+
+/// Propose a new admin, replaces the previous proposal if any
+pub fn propose_admin(
+    deps: DepsMut,
+    env: Env,
+    admin: String,
+    expires: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    let address = deps.api.addr_validate(&admin)?;
+    ensure!(
+        expires.is_none_or(|expires| !expires.is_expired(&env.block)),
+        ContractError::AdminProposalExpired
+    );
+
+    PENDING_ADMIN.save(deps.storage, &PendingAdmin { address, expires })?;
+
+    Ok(Response::default()
+        .add_attribute("method", "propose_admin")
+        .add_attribute("pending_admin", admin))
+}
+
+/// The multisig stops being the admin, drop its members, proposals and votes
+fn remove_multisig(storage: &mut dyn Storage) {
+    MULTISIG.remove(storage);
+    MULTISIG_MEMBERS.clear(storage);
+    PROPOSALS.clear(storage);
+    VOTES.clear(storage);
+}
+
+/// Accept the admin role, the sender must be the proposed admin. A multisig admin is removed.
+pub fn accept_admin(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    mut config: Config,
+) -> Result<Response, ContractError> {
+    let pending = PENDING_ADMIN
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoPendingAdmin)?;
+    ensure!(pending.address == info.sender, ContractError::Unauthorized);
+    ensure!(
+        pending
+            .expires
+            .is_none_or(|expires| !expires.is_expired(&env.block)),
+        ContractError::AdminProposalExpired
+    );
+
+    PENDING_ADMIN.remove(deps.storage);
+    remove_multisig(deps.storage);
+    config.admin = Some(pending.address);
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::default()
+        .add_attribute("method", "accept_admin")
+        .add_attribute("admin", info.sender))
+}
+
+/// Set the admin without the 2 steps transfer, only used by chain governance.
+///
+/// The multisig is removed with its members, proposals and votes.
+pub fn reset_admin(
+    deps: DepsMut,
+    mut config: Config,
+    admin: String,
+) -> Result<Response, ContractError> {
+    PENDING_ADMIN.remove(deps.storage);
+    remove_multisig(deps.storage);
+    config.admin = Some(deps.api.addr_validate(&admin)?);
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::default()
+        .add_attribute("method", "reset_admin")
+        .add_attribute("admin", admin))
+}
+
+/// Remove the admin (and any proposal), the multisig, every role grant and the timelocked changes,
+/// this cannot be undone
+pub fn renounce_admin(deps: DepsMut, mut config: Config) -> Result<Response, ContractError> {
+    PENDING_ADMIN.remove(deps.storage);
+    remove_multisig(deps.storage);
+    ROLES.clear(deps.storage);
+    PENDING_CHANGES.clear(deps.storage);
+    config.admin = None;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::default().add_attribute("method", "renounce_admin"))
+}
+
+pub fn grant_role(deps: DepsMut, role: Role, address: String) -> Result<Response, ContractError> {
+    let addr = deps.api.addr_validate(&address)?;
+    ROLES.save(deps.storage, (role.as_str(), &addr), &Empty {})?;
+
+    Ok(Response::default()
+        .add_attribute("method", "grant_role")
+        .add_attribute("role", role.as_str())
+        .add_attribute("address", address))
+}
+
+pub fn revoke_role(deps: DepsMut, role: Role, address: String) -> Result<Response, ContractError> {
+    let addr = deps.api.addr_validate(&address)?;
+    ROLES.remove(deps.storage, (role.as_str(), &addr));
+
+    Ok(Response::default()
+        .add_attribute("method", "revoke_role")
+        .add_attribute("role", role.as_str())
+        .add_attribute("address", address))
+}
@@ -0,0 +1,105 @@
+use cosmwasm_std::{coins, ensure, Addr, BankMsg, DepsMut, Empty, MessageInfo, Storage, Uint128};
+use sg_std::Response;
+
+use crate::error::ContractError;
+use crate::helpers::MAX_BPS;
+use crate::state::{FEES, REFERRAL_CLAIMABLE_TOTAL, REFERRAL_STATS, REFERRED_WALLETS, REFERRERS};
+use crate::types::Config;
+
+/// Register the referrer of the sender, can only be done once
+pub fn register_referrer(
+    deps: DepsMut,
+    info: MessageInfo,
+    referrer: String,
+) -> Result<Response, ContractError> {
+    let referrer = deps.api.addr_validate(&referrer)?;
+
+    ensure!(referrer != info.sender, ContractError::SelfReferral);
+    ensure!(
+        !REFERRERS.has(deps.storage, &info.sender),
+        ContractError::ReferrerAlreadySet
+    );
+    // Prevent 2 wallets from referring each other
+    ensure!(
+        REFERRERS.may_load(deps.storage, &referrer)? != Some(info.sender.clone()),
+        ContractError::SelfReferral
+    );
+
+    REFERRERS.save(deps.storage, &info.sender, &referrer)?;
+    REFERRED_WALLETS.save(deps.storage, (&referrer, &info.sender), &Empty {})?;
+    let mut stats = REFERRAL_STATS
+        .may_load(deps.storage, &referrer)?
+        .unwrap_or_default();
+    stats.referred_wallets += 1;
+    REFERRAL_STATS.save(deps.storage, &referrer, &stats)?;
+
+    Ok(Response::default()
+        .add_attribute("method", "register_referrer")
+        .add_attribute("referrer", referrer))
+}
+
+/// Credit the referrer of the flipper with his share of the flip fee.
+///
+/// The reward stays in FEES until claimed, so it is taken out before
+/// we split the fees between team, holders and reserve.
+pub fn credit_referrer(
+    storage: &mut dyn Storage,
+    config: &Config,
+    flipper: &Addr,
+    amount: Uint128,
+    fee_amount: Uint128,
+) -> Result<Uint128, ContractError> {
+    let Some(referrer) = REFERRERS.may_load(storage, flipper)? else {
+        return Ok(Uint128::zero());
+    };
+
+    let reward = fee_amount.multiply_ratio(config.fees.referral_bps, MAX_BPS);
+
+    let mut stats = REFERRAL_STATS
+        .may_load(storage, &referrer)?
+        .unwrap_or_default();
+    stats.volume = stats.volume.checked_add(amount)?;
+    stats.earned = stats.earned.checked_add(reward)?;
+    REFERRAL_STATS.save(storage, &referrer, &stats)?;
+
+    let claimable_total = REFERRAL_CLAIMABLE_TOTAL
+        .may_load(storage)?
+        .unwrap_or_default()
+        .checked_add(reward)?;
+    REFERRAL_CLAIMABLE_TOTAL.save(storage, &claimable_total)?;
+
+    Ok(reward)
+}
+
+/// Send the sender all his unclaimed referral rewards
+pub fn claim_referral_rewards(
+    deps: DepsMut,
+    info: MessageInfo,
+    config: &Config,
+) -> Result<Response, ContractError> {
+    let mut stats = REFERRAL_STATS
+        .may_load(deps.storage, &info.sender)?
+        .unwrap_or_default();
+    let claimable = stats.claimable()?;
+    ensure!(!claimable.is_zero(), ContractError::NoReferralRewards);
+
+    stats.claimed = stats.earned;
+    REFERRAL_STATS.save(deps.storage, &info.sender, &stats)?;
+
+    let claimable_total = REFERRAL_CLAIMABLE_TOTAL
+        .load(deps.storage)?
+        .checked_sub(claimable)?;
+    REFERRAL_CLAIMABLE_TOTAL.save(deps.storage, &claimable_total)?;
+
+    let fees = FEES.load(deps.storage)?.checked_sub(claimable)?;
+    FEES.save(deps.storage, &fees)?;
+
+    // TODO: Currently we only accept 1 denom
+    Ok(Response::default()
+        .add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: coins(claimable.into(), config.denoms[0].clone()),
+        })
+        .add_attribute("method", "claim_referral_rewards")
+        .add_attribute("claimed", claimable))
+}
@@ -0,0 +1,207 @@
+use cosmwasm_std::{
+    coin, ensure, to_binary, BankMsg, Deps, DepsMut, Env, Event, Reply, SubMsg, SubMsgResult,
+    Uint128, WasmMsg,
+};
+use sg_std::Response;
+
+use crate::error::ContractError;
+use crate::msg::{RefillStatusResponse, TreasuryExecuteMsg};
+use crate::state::{
+    get_liabilities, update_house_ledger, CONFIG, FEES, PENDING_AUTO_REFILL, REFILL_PERIOD, VAULT,
+};
+use crate::types::{Config, RefillPeriod, Treasury};
+
+/// Reply id of the refills we request after flips and distributions
+pub const AUTO_REFILL_REPLY_ID: u64 = 1;
+
+/// Get how much we can request from the treasury now
+pub fn get_refill_status(
+    deps: Deps,
+    env: &Env,
+    config: &Config,
+) -> Result<RefillStatusResponse, ContractError> {
+    refill_status(deps, env, config, Uint128::zero())
+}
+
+/// `outgoing` is what we are about to send, it's still in our balance
+fn refill_status(
+    deps: Deps,
+    env: &Env,
+    config: &Config,
+    outgoing: Uint128,
+) -> Result<RefillStatusResponse, ContractError> {
+    // TODO: Currently we only accept 1 denom
+    let balance = deps
+        .querier
+        .query_balance(&env.contract.address, config.denoms[0].clone())?;
+    let fees = FEES.load(deps.storage)?;
+    // Same as the bank limit check of the distribution, LPs capital and what
+    // we owe to pending flips are not ours
+    let lp_capital = VAULT
+        .may_load(deps.storage)?
+        .map_or(Uint128::zero(), |vault| vault.assets);
+    let liabilities = get_liabilities(deps.storage, &config.denoms[0])?;
+    let bank = balance
+        .amount
+        .saturating_sub(outgoing)
+        .saturating_sub(fees)
+        .saturating_sub(liabilities)
+        .saturating_sub(lp_capital);
+
+    let period = REFILL_PERIOD
+        .may_load(deps.storage)?
+        .filter(|period| !period.ends.is_expired(&env.block));
+
+    let refill_amount = match &config.treasury {
+        Some(treasury) => {
+            let refilled = period
+                .as_ref()
+                .map_or(Uint128::zero(), |period| period.refilled);
+            config
+                .bank_limit
+                .saturating_sub(bank)
+                .min(treasury.refill_cap.saturating_sub(refilled))
+        }
+        None => Uint128::zero(),
+    };
+
+    Ok(RefillStatusResponse {
+        treasury: config.treasury.clone(),
+        bank,
+        bank_limit: config.bank_limit,
+        period,
+        refill_amount,
+    })
+}
+
+/// Request what the bank is missing from the treasury, call-able by anyone.
+///
+/// Refills are capped per period, so a bad streak can't drain the treasury.
+pub fn refill_bank(deps: DepsMut, env: Env, config: &Config) -> Result<Response, ContractError> {
+    let treasury = config
+        .treasury
+        .clone()
+        .ok_or(ContractError::TreasuryNotSet)?;
+    let status = get_refill_status(deps.as_ref(), &env, config)?;
+    ensure!(
+        status.bank < config.bank_limit,
+        ContractError::BankAboveLimit
+    );
+
+    let (msg, event) = request_refill(deps, &env, config, treasury, status)?;
+    Ok(Response::default().add_message(msg).add_event(event))
+}
+
+/// Refill the bank after flips are settled or fees are distributed, if it's under the limit.
+///
+/// `outgoing` is what the response sends, it's still in our balance.
+/// This is best-effort: nothing is requested without a treasury or once the cap is reached,
+/// and a failing treasury doesn't fail the flips or the distribution.
+pub fn auto_refill(
+    deps: DepsMut,
+    env: &Env,
+    config: &Config,
+    outgoing: Uint128,
+) -> Result<Response, ContractError> {
+    let Some(treasury) = config.treasury.clone() else {
+        return Ok(Response::default());
+    };
+    let status = refill_status(deps.as_ref(), env, config, outgoing)?;
+    if status.bank >= config.bank_limit || status.refill_amount.is_zero() {
+        return Ok(Response::default());
+    }
+
+    PENDING_AUTO_REFILL.save(deps.storage, &status.refill_amount)?;
+    let (msg, event) = request_refill(deps, env, config, treasury, status)?;
+    Ok(Response::default()
+        .add_submessage(SubMsg::reply_always(msg, AUTO_REFILL_REPLY_ID))
+        .add_event(event))
+}
+
+/// Undo the accounting of an automatic refill the treasury refused
+pub fn auto_refill_reply(deps: DepsMut, msg: Reply) -> Result<Response, ContractError> {
+    let amount = PENDING_AUTO_REFILL.load(deps.storage)?;
+    PENDING_AUTO_REFILL.remove(deps.storage);
+
+    let SubMsgResult::Err(err) = msg.result else {
+        return Ok(Response::default());
+    };
+
+    let config = CONFIG.load(deps.storage)?;
+    if let Some(mut period) = REFILL_PERIOD.may_load(deps.storage)? {
+        period.refilled = period.refilled.saturating_sub(amount);
+        REFILL_PERIOD.save(deps.storage, &period)?;
+    }
+    update_house_ledger(deps.storage, &config.denoms[0], |ledger| {
+        ledger.refilled = ledger.refilled.checked_sub(amount)?;
+        Ok(())
+    })?;
+
+    Ok(Response::default().add_event(
+        Event::new("bank_refill_failed")
+            .add_attribute("amount", amount)
+            .add_attribute("error", err),
+    ))
+}
+
+/// Total of `denom` the bank msgs send out
+pub fn outgoing_amount(msgs: &[BankMsg], denom: &str) -> Uint128 {
+    msgs.iter()
+        .flat_map(|msg| match msg {
+            BankMsg::Send { amount, .. } | BankMsg::Burn { amount } => amount.as_slice(),
+            _ => &[],
+        })
+        .filter(|coin| coin.denom == denom)
+        .map(|coin| coin.amount)
+        .sum()
+}
+
+/// Book the refill in the current period and the house ledger, and build the treasury msg
+fn request_refill(
+    deps: DepsMut,
+    env: &Env,
+    config: &Config,
+    treasury: Treasury,
+    status: RefillStatusResponse,
+) -> Result<(WasmMsg, Event), ContractError> {
+    let RefillStatusResponse {
+        bank,
+        period,
+        refill_amount,
+        ..
+    } = status;
+
+    let mut period = period.unwrap_or(RefillPeriod {
+        ends: treasury.period.after(&env.block),
+        refilled: Uint128::zero(),
+    });
+    ensure!(
+        !refill_amount.is_zero(),
+        ContractError::RefillCapReached {
+            period_ends: period.ends
+        }
+    );
+    period.refilled = period.refilled.checked_add(refill_amount)?;
+    REFILL_PERIOD.save(deps.storage, &period)?;
+
+    update_house_ledger(deps.storage, &config.denoms[0], |ledger| {
+        ledger.refilled = ledger.refilled.checked_add(refill_amount)?;
+        Ok(())
+    })?;
+
+    let msg = WasmMsg::Execute {
+        contract_addr: treasury.addr.clone(),
+        msg: to_binary(&TreasuryExecuteMsg::Refill {
+            amount: coin(refill_amount.u128(), config.denoms[0].clone()),
+        })?,
+        funds: vec![],
+    };
+    let event = Event::new("bank_refill")
+        .add_attribute("treasury", treasury.addr)
+        .add_attribute("amount", refill_amount)
+        .add_attribute("bank", bank)
+        .add_attribute("bank_limit", config.bank_limit)
+        .add_attribute("period_refilled", period.refilled)
+        .add_attribute("period_ends", period.ends.to_string());
+    Ok((msg, event))
+}
@@ -0,0 +1,70 @@
+use cosmwasm_std::{ensure, Deps, DepsMut, Env, MessageInfo};
+use sg_std::Response;
+
+use crate::error::ContractError;
+use crate::msg::NextDistributionResponse;
+use crate::state::{DISTRIBUTION_RUN, LAST_DISTRIBUTION};
+use crate::sudo::{self, get_distributable_fees};
+use crate::types::Config;
+
+/// Get when the next scheduled distribution can be triggered
+pub fn get_next_distribution(
+    deps: Deps,
+    env: &Env,
+    config: &Config,
+) -> Result<NextDistributionResponse, ContractError> {
+    let last_distribution = LAST_DISTRIBUTION.may_load(deps.storage)?;
+    let distributable_fees = get_distributable_fees(deps.storage)?;
+    let schedule = config.distribution_schedule.clone();
+
+    let next_distribution = match (&schedule, &last_distribution) {
+        (Some(schedule), Some(last)) => Some(schedule.next_distribution(last)),
+        _ => None,
+    };
+
+    let is_due = match &schedule {
+        Some(schedule) => {
+            next_distribution.is_none_or(|next| next.is_expired(&env.block))
+                && distributable_fees >= schedule.min_fees
+                && DISTRIBUTION_RUN.may_load(deps.storage)?.is_none()
+        }
+        None => false,
+    };
+
+    Ok(NextDistributionResponse {
+        schedule,
+        last_distribution,
+        next_distribution,
+        distributable_fees,
+        is_due,
+    })
+}
+
+/// Distribute if the scheduled distribution is due, call-able by anyone.
+pub fn trigger_distribution(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    config: &Config,
+) -> Result<Response, ContractError> {
+    let NextDistributionResponse {
+        schedule,
+        next_distribution,
+        distributable_fees,
+        ..
+    } = get_next_distribution(deps.as_ref(), &env, config)?;
+    let schedule = schedule.ok_or(ContractError::DistributionNotScheduled)?;
+
+    ensure!(
+        next_distribution.is_none_or(|next| next.is_expired(&env.block)),
+        ContractError::DistributionNotDue
+    );
+    ensure!(
+        distributable_fees >= schedule.min_fees,
+        ContractError::NotEnoughFeesToDistribute {
+            min_fees: schedule.min_fees
+        }
+    );
+
+    sudo::distribute(deps, env, config, Some(info.sender))
+}
@@ -0,0 +1,106 @@
+use cosmwasm_std::{ensure, Addr, DepsMut, MessageInfo, Order};
+use sg_std::Response;
+
+use crate::error::ContractError;
+use crate::msg::{StakeChangedHookMsg, StakingQueryMsg};
+use crate::state::STAKED_TOKENS;
+use crate::types::Config;
+
+/// Page size we use when querying the staking contract
+const STAKING_QUERY_LIMIT: u32 = 30;
+
+/// Track stakers from the stake hooks of the staking contract.
+///
+/// cw721-staked can't list its stakers, so we keep our own list of staked tokens.
+pub fn stake_changed(
+    deps: DepsMut,
+    info: MessageInfo,
+    config: &Config,
+    msg: StakeChangedHookMsg,
+) -> Result<Response, ContractError> {
+    ensure!(
+        config.staking_addr.as_ref() == Some(&info.sender),
+        ContractError::Unauthorized
+    );
+
+    match msg {
+        StakeChangedHookMsg::Stake { addr, token_id } => {
+            STAKED_TOKENS.save(deps.storage, &token_id, &addr)?;
+            Ok(Response::default()
+                .add_attribute("method", "stake_changed")
+                .add_attribute("staked", token_id))
+        }
+        StakeChangedHookMsg::Unstake { addr, token_ids } => {
+            for token_id in &token_ids {
+                if STAKED_TOKENS.may_load(deps.storage, token_id)? == Some(addr.clone()) {
+                    STAKED_TOKENS.remove(deps.storage, token_id);
+                }
+            }
+            Ok(Response::default()
+                .add_attribute("method", "stake_changed")
+                .add_attribute("unstaked", token_ids.len().to_string()))
+        }
+    }
+}
+
+/// Set the staked tokens of an address from the staking contract, call-able by anyone.
+///
+/// Hooks only tell us about stake changes made after we were added as a hook,
+/// this picks up the NFTs staked before.
+pub fn sync_staker(
+    deps: DepsMut,
+    config: &Config,
+    address: String,
+) -> Result<Response, ContractError> {
+    let staking_addr = config
+        .staking_addr
+        .clone()
+        .ok_or(ContractError::NoStakingContract)?;
+    let staker = deps.api.addr_validate(&address)?;
+
+    // The staking contract is the source of truth, drop what we know of this staker
+    let known = STAKED_TOKENS
+        .range(deps.storage, None, None, Order::Ascending)
+        .filter_map(|item| match item {
+            Ok((token_id, owner)) if owner == staker => Some(Ok(token_id)),
+            Ok(_) => None,
+            Err(err) => Some(Err(err)),
+        })
+        .collect::<Result<Vec<String>, _>>()?;
+    for token_id in known {
+        STAKED_TOKENS.remove(deps.storage, &token_id);
+    }
+
+    let token_ids = query_staked_nfts(&deps, &staking_addr, &staker)?;
+    for token_id in &token_ids {
+        STAKED_TOKENS.save(deps.storage, token_id, &staker)?;
+    }
+
+    Ok(Response::default()
+        .add_attribute("method", "sync_staker")
+        .add_attribute("staker", staker)
+        .add_attribute("staked", token_ids.len().to_string()))
+}
+
+fn query_staked_nfts(
+    deps: &DepsMut,
+    staking_addr: &Addr,
+    staker: &Addr,
+) -> Result<Vec<String>, ContractError> {
+    let mut token_ids = vec![];
+    loop {
+        let page = deps.querier.query_wasm_smart::<Vec<String>>(
+            staking_addr,
+            &StakingQueryMsg::StakedNfts {
+                address: staker.to_string(),
+                start_after: token_ids.last().cloned(),
+                limit: Some(STAKING_QUERY_LIMIT),
+            },
+        )?;
+        let done = page.len() < STAKING_QUERY_LIMIT as usize;
+        token_ids.extend(page);
+        if done {
+            return Ok(token_ids);
+        }
+    }
+}
@@ -1,7 +1,11 @@
-use cosmwasm_std::{Addr, StdError, Storage, Uint128};
+use cosmwasm_std::{Addr, Empty, OverflowError, StdError, Storage, Uint128};
 use cw_storage_plus::{Item, Map};
 
-use crate::types::{Config, Flip, FlipScore, TodoFlip};
+use crate::types::{
+    Config, DistributionRecord, DistributionRun, Flip, FlipScore, HouseLedger, IntegratorStats,
+    LastDistribution, LpPosition, Multisig, PendingAdmin, PendingChange, Proposal, ReferralStats,
+    RefillPeriod, TodoFlip, Vault,
+};
 
 /// Our config holds admin and fees %
 pub const CONFIG: Item<Config> = Item::new("config");
@@ -14,6 +18,68 @@ pub const FLIP_ID: Item<u64> = Item::new("flip_id");
 /// Flips tracker so we can easily get stats later
 pub const FLIPS: Item<Vec<Flip>> = Item::new("last_flips");
 pub const TODO_FLIPS: Item<Vec<TodoFlip>> = Item::new("todo_flips");
+/// Distribution that is currently being paid over multiple txs
+pub const DISTRIBUTION_RUN: Item<DistributionRun> = Item::new("distribution_run");
+/// Holders that still need to be paid in the current distribution run
+pub const DISTRIBUTION_PAYOUTS: Map<&str, Uint128> = Map::new("distribution_payouts");
+/// Addresses that are not paid holders fees (marketplaces, burn, team, etc)
+pub const EXCLUDED_HOLDERS: Map<&Addr, Empty> = Map::new("excluded_holders");
+/// Holders amounts that are under the min payout, paid when they reach it
+pub const HOLDERS_PENDING: Map<&Addr, Uint128> = Map::new("holders_pending");
+/// Sum of all holders pending amounts (part of FEES)
+pub const HOLDERS_PENDING_TOTAL: Item<Uint128> = Item::new("holders_pending_total");
+/// History of finished distributions
+pub const DISTRIBUTIONS: Map<u64, DistributionRecord> = Map::new("distributions");
+/// Block of the last distribution, used for scheduled distributions
+pub const LAST_DISTRIBUTION: Item<LastDistribution> = Item::new("last_distribution");
+/// Last distribution id
+pub const DISTRIBUTION_ID: Item<u64> = Item::new("distribution_id");
+/// Total fees each holder earned from all distributions
+pub const HOLDER_EARNINGS: Map<&Addr, Uint128> = Map::new("holder_earnings");
+/// Stats per integrator
+pub const INTEGRATOR_STATS: Map<&Addr, IntegratorStats> = Map::new("integrator_stats");
+/// Sum of all integrator fees that are not claimed yet (part of FEES)
+pub const INTEGRATOR_CLAIMABLE_TOTAL: Item<Uint128> = Item::new("integrator_claimable_total");
+/// The referrer each wallet registered
+pub const REFERRERS: Map<&Addr, Addr> = Map::new("referrers");
+/// Wallets referred by each referrer (referrer, wallet)
+pub const REFERRED_WALLETS: Map<(&Addr, &Addr), Empty> = Map::new("referred_wallets");
+/// Referral stats per referrer
+pub const REFERRAL_STATS: Map<&Addr, ReferralStats> = Map::new("referral_stats");
+/// Sum of all referral rewards that are not claimed yet (part of FEES)
+pub const REFERRAL_CLAIMABLE_TOTAL: Item<Uint128> = Item::new("referral_claimable_total");
+/// Admin that was proposed and didn't accept yet
+pub const PENDING_ADMIN: Item<PendingAdmin> = Item::new("pending_admin");
+/// Members of each role, keyed by (role, address)
+pub const ROLES: Map<(&str, &Addr), Empty> = Map::new("roles");
+/// Config changes waiting for their timelock delay
+pub const PENDING_CHANGES: Map<u64, PendingChange> = Map::new("pending_changes");
+/// Last pending change id
+pub const PENDING_CHANGE_ID: Item<u64> = Item::new("pending_change_id");
+/// Set when the built-in multisig is the admin
+pub const MULTISIG: Item<Multisig> = Item::new("multisig");
+pub const MULTISIG_MEMBERS: Map<&Addr, Empty> = Map::new("multisig_members");
+/// Multisig proposals, kept after they are closed
+pub const PROPOSALS: Map<u64, Proposal> = Map::new("proposals");
+/// Last proposal id
+pub const PROPOSAL_ID: Item<u64> = Item::new("proposal_id");
+/// Multisig votes per proposal, true if the member approved
+pub const VOTES: Map<(u64, &Addr), bool> = Map::new("votes");
+/// House capital ledger per denom
+pub const HOUSE_LEDGER: Map<&str, HouseLedger> = Map::new("house_ledger");
+/// Treasury refills of the current period
+pub const REFILL_PERIOD: Item<RefillPeriod> = Item::new("refill_period");
+/// Amount of the automatic refill waiting for its reply
+pub const PENDING_AUTO_REFILL: Item<Uint128> = Item::new("pending_auto_refill");
+/// What we owe to pending flips if they all win, per denom
+pub const LIABILITIES: Map<&str, Uint128> = Map::new("liabilities");
+/// LPs capital in the bank (not part of FEES)
+pub const VAULT: Item<Vault> = Item::new("vault");
+/// Vault shares per LP
+pub const LP_POSITIONS: Map<&Addr, LpPosition> = Map::new("lp_positions");
+
+/// Staker of each token staked in the staking contract, by token id
+pub const STAKED_TOKENS: Map<&str, Addr> = Map::new("staked_tokens");
 
 /// Get the current flip id
 pub fn get_flip_id(store: &dyn Storage) -> Result<u64, StdError> {
@@ -27,63 +93,274 @@ pub fn get_next_flip_id(store: &dyn Storage) -> u64 {
     }
 }
 
+/// Get what we owe to pending flips of this denom
+pub fn get_liabilities(store: &dyn Storage, denom: &str) -> Result<Uint128, StdError> {
+    Ok(LIABILITIES.may_load(store, denom)?.unwrap_or_default())
+}
+
+/// Add the payout of a new flip to the liabilities, returns the new total
+pub fn add_liability(
+    store: &mut dyn Storage,
+    denom: &str,
+    amount: Uint128,
+) -> Result<Uint128, StdError> {
+    let liabilities = get_liabilities(store, denom)?.checked_add(amount)?;
+    LIABILITIES.save(store, denom, &liabilities)?;
+    Ok(liabilities)
+}
+
+/// Remove the payout of a finished flip from the liabilities
+pub fn remove_liability(
+    store: &mut dyn Storage,
+    denom: &str,
+    amount: Uint128,
+) -> Result<(), StdError> {
+    let liabilities = get_liabilities(store, denom)?.checked_sub(amount)?;
+    LIABILITIES.save(store, denom, &liabilities)?;
+    Ok(())
+}
+
+/// Update the house ledger of a denom
+pub fn update_house_ledger(
+    store: &mut dyn Storage,
+    denom: &str,
+    action: impl FnOnce(&mut HouseLedger) -> Result<(), OverflowError>,
+) -> Result<(), StdError> {
+    let mut ledger = HOUSE_LEDGER.may_load(store, denom)?.unwrap_or_default();
+    action(&mut ledger)?;
+    HOUSE_LEDGER.save(store, denom, &ledger)
+}
+
+/// helper function to get the next distribution id.
+pub fn get_next_distribution_id(store: &dyn Storage) -> u64 {
+    match DISTRIBUTION_ID.load(store) {
+        Ok(res) => res + 1,
+        Err(_) => 0,
+    }
+}
+
+/// Save a finished distribution to the history
+pub fn save_distribution_record(
+    store: &mut dyn Storage,
+    mut record: DistributionRecord,
+) -> Result<u64, StdError> {
+    let id = get_next_distribution_id(store);
+    record.id = id;
+    DISTRIBUTION_ID.save(store, &id)?;
+    DISTRIBUTIONS.save(store, id, &record)?;
+    Ok(id)
+}
+
+/// Add the paid amount to the holder total earnings
+pub fn add_holder_earnings(
+    store: &mut dyn Storage,
+    holder: &Addr,
+    amount: Uint128,
+) -> Result<Uint128, StdError> {
+    HOLDER_EARNINGS.update(store, holder, |earnings| -> Result<_, StdError> {
+        Ok(earnings.unwrap_or_default().checked_add(amount)?)
+    })
+}
+
 #[cfg(test)]
 mod test {
-    use cosmwasm_std::Uint128;
+    use cosmwasm_std::{testing::MockApi, Uint128};
 
-    use crate::types::{Fees, FeesToPay};
+    use crate::{
+        error::ContractError,
+        types::{FeeRecipient, FeeRecipientKind, Fees, FeesToPay, RecipientPayout},
+    };
+
+    fn recipient(name: &str, kind: FeeRecipientKind, bps: u64) -> FeeRecipient {
+        FeeRecipient {
+            name: name.to_string(),
+            kind,
+            bps,
+        }
+    }
+
+    fn test_fees() -> Fees {
+        Fees {
+            recipients: vec![
+                recipient(
+                    "team",
+                    FeeRecipientKind::Wallet {
+                        address: "team".to_string(),
+                    },
+                    1500,
+                ),
+                recipient("holders", FeeRecipientKind::Holders, 7000),
+                recipient(
+                    "reserve",
+                    FeeRecipientKind::Reserve {
+                        address: "reserve".to_string(),
+                    },
+                    1500,
+                ),
+            ],
+            flip_bps: 350,
+            flip_fee_tiers: vec![],
+            holder_discount_bps: 0,
+            referral_bps: 0,
+            remainder_recipient: "team".to_string(),
+        }
+    }
+
+    fn amounts(recipients: &[RecipientPayout]) -> Vec<Uint128> {
+        recipients
+            .iter()
+            .map(|recipient| recipient.amount)
+            .collect()
+    }
 
     #[test]
     fn test_fee_calc() {
-        let fee = Fees {
-            team_bps: 1500,
-            holders_bps: 7000,
-            reserve_bps: 1500,
-            flip_bps: 350,
-        };
+        let fee = test_fees();
 
         let total_fees = Uint128::new(100);
 
         let FeesToPay {
-            team: team_fees_to_pay,
-            holders: holders_fees_to_pay,
-            reserve: reserve_fees_to_pay,
-        } = fee.calculate(total_fees);
+            holders,
+            recipients,
+        } = fee.calculate(total_fees, true).unwrap();
 
+        assert_eq!(holders, Uint128::new(70));
         assert_eq!(
-            total_fees,
-            team_fees_to_pay
-                .checked_add(holders_fees_to_pay)
-                .unwrap()
-                .checked_add(reserve_fees_to_pay)
-                .unwrap()
+            amounts(&recipients),
+            vec![Uint128::new(15), Uint128::new(15)]
         );
 
-        // Make sure floor is working correctly
+        // Make sure the remainder of the floor goes to the remainder recipient
         let total_fees = Uint128::new(150);
 
         let FeesToPay {
-            team: team_fees_to_pay,
-            holders: holders_fees_to_pay,
-            reserve: reserve_fees_to_pay,
-        } = fee.calculate(total_fees);
+            holders,
+            recipients,
+        } = fee.calculate(total_fees, true).unwrap();
+
+        assert_eq!(holders, Uint128::new(105));
+        // 22 + 1 remainder for the team, 23 + 105 + 22 = 150
+        assert_eq!(
+            amounts(&recipients),
+            vec![Uint128::new(23), Uint128::new(22)]
+        );
+
+        let fee = Fees {
+            remainder_recipient: "reserve".to_string(),
+            ..fee
+        };
+        let FeesToPay { recipients, .. } = fee.calculate(total_fees, true).unwrap();
+        assert_eq!(
+            amounts(&recipients),
+            vec![Uint128::new(22), Uint128::new(23)]
+        );
+
+        // Without holders, their share is split between the other recipients
+        let FeesToPay {
+            holders,
+            recipients,
+        } = fee.calculate(Uint128::new(151), false).unwrap();
+        assert_eq!(holders, Uint128::zero());
+        assert_eq!(
+            amounts(&recipients),
+            vec![Uint128::new(75), Uint128::new(76)]
+        );
+
+        // Any amount of recipients
+        let fee = Fees {
+            recipients: vec![
+                recipient("holders", FeeRecipientKind::Holders, 5000),
+                recipient(
+                    "dev",
+                    FeeRecipientKind::Wallet {
+                        address: "dev".to_string(),
+                    },
+                    2000,
+                ),
+                recipient(
+                    "charity",
+                    FeeRecipientKind::Wallet {
+                        address: "charity".to_string(),
+                    },
+                    2000,
+                ),
+                recipient("burn", FeeRecipientKind::Burn, 1000),
+            ],
+            remainder_recipient: "dev".to_string(),
+            ..fee
+        };
+        fee.validate(&MockApi::default()).unwrap();
+        let FeesToPay {
+            holders,
+            recipients,
+        } = fee.calculate(Uint128::new(1001), true).unwrap();
+        assert_eq!(holders, Uint128::new(500));
+        assert_eq!(
+            amounts(&recipients),
+            vec![Uint128::new(201), Uint128::new(200), Uint128::new(100)]
+        );
+    }
+
+    #[test]
+    fn test_fee_validation() {
+        let api = MockApi::default();
+        let fee = test_fees();
+        fee.validate(&api).unwrap();
+
+        let mut recipients = fee.recipients.clone();
+        recipients[1].bps = 6999;
+        let err = Fees {
+            recipients,
+            ..fee.clone()
+        }
+        .validate(&api)
+        .unwrap_err();
+        assert_eq!(err, ContractError::InvalidFeesSplit { sum: 9999 });
+
+        let mut recipients = fee.recipients.clone();
+        recipients[2].name = "team".to_string();
+        let err = Fees {
+            recipients,
+            ..fee.clone()
+        }
+        .validate(&api)
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidFeeRecipientName {
+                name: "team".to_string()
+            }
+        );
+
+        let mut recipients = fee.recipients.clone();
+        recipients[0].kind = FeeRecipientKind::Holders;
+        let err = Fees {
+            recipients,
+            ..fee.clone()
+        }
+        .validate(&api)
+        .unwrap_err();
+        assert_eq!(err, ContractError::DuplicateFeeRecipientKind);
 
+        let err = Fees {
+            remainder_recipient: "holders".to_string(),
+            ..fee.clone()
+        }
+        .validate(&api)
+        .unwrap_err();
         assert_eq!(
-            total_fees.checked_sub(Uint128::one()).unwrap(), // this is 149 (150 - 1)
-            team_fees_to_pay // This should be 149 (22 + 22 + 105 = 149)
-                .checked_add(holders_fees_to_pay)
-                .unwrap()
-                .checked_add(reserve_fees_to_pay)
-                .unwrap()
+            err,
+            ContractError::InvalidRemainderRecipient {
+                name: "holders".to_string()
+            }
         );
 
-        println!(
-            "{:?}",
-            team_fees_to_pay
-                .checked_add(holders_fees_to_pay)
-                .unwrap()
-                .checked_add(reserve_fees_to_pay)
-                .unwrap()
-        )
+        let err = Fees {
+            flip_bps: 10001,
+            ..fee
+        }
+        .validate(&api)
+        .unwrap_err();
+        assert_eq!(err, ContractError::InvalidFlipFee { flip_bps: 10001 });
     }
 }
@@ -1,151 +1,865 @@
-use std::collections::HashMap;
-
-use cosmwasm_std::{coins, Addr, BankMsg, Decimal, Deps, DepsMut, Env, Uint128};
+use cosmwasm_std::{
+    coins, ensure, to_vec, Addr, Attribute, BankMsg, Decimal, Deps, DepsMut, Empty, Env, Event,
+    MessageInfo, Order, StdResult, Storage, Uint128,
+};
+use cw_storage_plus::Bound;
+use cw_utils::{one_coin, Duration};
+use serde::Serialize;
 use sg_std::Response;
 
 use crate::error::ContractError;
-use crate::state::{CONFIG, FEES};
-use crate::types::{Config, Fees, FeesToPay};
-
-/// Update the bank limit in the config
-pub fn update_bank_limit(
+use crate::helpers::MAX_BPS;
+use crate::refill;
+use crate::state::{
+    add_holder_earnings, get_liabilities, remove_liability, save_distribution_record,
+    update_house_ledger, CONFIG, DISTRIBUTION_PAYOUTS, DISTRIBUTION_RUN, EXCLUDED_HOLDERS, FEES,
+    HOLDERS_PENDING, HOLDERS_PENDING_TOTAL, INTEGRATOR_CLAIMABLE_TOTAL, LAST_DISTRIBUTION,
+    REFERRAL_CLAIMABLE_TOTAL, STAKED_TOKENS, TODO_FLIPS, VAULT,
+};
+use crate::types::{
+    recipients_total, Config, ConfigUpdate, Distribution, DistributionRecord, DistributionRun,
+    FeeRecipientKind, FeesToPay, HolderPayout, HolderShares, HoldersList, LastDistribution,
+    OptionUpdate, PauseScopes, RecipientPayout,
+};
+
+/// Default amount of holders we pay per `continue_distribution` call
+const DEFAULT_DISTRIBUTION_LIMIT: u32 = 50;
+/// Max amount of holders we pay per `continue_distribution` call
+const MAX_DISTRIBUTION_LIMIT: u32 = 150;
+
+pub fn update_excluded_holders(
     deps: DepsMut,
-    mut config: Config,
-    limit: Uint128,
+    add: Vec<String>,
+    remove: Vec<String>,
 ) -> Result<Response, ContractError> {
-    config.bank_limit = limit;
-    CONFIG.save(deps.storage, &config)?;
+    for addr in add {
+        let addr = deps.api.addr_validate(&addr)?;
+        EXCLUDED_HOLDERS.save(deps.storage, &addr, &Empty {})?;
+    }
+    for addr in remove {
+        let addr = deps.api.addr_validate(&addr)?;
+        EXCLUDED_HOLDERS.remove(deps.storage, &addr);
+    }
 
-    Ok(Response::default().add_attribute("method", "update_bank_limit"))
+    Ok(Response::default().add_attribute("method", "update_excluded_holders"))
 }
 
-pub fn update_fees(
+pub fn validate_max_integrator_bps(max_bps: u64) -> Result<(), ContractError> {
+    ensure!(
+        max_bps <= MAX_BPS,
+        ContractError::IntegratorFeeTooHigh {
+            bps: max_bps,
+            max_bps: MAX_BPS
+        }
+    );
+    Ok(())
+}
+
+/// Add house capital to the bank
+pub fn fund_bank(
     deps: DepsMut,
-    mut config: Config,
-    fees: Fees,
+    info: MessageInfo,
+    config: &Config,
 ) -> Result<Response, ContractError> {
-    config.fees = fees;
-    CONFIG.save(deps.storage, &config)?;
-
-    Ok(Response::default().add_attribute("method", "update_fees"))
+    let funds = one_coin(&info)?;
+    ensure!(
+        config.denoms.contains(&funds.denom),
+        ContractError::WrongDenom { denom: funds.denom }
+    );
+
+    update_house_ledger(deps.storage, &funds.denom, |ledger| {
+        ledger.deposited = ledger.deposited.checked_add(funds.amount)?;
+        Ok(())
+    })?;
+
+    Ok(Response::default()
+        .add_attribute("method", "fund_bank")
+        .add_attribute("amount", funds.to_string()))
 }
 
-pub fn update_sg721(
+/// Remove house capital from the bank.
+///
+/// Fees, LPs capital and what we owe to pending flips are not the house's to withdraw.
+pub fn withdraw_bank(
     deps: DepsMut,
-    mut config: Config,
-    addr: String,
+    env: Env,
+    info: MessageInfo,
+    config: &Config,
+    amount: Uint128,
+    denom: String,
 ) -> Result<Response, ContractError> {
-    config.sg721_addr = Some(deps.api.addr_validate(&addr)?);
-    CONFIG.save(deps.storage, &config)?;
+    ensure!(
+        config.denoms.contains(&denom),
+        ContractError::WrongDenom { denom }
+    );
+
+    let balance = deps
+        .querier
+        .query_balance(&env.contract.address, denom.clone())?;
+    let fees = FEES.load(deps.storage)?;
+    let liabilities = get_liabilities(deps.storage, &denom)?;
+    let lp_capital = VAULT
+        .may_load(deps.storage)?
+        .map_or(Uint128::zero(), |vault| vault.assets);
+    let available = balance
+        .amount
+        .saturating_sub(fees)
+        .saturating_sub(liabilities)
+        .saturating_sub(lp_capital);
+    ensure!(
+        amount <= available,
+        ContractError::WithdrawAboveAvailable { available }
+    );
+
+    update_house_ledger(deps.storage, &denom, |ledger| {
+        ledger.withdrawn = ledger.withdrawn.checked_add(amount)?;
+        Ok(())
+    })?;
+
+    Ok(Response::default()
+        .add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: coins(amount.u128(), denom),
+        })
+        .add_attribute("method", "withdraw_bank")
+        .add_attribute("amount", amount))
+}
+
+pub fn validate_max_bet_bank_bps(bps: Option<u64>) -> Result<(), ContractError> {
+    if let Some(bps) = bps {
+        ensure!(
+            bps > 0 && bps <= MAX_BPS,
+            ContractError::InvalidMaxBetBankBps { bps }
+        );
+    }
+    Ok(())
+}
 
-    Ok(Response::default().add_attribute("method", "update_sg721"))
+pub fn validate_vault_cooldown(cooldown: Duration) -> Result<(), ContractError> {
+    ensure!(
+        cooldown != Duration::Height(0) && cooldown != Duration::Time(0),
+        ContractError::InvalidVaultCooldown
+    );
+    Ok(())
 }
 
-pub fn update_bet_limit(
+/// Removed denoms must not have pending flips, and the first denom (flips, fees and LPs denom)
+/// can only change when there are no pending flips, fees or LPs capital.
+fn validate_denoms(deps: Deps, config: &Config, denoms: &[String]) -> Result<(), ContractError> {
+    for denom in config.denoms.iter().filter(|denom| !denoms.contains(denom)) {
+        ensure!(
+            get_liabilities(deps.storage, denom)?.is_zero(),
+            ContractError::DenomInUse {
+                denom: denom.clone()
+            }
+        );
+    }
+
+    if denoms.first() != config.denoms.first() {
+        let lp_capital = VAULT
+            .may_load(deps.storage)?
+            .map_or(Uint128::zero(), |vault| vault.assets);
+        ensure!(
+            FEES.load(deps.storage)?.is_zero()
+                && lp_capital.is_zero()
+                && get_liabilities(deps.storage, &config.denoms[0])?.is_zero(),
+            ContractError::DenomInUse {
+                denom: config.denoms[0].clone()
+            }
+        );
+    }
+    Ok(())
+}
+
+/// Add the old and new values of a field to the event if it changed
+fn add_diff<T: Serialize + PartialEq>(
+    event: Event,
+    field: &str,
+    old: &T,
+    new: &T,
+) -> StdResult<Event> {
+    if old == new {
+        return Ok(event);
+    }
+    let to_string =
+        |value: &T| to_vec(value).map(|json| String::from_utf8_lossy(&json).into_owned());
+    Ok(event
+        .add_attribute(format!("old_{field}"), to_string(old)?)
+        .add_attribute(format!("new_{field}"), to_string(new)?))
+}
+
+/// Build the config with the update applied, every config change goes through here
+pub fn apply_config_update(
+    deps: Deps,
+    config: Config,
+    update: ConfigUpdate,
+) -> Result<Config, ContractError> {
+    update.validate(deps.api)?;
+    if let Some(denoms) = &update.denoms {
+        validate_denoms(deps, &config, denoms)?;
+    }
+
+    let addr_validate = |addr: OptionUpdate<String>| {
+        addr.into_option()
+            .map(|addr| deps.api.addr_validate(&addr))
+            .transpose()
+    };
+    let new = Config {
+        admin: config.admin,
+        denoms: update.denoms.unwrap_or(config.denoms),
+        bank_limit: update.bank_limit.unwrap_or(config.bank_limit),
+        min_bet_limit: update.min_bet_limit.unwrap_or(config.min_bet_limit),
+        max_bet_limit: update.max_bet_limit.unwrap_or(config.max_bet_limit),
+        max_bet_bank_bps: update
+            .max_bet_bank_bps
+            .map_or(config.max_bet_bank_bps, OptionUpdate::into_option),
+        flips_per_block_limit: update
+            .flips_per_block_limit
+            .unwrap_or(config.flips_per_block_limit),
+        fees: update.fees.unwrap_or(config.fees),
+        sg721_addr: match update.sg721_addr {
+            Some(addr) => addr_validate(addr)?,
+            None => config.sg721_addr,
+        },
+        staking_addr: match update.staking_addr {
+            Some(addr) => addr_validate(addr)?,
+            None => config.staking_addr,
+        },
+        min_holder_payout: update.min_holder_payout.unwrap_or(config.min_holder_payout),
+        max_integrator_bps: update
+            .max_integrator_bps
+            .unwrap_or(config.max_integrator_bps),
+        distribution_schedule: update
+            .distribution_schedule
+            .map_or(config.distribution_schedule, OptionUpdate::into_option),
+        vault_cooldown: update.vault_cooldown.unwrap_or(config.vault_cooldown),
+        treasury: update
+            .treasury
+            .map_or(config.treasury, OptionUpdate::into_option),
+        timelock_delay: update.timelock_delay.unwrap_or(config.timelock_delay),
+        paused: config.paused,
+    };
+    new.validate()?;
+    Ok(new)
+}
+
+pub fn update_config(
     deps: DepsMut,
-    mut config: Config,
-    min_bet: Uint128,
-    max_bet: Uint128,
+    config: Config,
+    update: ConfigUpdate,
 ) -> Result<Response, ContractError> {
-    config.min_bet_limit = min_bet;
-    config.max_bet_limit = max_bet;
-    CONFIG.save(deps.storage, &config)?;
+    let old = config.clone();
+    let new = apply_config_update(deps.as_ref(), config, update)?;
+    CONFIG.save(deps.storage, &new)?;
+    // Tracked stakers belong to the old staking contract
+    if new.staking_addr != old.staking_addr {
+        STAKED_TOKENS.clear(deps.storage);
+    }
 
-    Ok(Response::default().add_attribute("method", "update_bet_limit"))
+    let mut event = Event::new("update_config");
+    event = add_diff(event, "denoms", &old.denoms, &new.denoms)?;
+    event = add_diff(event, "bank_limit", &old.bank_limit, &new.bank_limit)?;
+    event = add_diff(
+        event,
+        "min_bet_limit",
+        &old.min_bet_limit,
+        &new.min_bet_limit,
+    )?;
+    event = add_diff(
+        event,
+        "max_bet_limit",
+        &old.max_bet_limit,
+        &new.max_bet_limit,
+    )?;
+    event = add_diff(
+        event,
+        "max_bet_bank_bps",
+        &old.max_bet_bank_bps,
+        &new.max_bet_bank_bps,
+    )?;
+    event = add_diff(
+        event,
+        "flips_per_block_limit",
+        &old.flips_per_block_limit,
+        &new.flips_per_block_limit,
+    )?;
+    event = add_diff(event, "fees", &old.fees, &new.fees)?;
+    event = add_diff(event, "sg721_addr", &old.sg721_addr, &new.sg721_addr)?;
+    event = add_diff(event, "staking_addr", &old.staking_addr, &new.staking_addr)?;
+    event = add_diff(
+        event,
+        "min_holder_payout",
+        &old.min_holder_payout,
+        &new.min_holder_payout,
+    )?;
+    event = add_diff(
+        event,
+        "max_integrator_bps",
+        &old.max_integrator_bps,
+        &new.max_integrator_bps,
+    )?;
+    event = add_diff(
+        event,
+        "distribution_schedule",
+        &old.distribution_schedule,
+        &new.distribution_schedule,
+    )?;
+    event = add_diff(
+        event,
+        "vault_cooldown",
+        &old.vault_cooldown,
+        &new.vault_cooldown,
+    )?;
+    event = add_diff(event, "treasury", &old.treasury, &new.treasury)?;
+    event = add_diff(
+        event,
+        "timelock_delay",
+        &old.timelock_delay,
+        &new.timelock_delay,
+    )?;
+
+    Ok(Response::default()
+        .add_attribute("method", "update_config")
+        .add_event(event))
 }
 
 pub fn update_pause(
     deps: DepsMut,
     mut config: Config,
-    is_paused: bool,
+    paused: PauseScopes,
 ) -> Result<Response, ContractError> {
-    config.is_paused = is_paused;
+    config.paused = paused;
     CONFIG.save(deps.storage, &config)?;
 
-    Ok(Response::default().add_attribute("method", "update_pause"))
+    Ok(Response::default()
+        .add_attribute("method", "update_pause")
+        .add_attribute("new_flips", paused.new_flips.to_string())
+        .add_attribute("settlement", paused.settlement.to_string())
+        .add_attribute("distribution", paused.distribution.to_string())
+        .add_attribute("withdrawals", paused.withdrawals.to_string()))
 }
 
-pub fn distribute(deps: DepsMut, env: Env, config: &Config) -> Result<Response, ContractError> {
-    let total_fees = FEES.load(deps.storage).unwrap_or_default();
+/// Send back the bets of all pending flips.
+/// Fees, referral and integrator credits of these flips are kept.
+pub fn refund_pending_flips(deps: DepsMut) -> Result<Response, ContractError> {
+    let todo_flips = TODO_FLIPS.load(deps.storage)?;
+    ensure!(!todo_flips.is_empty(), ContractError::NoPendingFlips);
+
+    let mut msgs = Vec::with_capacity(todo_flips.len());
+    for todo_flip in &todo_flips {
+        remove_liability(
+            deps.storage,
+            &todo_flip.amount.denom,
+            todo_flip.amount.amount * Uint128::new(2),
+        )?;
+        msgs.push(BankMsg::Send {
+            to_address: todo_flip.wallet.to_string(),
+            amount: vec![todo_flip.amount.clone()],
+        });
+    }
+    TODO_FLIPS.save(deps.storage, &vec![])?;
 
-    // TODO: Currently we only accept 1 denom
-    let denom = config.denoms[0].clone();
-    let (
-        sg721_addr,
-        FeesToPay {
-            team: team_fees_to_send,
-            holders: holders_fees_to_send,
-            reserve: reserve_fees,
-        },
-    ) = calculate_fees_to_pay(config, total_fees)?;
+    Ok(Response::default()
+        .add_messages(msgs)
+        .add_attribute("method", "refund_pending_flips")
+        .add_attribute("refunded_flips", todo_flips.len().to_string()))
+}
 
-    let reserve_fees_to_send = verify_contract_balance(
-        deps.as_ref(),
-        env,
-        denom.clone(),
+/// Distribute the fees to the fee recipients and holders.
+///
+/// `caller` is set when a scheduled distribution is triggered by anyone,
+/// they get the caller tip.
+pub fn distribute(
+    deps: DepsMut,
+    env: Env,
+    config: &Config,
+    caller: Option<Addr>,
+) -> Result<Response, ContractError> {
+    ensure!(
+        DISTRIBUTION_RUN.may_load(deps.storage)?.is_none(),
+        ContractError::DistributionInProgress
+    );
+
+    let caller_tip_bps = match (&caller, &config.distribution_schedule) {
+        (Some(_), Some(schedule)) => schedule.caller_tip_bps,
+        _ => 0,
+    };
+
+    let Distribution {
+        denom,
         total_fees,
-        reserve_fees,
-        config.bank_limit,
-    )?;
+        recipients,
+        total_shares,
+        fees_per_token,
+        holders_payouts,
+        pay_to_holders: paid_to_holders,
+        holders_pending,
+        rounding_remainder,
+        released_pending,
+        released_holders,
+        caller_tip,
+        ..
+    } = calculate_distribution(deps.as_ref(), env.clone(), config, caller_tip_bps)?;
 
     // Handle holders fees
     let mut msgs: Vec<BankMsg> = vec![];
-    let mut paid_to_holders = Uint128::zero();
-    let mut total_shares = Decimal::zero();
-    let mut fees_per_token = Decimal::zero();
-
-    if !holders_fees_to_send.is_zero() {
-        let (calculated_total_shares, holders_list) = get_holders_list(deps.as_ref(), sg721_addr)?;
-        total_shares = calculated_total_shares;
-
-        fees_per_token =
-            Decimal::from_atomics(holders_fees_to_send, 0)?.checked_div(total_shares)?;
-
-        for (addr, num) in holders_list {
-            let amount = fees_per_token.checked_mul(num)?.to_uint_floor();
-
-            if !amount.is_zero() {
-                msgs.push(BankMsg::Send {
-                    to_address: addr,
-                    amount: coins(amount.into(), denom.clone()),
-                });
-            }
-
-            paid_to_holders = paid_to_holders.checked_add(amount)?;
+    let mut number_of_holders: u64 = 0;
+
+    for payout in holders_payouts {
+        update_holder_pending(deps.storage, &payout)?;
+
+        if !payout.to_pay.is_zero() {
+            add_holder_earnings(
+                deps.storage,
+                &Addr::unchecked(&payout.address),
+                payout.to_pay,
+            )?;
+            msgs.push(BankMsg::Send {
+                to_address: payout.address,
+                amount: coins(payout.to_pay.into(), denom.clone()),
+            });
+            number_of_holders += 1;
         }
     }
+    release_holders_pending(deps.storage, &released_holders);
+    HOLDERS_PENDING_TOTAL.save(deps.storage, &holders_pending)?;
 
-    // create subMsg send to team wallet
-    msgs.push(BankMsg::Send {
-        to_address: config.wallets.team.clone(),
-        amount: coins(team_fees_to_send.into(), denom.clone()),
-    });
+    msgs.extend(recipients_msgs(&denom, &recipients));
 
-    // Send to reserve
-    if !reserve_fees_to_send.is_zero() {
+    if let Some(caller) = caller.filter(|_| !caller_tip.is_zero()) {
         msgs.push(BankMsg::Send {
-            to_address: config.wallets.reserve.clone(),
-            amount: coins(reserve_fees_to_send.into(), denom),
+            to_address: caller.to_string(),
+            amount: coins(caller_tip.into(), denom.clone()),
         });
     }
 
     // calculate remaining fees and save them to state
     let remaining_fees = total_fees
         .checked_sub(paid_to_holders)?
-        .checked_sub(team_fees_to_send)?
-        .checked_sub(reserve_fees)?;
+        .checked_sub(recipients_total(&recipients)?)?
+        .checked_sub(caller_tip)?;
     FEES.save(deps.storage, &remaining_fees)?;
+    save_last_distribution(deps.storage, &env)?;
+
+    let distribution_id = save_distribution_record(
+        deps.storage,
+        DistributionRecord {
+            id: 0,
+            denom,
+            total_fees,
+            recipients: recipients.clone(),
+            holders_paid: paid_to_holders,
+            fees_per_token,
+            total_shares,
+            number_of_holders,
+            caller_tip,
+            timestamp: env.block.time,
+        },
+    )?;
+
+    // Fees were part of the bank balance check, top it up if paying them left it short
+    let refill = refill::auto_refill(
+        deps,
+        &env,
+        config,
+        refill::outgoing_amount(&msgs, &config.denoms[0]),
+    )?;
 
     Ok(Response::new()
         .add_messages(msgs)
+        .add_submessages(refill.messages)
+        .add_events(refill.events)
+        .add_attribute("distribution_id", distribution_id.to_string())
         .add_attribute("total_fees", total_fees)
-        .add_attribute("reserve_paid", reserve_fees)
-        .add_attribute("team_paid", team_fees_to_send)
+        .add_attributes(recipients_attributes(&recipients))
         .add_attribute("holders_paid", paid_to_holders)
+        .add_attribute("holders_pending", holders_pending)
+        .add_attribute("holders_released", released_pending)
+        .add_attribute("rounding_remainder", rounding_remainder)
+        .add_attribute("caller_tip", caller_tip)
+        .add_attribute("fees_per_token", fees_per_token.to_string())
+        .add_attribute("total_shares", total_shares.to_string()))
+}
+
+/// Start a distribution that pays holders over multiple txs.
+///
+/// Fee recipients are paid right away, holders payouts are snapshotted
+/// and paid with `continue_distribution`, so fees collected in between
+/// are left for the next distribution.
+pub fn start_distribution(
+    deps: DepsMut,
+    env: Env,
+    config: &Config,
+) -> Result<Response, ContractError> {
+    ensure!(
+        DISTRIBUTION_RUN.may_load(deps.storage)?.is_none(),
+        ContractError::DistributionInProgress
+    );
+
+    let Distribution {
+        denom,
+        total_fees,
+        holders_total_fee,
+        recipients,
+        total_shares,
+        fees_per_token,
+        holders_payouts,
+        pay_to_holders,
+        holders_pending,
+        released_pending,
+        released_holders,
+        ..
+    } = calculate_distribution(deps.as_ref(), env.clone(), config, 0)?;
+
+    let mut number_of_holders: u64 = 0;
+    for payout in holders_payouts {
+        update_holder_pending(deps.storage, &payout)?;
+
+        if !payout.to_pay.is_zero() {
+            DISTRIBUTION_PAYOUTS.save(deps.storage, &payout.address, &payout.to_pay)?;
+            number_of_holders += 1;
+        }
+    }
+    release_holders_pending(deps.storage, &released_holders);
+    HOLDERS_PENDING_TOTAL.save(deps.storage, &holders_pending)?;
+
+    let run = DistributionRun {
+        denom: denom.clone(),
+        total_fees,
+        recipients: recipients.clone(),
+        holders_total_fee,
+        total_shares,
+        fees_per_token,
+        pay_to_holders,
+        paid_to_holders: Uint128::zero(),
+        number_of_holders,
+        paid_holders: 0,
+        cursor: None,
+        started_at: env.block.time,
+    };
+    // Nothing to pay holders, so there is nothing to continue
+    if number_of_holders > 0 {
+        DISTRIBUTION_RUN.save(deps.storage, &run)?;
+    } else {
+        save_distribution_record(deps.storage, run.to_record(env.block.time))?;
+    }
+
+    // Holders fees stay in FEES until they are paid.
+    let remaining_fees = FEES
+        .load(deps.storage)?
+        .checked_sub(recipients_total(&recipients)?)?;
+    FEES.save(deps.storage, &remaining_fees)?;
+    save_last_distribution(deps.storage, &env)?;
+
+    Ok(Response::new()
+        .add_messages(recipients_msgs(&denom, &recipients))
+        .add_attribute("method", "start_distribution")
+        .add_attribute("total_fees", total_fees)
+        .add_attributes(recipients_attributes(&recipients))
+        .add_attribute("pay_to_holders", pay_to_holders)
+        .add_attribute("holders_released", released_pending)
+        .add_attribute("number_of_holders", number_of_holders.to_string())
         .add_attribute("fees_per_token", fees_per_token.to_string())
         .add_attribute("total_shares", total_shares.to_string()))
 }
 
+/// Pay the next `limit` holders of the running distribution.
+pub fn continue_distribution(
+    deps: DepsMut,
+    env: Env,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let mut run = DISTRIBUTION_RUN
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoDistributionInProgress)?;
+    let limit = limit
+        .unwrap_or(DEFAULT_DISTRIBUTION_LIMIT)
+        .min(MAX_DISTRIBUTION_LIMIT) as usize;
+
+    let start = run.cursor.as_deref().map(Bound::exclusive);
+    let payouts = DISTRIBUTION_PAYOUTS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<(String, Uint128)>>>()?;
+
+    let mut msgs: Vec<BankMsg> = vec![];
+    let mut paid = Uint128::zero();
+    for (addr, amount) in payouts {
+        DISTRIBUTION_PAYOUTS.remove(deps.storage, &addr);
+        add_holder_earnings(deps.storage, &Addr::unchecked(&addr), amount)?;
+        msgs.push(BankMsg::Send {
+            to_address: addr.clone(),
+            amount: coins(amount.into(), run.denom.clone()),
+        });
+
+        paid = paid.checked_add(amount)?;
+        run.paid_holders += 1;
+        run.cursor = Some(addr);
+    }
+    run.paid_to_holders = run.paid_to_holders.checked_add(paid)?;
+
+    let remaining_fees = FEES.load(deps.storage)?.checked_sub(paid)?;
+    FEES.save(deps.storage, &remaining_fees)?;
+
+    let finished = run.paid_holders >= run.number_of_holders;
+    if finished {
+        DISTRIBUTION_RUN.remove(deps.storage);
+        save_distribution_record(deps.storage, run.to_record(env.block.time))?;
+    } else {
+        DISTRIBUTION_RUN.save(deps.storage, &run)?;
+    }
+
+    let config = CONFIG.load(deps.storage)?;
+    let refill = refill::auto_refill(
+        deps,
+        &env,
+        &config,
+        refill::outgoing_amount(&msgs, &config.denoms[0]),
+    )?;
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_submessages(refill.messages)
+        .add_events(refill.events)
+        .add_attribute("method", "continue_distribution")
+        .add_attribute("holders_paid", paid)
+        .add_attribute("paid_holders", run.paid_holders.to_string())
+        .add_attribute("number_of_holders", run.number_of_holders.to_string())
+        .add_attribute("finished", finished.to_string()))
+}
+
+fn save_last_distribution(storage: &mut dyn Storage, env: &Env) -> Result<(), ContractError> {
+    LAST_DISTRIBUTION.save(
+        storage,
+        &LastDistribution {
+            height: env.block.height,
+            time: env.block.time,
+        },
+    )?;
+    Ok(())
+}
+
+fn recipients_msgs(denom: &str, recipients: &[RecipientPayout]) -> Vec<BankMsg> {
+    recipients
+        .iter()
+        .filter(|recipient| !recipient.to_send.is_zero())
+        .filter_map(|recipient| {
+            let amount = coins(recipient.to_send.into(), denom);
+            match &recipient.kind {
+                FeeRecipientKind::Wallet { address } | FeeRecipientKind::Reserve { address } => {
+                    Some(BankMsg::Send {
+                        to_address: address.clone(),
+                        amount,
+                    })
+                }
+                FeeRecipientKind::Burn => Some(BankMsg::Burn { amount }),
+                FeeRecipientKind::Holders => None,
+            }
+        })
+        .collect()
+}
+
+fn recipients_attributes(recipients: &[RecipientPayout]) -> Vec<Attribute> {
+    recipients
+        .iter()
+        .map(|recipient| Attribute::new(format!("{}_paid", recipient.name), recipient.amount))
+        .collect()
+}
+
+/// Fees we can distribute, FEES without the amounts we already owe.
+pub fn get_distributable_fees(storage: &dyn Storage) -> Result<Uint128, ContractError> {
+    let total_fees = FEES.load(storage).unwrap_or_default();
+
+    // Pending holders amounts, holders we still need to pay in a running
+    // distribution and unclaimed referral and integrator fees are kept in FEES,
+    // but they are not ours to distribute again.
+    let holders_pending = HOLDERS_PENDING_TOTAL.may_load(storage)?.unwrap_or_default();
+    let running_distribution = match DISTRIBUTION_RUN.may_load(storage)? {
+        Some(run) => run.pay_to_holders.checked_sub(run.paid_to_holders)?,
+        None => Uint128::zero(),
+    };
+    let referral_claimable = REFERRAL_CLAIMABLE_TOTAL
+        .may_load(storage)?
+        .unwrap_or_default();
+    let integrator_claimable = INTEGRATOR_CLAIMABLE_TOTAL
+        .may_load(storage)?
+        .unwrap_or_default();
+
+    Ok(total_fees
+        .checked_sub(holders_pending)?
+        .checked_sub(running_distribution)?
+        .checked_sub(referral_claimable)?
+        .checked_sub(integrator_claimable)?)
+}
+
+/// Calculate how much each party should get from the collected fees.
+///
+/// This is used by both the dry run query and the actual distribution,
+/// so what we show is always what we pay.
+/// `caller_tip_bps` of the distributable fees is taken before the split.
+pub fn calculate_distribution(
+    deps: Deps,
+    env: Env,
+    config: &Config,
+    caller_tip_bps: u64,
+) -> Result<Distribution, ContractError> {
+    let total_fees = FEES.load(deps.storage).unwrap_or_default();
+
+    let holders_pending_before = HOLDERS_PENDING_TOTAL
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+    let distributable_fees = get_distributable_fees(deps.storage)?;
+    let caller_tip = distributable_fees.multiply_ratio(caller_tip_bps, MAX_BPS);
+    let distributable_fees = distributable_fees.checked_sub(caller_tip)?;
+
+    // TODO: Currently we only accept 1 denom
+    let denom = config.denoms[0].clone();
+    let (sg721_addr, mut fees_to_pay) = calculate_fees_to_pay(config, distributable_fees)?;
+    let holders_total_fee = fees_to_pay.holders;
+
+    let mut holders_payouts: Vec<HolderPayout> = vec![];
+    let mut pay_to_holders = Uint128::zero();
+    let mut holders_earned = Uint128::zero();
+    let mut holders_pending = holders_pending_before;
+    let mut total_shares = Decimal::zero();
+    let mut fees_per_token = Decimal::zero();
+
+    let holders_list = if holders_total_fee.is_zero() {
+        HoldersList::default()
+    } else if config.staking_addr.is_some() {
+        get_stakers_list(deps)?
+    } else {
+        get_holders_list(deps, sg721_addr)?
+    };
+    let excluded_holders = holders_list.excluded_holders.len() as u64;
+
+    // Holders that are not in the list anymore would never cross the min payout,
+    // so their pending amounts are released instead of being kept in FEES forever.
+    let mut released_pending = Uint128::zero();
+    let mut released_holders: Vec<String> = vec![];
+    if !holders_total_fee.is_zero() {
+        for item in HOLDERS_PENDING.range(deps.storage, None, None, Order::Ascending) {
+            let (holder, pending) = item?;
+            if !holders_list.holders.contains_key(holder.as_str()) {
+                released_pending = released_pending.checked_add(pending)?;
+                released_holders.push(holder.into_string());
+            }
+        }
+        holders_pending = holders_pending.checked_sub(released_pending)?;
+    }
+
+    // If we have no holders to pay, everything goes to the remainder recipient
+    if !holders_list.total_shares.is_zero() {
+        total_shares = holders_list.total_shares;
+
+        fees_per_token = Decimal::from_atomics(holders_total_fee, 0)?.checked_div(total_shares)?;
+
+        for (address, HolderShares { shares, token_ids }) in holders_list.holders {
+            let amount = fees_per_token.checked_mul(shares)?.to_uint_floor();
+            let carried = HOLDERS_PENDING
+                .may_load(deps.storage, &Addr::unchecked(&address))?
+                .unwrap_or_default();
+
+            // Only pay the holder if the amount is above our min payout,
+            // else we keep it pending until the next distribution.
+            let total_amount = amount.checked_add(carried)?;
+            let to_pay = if !total_amount.is_zero() && total_amount >= config.min_holder_payout {
+                holders_pending = holders_pending.checked_sub(carried)?;
+                total_amount
+            } else {
+                holders_pending = holders_pending.checked_add(amount)?;
+                Uint128::zero()
+            };
+
+            holders_earned = holders_earned.checked_add(amount)?;
+            pay_to_holders = pay_to_holders.checked_add(to_pay)?;
+            holders_payouts.push(HolderPayout {
+                address,
+                shares,
+                token_ids,
+                amount,
+                carried,
+                to_pay,
+            });
+        }
+    }
+
+    // Give what holders couldn't get because of flooring to the remainder recipient,
+    // so we distribute exactly the distributable fees.
+    let rounding_remainder = holders_total_fee.checked_sub(holders_earned)?;
+    fees_to_pay.add_remainder(
+        &config.fees.remainder_recipient,
+        rounding_remainder.checked_add(released_pending)?,
+    )?;
+
+    let FeesToPay { mut recipients, .. } = fees_to_pay;
+
+    // Reserve fees are kept in the bank while it's under the bank limit,
+    // LPs capital and pending flips liabilities are not ours so they don't count toward it.
+    let lp_capital = VAULT
+        .may_load(deps.storage)?
+        .map_or(Uint128::zero(), |vault| vault.assets);
+    let mut reserve = recipients
+        .iter_mut()
+        .find(|recipient| matches!(recipient.kind, FeeRecipientKind::Reserve { .. }));
+    let reserve_to_send = verify_contract_balance(
+        deps,
+        env,
+        denom.clone(),
+        total_fees,
+        reserve
+            .as_ref()
+            .map_or(Uint128::zero(), |reserve| reserve.amount),
+        lp_capital,
+        config.bank_limit,
+    )?;
+    if let Some(reserve) = reserve.as_mut() {
+        reserve.to_send = reserve_to_send;
+    }
+
+    Ok(Distribution {
+        denom,
+        total_fees,
+        recipients,
+        holders_total_fee,
+        total_shares,
+        fees_per_token,
+        holders_payouts,
+        pay_to_holders,
+        holders_pending,
+        rounding_remainder,
+        released_pending,
+        released_holders,
+        excluded_holders,
+        caller_tip,
+    })
+}
+
+/// Update the holder pending balance after a payout
+fn update_holder_pending(
+    storage: &mut dyn Storage,
+    payout: &HolderPayout,
+) -> Result<(), ContractError> {
+    let holder = Addr::unchecked(&payout.address);
+
+    if !payout.to_pay.is_zero() {
+        HOLDERS_PENDING.remove(storage, &holder);
+    } else {
+        let pending = payout.carried.checked_add(payout.amount)?;
+        if !pending.is_zero() {
+            HOLDERS_PENDING.save(storage, &holder, &pending)?;
+        }
+    }
+    Ok(())
+}
+
+/// Remove the pending balances that were given to the remainder recipient
+fn release_holders_pending(storage: &mut dyn Storage, holders: &[String]) {
+    for holder in holders {
+        HOLDERS_PENDING.remove(storage, &Addr::unchecked(holder));
+    }
+}
+
 pub fn calculate_fees_to_pay(
     config: &Config,
     total_fees: Uint128,
@@ -156,20 +870,17 @@ pub fn calculate_fees_to_pay(
     }
 
     // If we have sg721_addr, it means we have a collection we need to distribute to
-    // the holders. If not, we distribute to the team and reserve 50/50.
-    if let Some(sg721_addr) = config.sg721_addr.clone() {
-        Ok((sg721_addr, config.fees.calculate(total_fees)))
-    } else {
-        let half = total_fees.checked_div(Uint128::new(2))?;
-        Ok((
-            Addr::unchecked("sg721"),
-            FeesToPay {
-                team: half,
-                holders: Uint128::zero(),
-                reserve: half,
-            },
-        ))
-    }
+    // the holders. If not, the holders share is split between the other recipients.
+    let fees_to_pay = config
+        .fees
+        .calculate(total_fees, config.sg721_addr.is_some())?;
+    Ok((
+        config
+            .sg721_addr
+            .clone()
+            .unwrap_or_else(|| Addr::unchecked("sg721")),
+        fees_to_pay,
+    ))
 }
 
 pub fn verify_contract_balance(
@@ -178,14 +889,18 @@ pub fn verify_contract_balance(
     denom: String,
     total_fees: Uint128,
     reserve_fees: Uint128,
+    lp_capital: Uint128,
     bank_limit: Uint128,
 ) -> Result<Uint128, ContractError> {
     let mut reserve_fees_to_send = reserve_fees;
+    let liabilities = get_liabilities(deps.storage, &denom)?;
     let contract_balance = deps.querier.query_balance(env.contract.address, denom)?;
     let bank_balance = contract_balance
         .amount
         .checked_sub(total_fees)
-        .map_err(|_| ContractError::NotEnoughFundsToPayFees)?;
+        .map_err(|_| ContractError::NotEnoughFundsToPayFees)?
+        .saturating_sub(liabilities)
+        .saturating_sub(lp_capital);
 
     if bank_balance < bank_limit {
         // How much we need to reach to the minimum bank amount.
@@ -196,18 +911,14 @@ pub fn verify_contract_balance(
             reserve_fees_to_send = Uint128::zero();
         } else {
             // If we need less then we have, we send the difference.
-            reserve_fees_to_send.checked_sub(reserve_diff)?;
+            reserve_fees_to_send = reserve_fees_to_send.checked_sub(reserve_diff)?;
         }
     }
     Ok(reserve_fees_to_send)
 }
 
-pub fn get_holders_list(
-    deps: Deps,
-    sg721_addr: Addr,
-) -> Result<(Decimal, HashMap<String, Decimal>), ContractError> {
-    let mut total_shares = Decimal::zero();
-    let mut holders_list: HashMap<String, Decimal> = HashMap::new();
+pub fn get_holders_list(deps: Deps, sg721_addr: Addr) -> Result<HoldersList, ContractError> {
+    let mut holders_list = HoldersList::default();
 
     for num in 1..=777 {
         // Get the owner of the token.
@@ -220,16 +931,51 @@ pub fn get_holders_list(
         );
 
         if let Ok(res) = owner_addr {
-            let rewards_share = get_share(num)?;
-            total_shares = total_shares.checked_add(rewards_share)?;
-
-            holders_list
-                .entry(res.owner)
-                .and_modify(|e| *e += rewards_share)
-                .or_insert(rewards_share);
+            add_to_holders_list(deps, &mut holders_list, res.owner, num)?;
         }
     }
-    Ok((total_shares, holders_list))
+    Ok(holders_list)
+}
+
+/// Same as `get_holders_list` but only counts NFTs staked in the staking contract,
+/// so owners that only list their NFTs on marketplaces are not paid.
+///
+/// Stakers are tracked from the stake hooks, see `staking::stake_changed`.
+pub fn get_stakers_list(deps: Deps) -> Result<HoldersList, ContractError> {
+    let mut holders_list = HoldersList::default();
+
+    for item in STAKED_TOKENS.range(deps.storage, None, None, Order::Ascending) {
+        let (token_id, staker) = item?;
+        let num = token_id
+            .parse::<u32>()
+            .map_err(|_| ContractError::InvalidTokenId {
+                token_id: token_id.clone(),
+            })?;
+        add_to_holders_list(deps, &mut holders_list, staker.into_string(), num)?;
+    }
+    Ok(holders_list)
+}
+
+/// Add the token shares to the owner, unless the owner is excluded from distribution.
+fn add_to_holders_list(
+    deps: Deps,
+    holders_list: &mut HoldersList,
+    owner: String,
+    num: u32,
+) -> Result<(), ContractError> {
+    // Excluded holders share is split between everyone else
+    if EXCLUDED_HOLDERS.has(deps.storage, &Addr::unchecked(&owner)) {
+        holders_list.excluded_holders.insert(owner);
+        return Ok(());
+    }
+
+    let rewards_share = get_share(num)?;
+    holders_list.total_shares = holders_list.total_shares.checked_add(rewards_share)?;
+
+    let holder = holders_list.holders.entry(owner).or_default();
+    holder.shares = holder.shares.checked_add(rewards_share)?;
+    holder.token_ids.push(num.to_string());
+    Ok(())
 }
 
 pub fn get_share(num: u32) -> Result<Decimal, ContractError> {
@@ -2,4 +2,14 @@ mod utils;
 
 mod test_contract;
 mod test_distribute;
+mod test_governance;
+mod test_integrator;
+mod test_migrate;
+mod test_multisig;
+mod test_ownership;
 mod test_queries;
+mod test_referral;
+mod test_refill;
+mod test_roles;
+mod test_timelock;
+mod test_vault;
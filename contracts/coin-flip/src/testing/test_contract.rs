@@ -4,19 +4,32 @@ use cw_multi_test::Executor;
 use crate::{
     contract::{MAX_BET, MIN_BET},
     error::ContractError,
-    msg::{ExecuteMsg, FlipExecuteMsg, SudoMsg},
-    testing::utils::{executes::sudo_update_pause, helpers::MIN_FUNDS},
-    types::{Fees, Flip, FlipScore, PickTypes, Streak},
+    msg::{ExecuteMsg, FlipExecuteMsg, QueryMsg, SudoMsg},
+    testing::utils::{
+        executes::sudo_update_pause,
+        helpers::{MIN_FEES, MIN_FUNDS},
+    },
+    types::{
+        ConfigChange, ConfigUpdate, Fees, Flip, FlipFeeTier, FlipScore, OptionUpdate, PauseScopes,
+        PickTypes, Role, Streak,
+    },
 };
 
 use super::utils::{
     executes::{
-        execute_do_flips, execute_start_flip, sudo_update_bank_limit, sudo_update_bet_limit,
-        sudo_update_fees, unwrap_execute,
+        apply_config_change, execute_do_flips, execute_start_flip, execute_sudo, sudo_distribute,
+        sudo_fund_bank, sudo_grant_role, sudo_update_bank_limit, sudo_update_bet_limit,
+        sudo_update_fees, sudo_update_max_bet_bank_bps, sudo_withdraw_bank, unwrap_execute,
+    },
+    helpers::{add_10_todo_flips, add_balance},
+    queries::{
+        query_config, query_effective_limits, query_flip_quote, query_house_ledger,
+        query_last_flips, query_score, query_solvency,
+    },
+    setup::{
+        fee_recipients, next_block, setup_base_contract, setup_contract, CREATOR_ADDR,
+        FLIPPER_ADDR, FLIPPER_ADDR2, NATIVE_DENOM, PLUS_NANOS,
     },
-    helpers::add_10_todo_flips,
-    queries::{query_config, query_last_flips, query_score},
-    setup::{setup_base_contract, FLIPPER_ADDR, FLIPPER_ADDR2, NATIVE_DENOM, PLUS_NANOS},
 };
 
 #[test]
@@ -146,14 +159,77 @@ fn test_update_bank_limit() {
     assert_eq!(config.bank_limit, new_limit);
 }
 
+#[test]
+fn test_fund_and_withdraw_bank() {
+    let (mut app, contract_addr) = setup_base_contract();
+    let admin = Addr::unchecked(CREATOR_ADDR);
+    let deposit = Uint128::new(1000000000);
+    add_balance(&mut app, admin.clone(), deposit.u128());
+
+    let err = sudo_fund_bank(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        deposit,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized);
+
+    sudo_fund_bank(&mut app, contract_addr.clone(), admin.clone(), deposit).unwrap();
+    let ledger = query_house_ledger(&app, contract_addr.clone(), NATIVE_DENOM).unwrap();
+    assert_eq!(ledger.deposited, deposit);
+
+    execute_start_flip(
+        &mut app,
+        contract_addr.clone(),
+        PickTypes::Heads,
+        MIN_BET,
+        Addr::unchecked(FLIPPER_ADDR),
+        MIN_FUNDS,
+    )
+    .unwrap();
+
+    // Cannot withdraw the fees and what we owe to the pending flip
+    let available =
+        Uint128::new(30000000000) + deposit + MIN_FUNDS - MIN_FEES - MIN_BET * Uint128::new(2);
+    let err = sudo_withdraw_bank(
+        &mut app,
+        contract_addr.clone(),
+        available + Uint128::one(),
+        NATIVE_DENOM,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::WithdrawAboveAvailable { available });
+
+    let err = sudo_withdraw_bank(&mut app, contract_addr.clone(), deposit, "random").unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::WrongDenom {
+            denom: "random".to_string()
+        }
+    );
+
+    sudo_withdraw_bank(&mut app, contract_addr.clone(), deposit, NATIVE_DENOM).unwrap();
+    let balance = app.wrap().query_balance(admin, NATIVE_DENOM).unwrap();
+    assert_eq!(balance.amount, deposit);
+
+    // Flip result is realized in the ledger
+    execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+    let ledger = query_house_ledger(&app, contract_addr, NATIVE_DENOM).unwrap();
+    assert_eq!(ledger.withdrawn, deposit);
+    assert_eq!(ledger.flip_profit + ledger.flip_loss, MIN_BET);
+}
+
 #[test]
 fn test_update_config_fees() {
     let (mut app, contract_addr) = setup_base_contract();
     let new_fees = Fees {
-        team_bps: 1000,
-        holders_bps: 8000,
-        reserve_bps: 1000,
+        recipients: fee_recipients(1000, 8000, 1000),
         flip_bps: 400,
+        flip_fee_tiers: vec![],
+        holder_discount_bps: 0,
+        referral_bps: 0,
+        remainder_recipient: "team".to_string(),
     };
 
     sudo_update_fees(&mut app, contract_addr.clone(), new_fees.clone()).unwrap();
@@ -163,6 +239,26 @@ fn test_update_config_fees() {
     assert_eq!(config.fees, new_fees);
 }
 
+#[test]
+fn test_update_config_invalid_fees() {
+    let (mut app, contract_addr) = setup_base_contract();
+    let new_fees = Fees {
+        recipients: fee_recipients(1000, 8000, 1500),
+        flip_bps: 400,
+        flip_fee_tiers: vec![],
+        holder_discount_bps: 0,
+        referral_bps: 0,
+        remainder_recipient: "reserve".to_string(),
+    };
+
+    let err = sudo_update_fees(&mut app, contract_addr.clone(), new_fees).unwrap_err();
+    assert_eq!(err, ContractError::InvalidFeesSplit { sum: 10500 });
+
+    // Config wasn't changed
+    let config = query_config(&app, contract_addr).unwrap();
+    assert_eq!(config.fees.recipients, fee_recipients(1500, 7000, 1500));
+}
+
 #[test]
 fn test_flips_per_block_limit() {
     let (mut app, contract_addr) = setup_base_contract();
@@ -204,6 +300,7 @@ fn test_wrong_funds() {
         &ExecuteMsg::Flip(FlipExecuteMsg::StartFlip {
             pick: PickTypes::Heads,
             amount: MIN_BET,
+            integrator: None,
         }),
         &[coin(MIN_FUNDS.u128(), NATIVE_DENOM), coin(1, "random")],
     ))
@@ -211,12 +308,44 @@ fn test_wrong_funds() {
 
     assert_eq!(err, ContractError::WrongFundsAmount);
 
+    let err = unwrap_execute(app.execute_contract(
+        Addr::unchecked(FLIPPER_ADDR),
+        contract_addr.clone(),
+        &ExecuteMsg::Flip(FlipExecuteMsg::StartFlip {
+            pick: PickTypes::Heads,
+            amount: MIN_BET,
+            integrator: None,
+        }),
+        &coins(MIN_FUNDS.u128(), "random"),
+    ))
+    .unwrap_err();
+
+    assert_eq!(
+        err,
+        ContractError::WrongDenom {
+            denom: "random".to_string()
+        }
+    );
+
+    // Only the first denom can be flipped, even if more are configured
+    apply_config_change(
+        &mut app,
+        contract_addr.clone(),
+        ConfigChange::UpdateConfig {
+            update: Box::new(ConfigUpdate {
+                denoms: Some(vec![NATIVE_DENOM.to_string(), "random".to_string()]),
+                ..Default::default()
+            }),
+        },
+    )
+    .unwrap();
     let err = unwrap_execute(app.execute_contract(
         Addr::unchecked(FLIPPER_ADDR),
         contract_addr,
         &ExecuteMsg::Flip(FlipExecuteMsg::StartFlip {
             pick: PickTypes::Heads,
             amount: MIN_BET,
+            integrator: None,
         }),
         &coins(MIN_FUNDS.u128(), "random"),
     ))
@@ -237,13 +366,18 @@ fn test_sudo_unauth() {
     let err = unwrap_execute(app.execute_contract(
         Addr::unchecked("random"),
         contract_addr,
-        &ExecuteMsg::Sudo(SudoMsg::UpdateFees {
-            fees: Fees {
-                team_bps: 1500,
-                holders_bps: 7000,
-                reserve_bps: 1500,
-                flip_bps: 300,
+        &ExecuteMsg::Sudo(SudoMsg::ProposeConfigChange {
+            change: ConfigChange::UpdateFees {
+                fees: Fees {
+                    recipients: fee_recipients(1500, 7000, 1500),
+                    flip_bps: 300,
+                    flip_fee_tiers: vec![],
+                    holder_discount_bps: 0,
+                    referral_bps: 0,
+                    remainder_recipient: "team".to_string(),
+                },
             },
+            effective_at: app.block_info().time,
         }),
         &[],
     ))
@@ -297,6 +431,188 @@ fn test_max_min_amounts() {
     );
 }
 
+#[test]
+fn test_effective_max_bet() {
+    let (mut app, contract_addr) = setup_base_contract();
+
+    // Without a policy, max bet is the static limit
+    let limits = query_effective_limits(&app, contract_addr.clone(), NATIVE_DENOM).unwrap();
+    assert_eq!(limits.max_bet, MAX_BET);
+    assert_eq!(limits.available_bank, Uint128::new(30000000000));
+
+    query_effective_limits(&app, contract_addr.clone(), "random").unwrap_err();
+
+    let err = sudo_update_max_bet_bank_bps(&mut app, contract_addr.clone(), Some(0)).unwrap_err();
+    assert_eq!(err, ContractError::InvalidMaxBetBankBps { bps: 0 });
+
+    // 0.05% of the bank (30k) is 15
+    sudo_update_max_bet_bank_bps(&mut app, contract_addr.clone(), Some(5)).unwrap();
+    let limits = query_effective_limits(&app, contract_addr.clone(), NATIVE_DENOM).unwrap();
+    assert_eq!(limits.max_bet, Uint128::new(15000000));
+
+    let err = execute_start_flip(
+        &mut app,
+        contract_addr.clone(),
+        PickTypes::Heads,
+        Uint128::new(20000000),
+        Addr::unchecked(FLIPPER_ADDR),
+        Uint128::new(20700000),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::OverTheLimitBet {
+            max_limit: "15".to_string()
+        }
+    );
+
+    // Pending flips are not part of the available bank
+    execute_start_flip(
+        &mut app,
+        contract_addr.clone(),
+        PickTypes::Heads,
+        MIN_BET,
+        Addr::unchecked(FLIPPER_ADDR),
+        MIN_FUNDS,
+    )
+    .unwrap();
+    let limits = query_effective_limits(&app, contract_addr.clone(), NATIVE_DENOM).unwrap();
+    assert_eq!(
+        limits.available_bank,
+        Uint128::new(30000000000) + MIN_FUNDS - MIN_FEES - MIN_BET * Uint128::new(2)
+    );
+
+    // Static limit still caps the max bet
+    sudo_update_max_bet_bank_bps(&mut app, contract_addr.clone(), Some(10000)).unwrap();
+    let limits = query_effective_limits(&app, contract_addr, NATIVE_DENOM).unwrap();
+    assert_eq!(limits.max_bet, MAX_BET);
+}
+
+#[test]
+fn test_pending_flips_liabilities() {
+    let (mut app, contract_addr) = setup_base_contract();
+    // Bank can pay 1 win, but not 2
+    add_balance(&mut app, contract_addr.clone(), 9000000);
+
+    execute_start_flip(
+        &mut app,
+        contract_addr.clone(),
+        PickTypes::Heads,
+        MIN_BET,
+        Addr::unchecked(FLIPPER_ADDR),
+        MIN_FUNDS,
+    )
+    .unwrap();
+
+    let solvency = query_solvency(&app, contract_addr.clone(), NATIVE_DENOM).unwrap();
+    assert_eq!(solvency.balance, Uint128::new(9000000) + MIN_FUNDS);
+    assert_eq!(solvency.fees, MIN_FEES);
+    assert_eq!(solvency.liabilities, MIN_BET * Uint128::new(2));
+    assert!(solvency.is_solvent);
+
+    // Each bet is covered by itself, but not together with the pending one
+    let err = execute_start_flip(
+        &mut app,
+        contract_addr.clone(),
+        PickTypes::Heads,
+        MIN_BET,
+        Addr::unchecked(FLIPPER_ADDR2),
+        MIN_FUNDS,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::ContractMissingFunds);
+
+    execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+    let solvency = query_solvency(&app, contract_addr, NATIVE_DENOM).unwrap();
+    assert_eq!(solvency.liabilities, Uint128::zero());
+    assert!(solvency.is_solvent);
+}
+
+#[test]
+fn test_update_config() {
+    let (mut app, contract_addr) = setup_base_contract();
+
+    let err = apply_config_change(
+        &mut app,
+        contract_addr.clone(),
+        ConfigChange::UpdateConfig {
+            update: Box::new(ConfigUpdate {
+                denoms: Some(vec![]),
+                ..Default::default()
+            }),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NoDenoms);
+
+    // Can't remove a denom while flips in it are pending
+    execute_start_flip(
+        &mut app,
+        contract_addr.clone(),
+        PickTypes::Heads,
+        MIN_BET,
+        Addr::unchecked(FLIPPER_ADDR),
+        MIN_FUNDS,
+    )
+    .unwrap();
+    let err = apply_config_change(
+        &mut app,
+        contract_addr.clone(),
+        ConfigChange::UpdateConfig {
+            update: Box::new(ConfigUpdate {
+                denoms: Some(vec!["other_denom".to_string()]),
+                ..Default::default()
+            }),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::DenomInUse {
+            denom: NATIVE_DENOM.to_string()
+        }
+    );
+
+    let res = apply_config_change(
+        &mut app,
+        contract_addr.clone(),
+        ConfigChange::UpdateConfig {
+            update: Box::new(ConfigUpdate {
+                denoms: Some(vec![NATIVE_DENOM.to_string(), "other_denom".to_string()]),
+                flips_per_block_limit: Some(5),
+                max_bet_bank_bps: Some(OptionUpdate::Set(100)),
+                staking_addr: Some(OptionUpdate::Unset),
+                ..Default::default()
+            }),
+        },
+    )
+    .unwrap();
+
+    let config = query_config(&app, contract_addr).unwrap();
+    assert_eq!(config.denoms.len(), 2);
+    assert_eq!(config.flips_per_block_limit, 5);
+    assert_eq!(config.max_bet_bank_bps, Some(100));
+
+    // Only changed fields are in the event
+    let event = res
+        .events
+        .iter()
+        .find(|event| event.ty == "wasm-update_config")
+        .unwrap();
+    let attr = |key: &str| {
+        event
+            .attributes
+            .iter()
+            .find(|attr| attr.key == key)
+            .map(|attr| attr.value.clone())
+    };
+    assert_eq!(attr("old_flips_per_block_limit"), Some("10".to_string()));
+    assert_eq!(attr("new_flips_per_block_limit"), Some("5".to_string()));
+    assert_eq!(attr("old_max_bet_bank_bps"), Some("null".to_string()));
+    assert_eq!(attr("new_max_bet_bank_bps"), Some("100".to_string()));
+    assert_eq!(attr("old_staking_addr"), None);
+}
+
 #[test]
 fn test_no_flip_same_block() {
     let (mut app, contract_addr) = setup_base_contract();
@@ -409,6 +725,138 @@ fn test_contract_is_paused() {
     execute_do_flips(&mut app, contract_addr).unwrap();
 }
 
+#[test]
+fn test_pause_scopes() {
+    let (mut app, contract_addr) = setup_base_contract();
+    let flipper = Addr::unchecked(FLIPPER_ADDR);
+    let pauser = Addr::unchecked(FLIPPER_ADDR2);
+    sudo_grant_role(&mut app, contract_addr.clone(), Role::Pauser, FLIPPER_ADDR2).unwrap();
+
+    execute_start_flip(
+        &mut app,
+        contract_addr.clone(),
+        PickTypes::Heads,
+        MIN_BET,
+        flipper.clone(),
+        MIN_FUNDS,
+    )
+    .unwrap();
+
+    let new_flips_paused = PauseScopes {
+        new_flips: true,
+        ..Default::default()
+    };
+    execute_sudo(
+        &mut app,
+        contract_addr.clone(),
+        pauser.clone(),
+        SudoMsg::UpdatePauseScopes {
+            scopes: new_flips_paused,
+        },
+    )
+    .unwrap();
+
+    app.update_block(next_block);
+    let err = execute_start_flip(
+        &mut app,
+        contract_addr.clone(),
+        PickTypes::Heads,
+        MIN_BET,
+        flipper,
+        MIN_FUNDS,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Paused);
+
+    // Paid bets are still settled
+    execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+    let solvency = query_solvency(&app, contract_addr.clone(), NATIVE_DENOM).unwrap();
+    assert_eq!(solvency.liabilities, Uint128::zero());
+
+    // Pauser can pause more, but not less
+    execute_sudo(
+        &mut app,
+        contract_addr.clone(),
+        pauser.clone(),
+        SudoMsg::UpdatePauseScopes {
+            scopes: PauseScopes {
+                distribution: true,
+                ..new_flips_paused
+            },
+        },
+    )
+    .unwrap();
+    let err = sudo_distribute(&mut app, contract_addr.clone()).unwrap_err();
+    assert_eq!(err, ContractError::Paused);
+
+    let err = execute_sudo(
+        &mut app,
+        contract_addr.clone(),
+        pauser,
+        SudoMsg::UpdatePauseScopes {
+            scopes: new_flips_paused,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized);
+}
+
+#[test]
+fn test_refund_pending_flips() {
+    let (mut app, contract_addr) = setup_base_contract();
+    let admin = Addr::unchecked(CREATOR_ADDR);
+    let flipper = Addr::unchecked(FLIPPER_ADDR);
+
+    let err = execute_sudo(
+        &mut app,
+        contract_addr.clone(),
+        admin.clone(),
+        SudoMsg::RefundPendingFlips {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NoPendingFlips);
+
+    let balance_before = app
+        .wrap()
+        .query_balance(&flipper, NATIVE_DENOM)
+        .unwrap()
+        .amount;
+    execute_start_flip(
+        &mut app,
+        contract_addr.clone(),
+        PickTypes::Heads,
+        MIN_BET,
+        flipper.clone(),
+        MIN_FUNDS,
+    )
+    .unwrap();
+    sudo_update_pause(&mut app, contract_addr.clone(), true).unwrap();
+
+    execute_sudo(
+        &mut app,
+        contract_addr.clone(),
+        admin,
+        SudoMsg::RefundPendingFlips {},
+    )
+    .unwrap();
+
+    // Bet is sent back, the fee is kept
+    let balance_after = app
+        .wrap()
+        .query_balance(&flipper, NATIVE_DENOM)
+        .unwrap()
+        .amount;
+    assert_eq!(balance_before - balance_after, MIN_FEES);
+    let solvency = query_solvency(&app, contract_addr.clone(), NATIVE_DENOM).unwrap();
+    assert_eq!(solvency.liabilities, Uint128::zero());
+
+    let should_do_flips: bool = app
+        .wrap()
+        .query_wasm_smart(contract_addr, &QueryMsg::ShouldDoFlips {})
+        .unwrap();
+    assert!(!should_do_flips);
+}
+
 #[test]
 fn test_update_bet_limit() {
     let (mut app, contract_addr) = setup_base_contract();
@@ -448,3 +896,106 @@ fn test_update_bet_limit() {
         }
     );
 }
+
+#[test]
+fn test_flip_fee_tiers_and_holder_discount() {
+    let (mut app, contract_addr) = setup_contract();
+    let config = query_config(&app, contract_addr.clone()).unwrap();
+
+    sudo_update_fees(
+        &mut app,
+        contract_addr.clone(),
+        Fees {
+            flip_fee_tiers: vec![
+                FlipFeeTier {
+                    min_amount: Uint128::new(10_000_000),
+                    flip_bps: 300,
+                },
+                FlipFeeTier {
+                    min_amount: Uint128::new(20_000_000),
+                    flip_bps: 250,
+                },
+            ],
+            holder_discount_bps: 2000, // 20% off
+            ..config.fees
+        },
+    )
+    .unwrap();
+
+    // Base fee under the first tier
+    let quote = query_flip_quote(&app, contract_addr.clone(), MIN_BET, None, None).unwrap();
+    assert_eq!(quote.fee_bps, 350);
+    assert_eq!(quote.fee, Uint128::new(175_000));
+    assert_eq!(quote.total, MIN_FUNDS);
+
+    let quote = query_flip_quote(
+        &app,
+        contract_addr.clone(),
+        Uint128::new(10_000_000),
+        None,
+        None,
+    )
+    .unwrap();
+    assert_eq!(quote.fee_bps, 300);
+    assert_eq!(quote.fee, Uint128::new(300_000));
+
+    let quote = query_flip_quote(&app, contract_addr.clone(), MAX_BET, None, None).unwrap();
+    assert_eq!(quote.fee_bps, 250);
+
+    // flipper-1 holds an NFT so gets the discount (350 * 0.8 = 280)
+    let quote = query_flip_quote(
+        &app,
+        contract_addr.clone(),
+        MIN_BET,
+        Some("flipper-1".to_string()),
+        None,
+    )
+    .unwrap();
+    assert!(quote.is_holder);
+    assert_eq!(quote.fee_bps, 280);
+    assert_eq!(quote.fee, Uint128::new(140_000));
+
+    execute_start_flip(
+        &mut app,
+        contract_addr.clone(),
+        PickTypes::Heads,
+        MIN_BET,
+        Addr::unchecked("flipper-1"),
+        quote.total,
+    )
+    .unwrap();
+
+    // Non holder must pay the full fee
+    let err = execute_start_flip(
+        &mut app,
+        contract_addr.clone(),
+        PickTypes::Heads,
+        MIN_BET,
+        Addr::unchecked(FLIPPER_ADDR),
+        quote.total,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::WrongPaidAmount);
+
+    // Tiers must be sorted
+    let fees = query_config(&app, contract_addr.clone()).unwrap().fees;
+    let err = sudo_update_fees(
+        &mut app,
+        contract_addr,
+        Fees {
+            flip_fee_tiers: vec![
+                FlipFeeTier {
+                    min_amount: Uint128::new(20_000_000),
+                    flip_bps: 250,
+                },
+                FlipFeeTier {
+                    min_amount: Uint128::new(10_000_000),
+                    flip_bps: 300,
+                },
+            ],
+            ..fees
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidFlipFeeTiers);
+}
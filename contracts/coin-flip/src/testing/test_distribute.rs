@@ -1,22 +1,38 @@
-use cosmwasm_std::{coin, coins, Decimal, Uint128};
+use cosmwasm_std::{coin, coins, Addr, Decimal, Empty, Uint128};
 use cw_multi_test::Executor;
+use cw_utils::{Duration, Expiration};
 
 use crate::{
+    contract::MIN_BET,
     error::ContractError,
-    msg::DryDistributionResponse,
+    msg::{DryDistributionResponse, ExecuteMsg, StakeChangedHookMsg},
     state::FEES,
     testing::utils::{
-        helpers::{get_dist_result, update_storage, MIN_FEES},
-        queries::query_dry_distribution,
-        setup::setup_base_contract,
+        helpers::{get_dist_result, update_storage, MIN_FEES, MIN_FUNDS},
+        queries::{
+            query_distribution_status, query_distributions, query_dry_distribution,
+            query_dry_distribution_holders, query_excluded_holders, query_next_distribution,
+        },
+        setup::{setup_base_contract, FLIPPER_ADDR},
+    },
+    types::{
+        DistributionSchedule, FeeRecipient, FeeRecipientKind, Fees, PickTypes, RecipientPayout,
     },
 };
 
 use super::utils::{
-    executes::{execute_do_flips, sudo_distribute},
-    helpers::{add_10_todo_flips, add_balance},
-    queries::query_fees,
-    setup::{setup_contract, NATIVE_DENOM, RESERVE_ADDR, TEAM_ADDR},
+    executes::{
+        execute_distribute, execute_do_flips, execute_start_flip, sudo_continue_distribution,
+        sudo_distribute, sudo_start_distribution, sudo_update_distribution_schedule,
+        sudo_update_excluded_holders, sudo_update_fees, sudo_update_min_holder_payout,
+        sudo_update_staking, unwrap_execute,
+    },
+    helpers::{add_10_todo_flips, add_balance, stake_nft},
+    mocks::{staking_contract, MockStakingExecuteMsg},
+    queries::{query_config, query_fees},
+    setup::{
+        setup_contract, setup_staking_contract, CREATOR_ADDR, NATIVE_DENOM, RESERVE_ADDR, TEAM_ADDR,
+    },
 };
 
 #[test]
@@ -37,17 +53,43 @@ fn test_distribute() {
 
     //do dry distribute query
     let dry_dist = query_dry_distribution(&app, contract_addr.clone()).unwrap();
+    // Team gets what holders couldn't get because of flooring
+    let team_total_fee = Uint128::new(262500)
+        .checked_add(dry_dist.rounding_remainder)
+        .unwrap();
     assert_eq!(
         dry_dist,
         DryDistributionResponse {
             total_fees: MIN_FEES.checked_mul(Uint128::new(10)).unwrap(),
-            team_total_fee: Uint128::new(262500),
-            reserve_total_fee: Uint128::new(262500),
+            recipients: vec![
+                RecipientPayout {
+                    name: "team".to_string(),
+                    kind: FeeRecipientKind::Wallet {
+                        address: TEAM_ADDR.to_string()
+                    },
+                    amount: team_total_fee,
+                    to_send: team_total_fee,
+                },
+                RecipientPayout {
+                    name: "reserve".to_string(),
+                    kind: FeeRecipientKind::Reserve {
+                        address: RESERVE_ADDR.to_string()
+                    },
+                    amount: Uint128::new(262500),
+                    to_send: Uint128::new(262500),
+                },
+            ],
             holders_total_fee: Uint128::new(1225000),
             holders_total_shares: Decimal::from_atomics(Uint128::new(866), 0).unwrap(),
             fees_per_token: dry_dist.fees_per_token, // TODO: calculate the actual fee per token
             pay_to_holders: dry_dist.pay_to_holders, // This is calculation based on how much each holder has
-            number_of_holders: 777
+            number_of_holders: 777,
+            number_of_excluded_holders: 0,
+            holders_pending: Uint128::zero(),
+            holders_released: Uint128::zero(),
+            rounding_remainder: Uint128::new(1225000)
+                .checked_sub(dry_dist.pay_to_holders)
+                .unwrap(),
         }
     );
 
@@ -137,8 +179,11 @@ fn test_distribute_without_collection() {
     let res = sudo_distribute(&mut app, contract_addr.clone()).unwrap();
     let res_data = get_dist_result(res);
 
-    // There should be 1 left because of rounding
+    // The 1 left from rounding goes to the team
+    assert_eq!(res_data.team_paid, Uint128::new(875001));
+    assert_eq!(res_data.reserve_paid, Uint128::new(875000));
     let total_fee_amount = query_fees(&app, contract_addr.clone()).unwrap();
+    assert_eq!(total_fee_amount, Uint128::zero());
     assert_eq!(
         total_fee_amount,
         total_fee_amount_to_pay
@@ -152,13 +197,18 @@ fn test_distribute_without_collection() {
     let team_balance = app.wrap().query_balance(TEAM_ADDR, NATIVE_DENOM).unwrap();
     assert_eq!(team_balance, coin(res_data.team_paid.into(), NATIVE_DENOM));
 
+    // The extra 1 we added to fees put the bank 1 under the limit,
+    // so the reserve keeps it in the bank.
     let reserve_balance = app
         .wrap()
         .query_balance(RESERVE_ADDR, NATIVE_DENOM)
         .unwrap();
     assert_eq!(
         reserve_balance,
-        coin(res_data.reserve_paid.into(), NATIVE_DENOM)
+        coin(
+            (res_data.reserve_paid - Uint128::one()).into(),
+            NATIVE_DENOM
+        )
     );
 }
 
@@ -211,3 +261,620 @@ fn test_failing_distribute() {
     let err = sudo_distribute(&mut app, contract_addr).unwrap_err();
     assert_eq!(err, ContractError::NotEnoughFundsToPayFees);
 }
+
+#[test]
+fn test_stakers_tracked_by_hooks() {
+    let (mut app, contract_addr, staking_addr, nft_addr) = setup_staking_contract();
+
+    stake_nft(&mut app, nft_addr.clone(), staking_addr.clone(), 20);
+    stake_nft(&mut app, nft_addr.clone(), staking_addr.clone(), 728);
+    app.execute_contract(
+        Addr::unchecked("flipper-728"),
+        staking_addr,
+        &MockStakingExecuteMsg::Unstake {
+            token_ids: vec!["728".to_string()],
+        },
+        &[],
+    )
+    .unwrap();
+
+    add_10_todo_flips(&mut app, contract_addr.clone());
+    execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+
+    // Unstaked NFTs are not counted anymore
+    let dry_dist = query_dry_distribution(&app, contract_addr.clone()).unwrap();
+    assert_eq!(dry_dist.holders_total_shares, Decimal::one());
+    assert_eq!(dry_dist.number_of_holders, 1);
+
+    // Only the staking contract can send hooks
+    let err = unwrap_execute(app.execute_contract(
+        Addr::unchecked(FLIPPER_ADDR),
+        contract_addr.clone(),
+        &ExecuteMsg::StakeChangeHook(StakeChangedHookMsg::Stake {
+            addr: Addr::unchecked(FLIPPER_ADDR),
+            token_id: "1".to_string(),
+        }),
+        &[],
+    ))
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized);
+
+    // NFTs staked before we were added as a hook are picked up by syncing the staker
+    let staking_code_id = app.store_code(staking_contract());
+    let new_staking_addr = app
+        .instantiate_contract(
+            staking_code_id,
+            Addr::unchecked(CREATOR_ADDR),
+            &Empty {},
+            &[],
+            "staking contract",
+            None,
+        )
+        .unwrap();
+    stake_nft(&mut app, nft_addr, new_staking_addr.clone(), 21);
+    sudo_update_staking(
+        &mut app,
+        contract_addr.clone(),
+        Some(new_staking_addr.to_string()),
+    )
+    .unwrap();
+    let dry_dist = query_dry_distribution(&app, contract_addr.clone()).unwrap();
+    assert_eq!(dry_dist.number_of_holders, 0);
+
+    unwrap_execute(app.execute_contract(
+        Addr::unchecked(FLIPPER_ADDR),
+        contract_addr.clone(),
+        &ExecuteMsg::SyncStaker {
+            address: "flipper-21".to_string(),
+        },
+        &[],
+    ))
+    .unwrap();
+    let dry_dist = query_dry_distribution(&app, contract_addr).unwrap();
+    assert_eq!(dry_dist.holders_total_shares, Decimal::one());
+    assert_eq!(dry_dist.number_of_holders, 1);
+}
+
+#[test]
+fn test_distribute_to_stakers() {
+    let (mut app, contract_addr, staking_addr, nft_addr) = setup_staking_contract();
+
+    // Only 2 holders staked their NFTs, 1 normal token (1 share) and 1 rare token (2 shares)
+    stake_nft(&mut app, nft_addr.clone(), staking_addr.clone(), 20);
+    stake_nft(&mut app, nft_addr, staking_addr, 728);
+
+    add_10_todo_flips(&mut app, contract_addr.clone());
+    execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+
+    let dry_dist = query_dry_distribution(&app, contract_addr.clone()).unwrap();
+    assert_eq!(
+        dry_dist.holders_total_shares,
+        Decimal::from_atomics(Uint128::new(3), 0).unwrap()
+    );
+    assert_eq!(dry_dist.number_of_holders, 2);
+
+    let res = sudo_distribute(&mut app, contract_addr).unwrap();
+    let res_data = get_dist_result(res);
+    assert_eq!(res_data.total_fees, dry_dist.total_fees);
+    assert_eq!(res_data.total_shares, Uint128::new(3));
+    assert_eq!(res_data.fees_per_token, dry_dist.fees_per_token);
+    assert_eq!(res_data.holders_paid, Uint128::new(408333 + 816666));
+
+    // Stakers got paid by their shares
+    let balance = app
+        .wrap()
+        .query_balance("flipper-20", NATIVE_DENOM)
+        .unwrap();
+    assert_eq!(balance.amount, Uint128::new(100000000 + 408333));
+    let balance = app
+        .wrap()
+        .query_balance("flipper-728", NATIVE_DENOM)
+        .unwrap();
+    assert_eq!(balance.amount, Uint128::new(100000000 + 816666));
+
+    // Holders that didn't stake got nothing
+    let balance = app
+        .wrap()
+        .query_balance("flipper-30", NATIVE_DENOM)
+        .unwrap();
+    assert_eq!(balance.amount, Uint128::new(100000000));
+}
+
+#[test]
+fn test_paginated_distribution() {
+    let (mut app, contract_addr) = setup_contract();
+
+    // Can't continue a distribution that never started
+    let err = sudo_continue_distribution(&mut app, contract_addr.clone(), None).unwrap_err();
+    assert_eq!(err, ContractError::NoDistributionInProgress);
+
+    add_10_todo_flips(&mut app, contract_addr.clone());
+    execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+
+    let dry_dist = query_dry_distribution(&app, contract_addr.clone()).unwrap();
+    sudo_start_distribution(&mut app, contract_addr.clone()).unwrap();
+
+    // Team and reserve are paid on start
+    let team_balance = app.wrap().query_balance(TEAM_ADDR, NATIVE_DENOM).unwrap();
+    assert_eq!(team_balance.amount, dry_dist.recipients[0].to_send);
+    let reserve_balance = app
+        .wrap()
+        .query_balance(RESERVE_ADDR, NATIVE_DENOM)
+        .unwrap();
+    assert_eq!(reserve_balance.amount, dry_dist.recipients[1].to_send);
+
+    let run = query_distribution_status(&app, contract_addr.clone())
+        .unwrap()
+        .unwrap();
+    assert_eq!(run.number_of_holders, 777);
+    assert_eq!(run.pay_to_holders, dry_dist.pay_to_holders);
+    assert_eq!(run.paid_holders, 0);
+
+    // Only 1 distribution can run at a time
+    let err = sudo_distribute(&mut app, contract_addr.clone()).unwrap_err();
+    assert_eq!(err, ContractError::DistributionInProgress);
+    let err = sudo_start_distribution(&mut app, contract_addr.clone()).unwrap_err();
+    assert_eq!(err, ContractError::DistributionInProgress);
+
+    // A flip in the middle of the run shouldn't change what holders get
+    execute_start_flip(
+        &mut app,
+        contract_addr.clone(),
+        PickTypes::Heads,
+        MIN_BET,
+        Addr::unchecked(FLIPPER_ADDR),
+        MIN_FUNDS,
+    )
+    .unwrap();
+    execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+
+    sudo_continue_distribution(&mut app, contract_addr.clone(), Some(100)).unwrap();
+    let run = query_distribution_status(&app, contract_addr.clone())
+        .unwrap()
+        .unwrap();
+    assert_eq!(run.paid_holders, 100);
+    assert_eq!(run.fees_per_token, dry_dist.fees_per_token);
+
+    for _ in 0..5 {
+        sudo_continue_distribution(&mut app, contract_addr.clone(), Some(150)).unwrap();
+    }
+
+    // Run is done, and saved to history
+    let run = query_distribution_status(&app, contract_addr.clone()).unwrap();
+    assert_eq!(run, None);
+    let distributions = query_distributions(&app, contract_addr.clone(), None, None).unwrap();
+    assert_eq!(distributions[0].holders_paid, dry_dist.pay_to_holders);
+    assert_eq!(distributions[0].number_of_holders, 777);
+
+    // Only rounding and the new flip fees are left
+    let fees = query_fees(&app, contract_addr).unwrap();
+    assert_eq!(
+        fees,
+        dry_dist
+            .total_fees
+            .checked_sub(dry_dist.recipients[0].amount)
+            .unwrap()
+            .checked_sub(dry_dist.recipients[1].amount)
+            .unwrap()
+            .checked_sub(dry_dist.pay_to_holders)
+            .unwrap()
+            .checked_add(MIN_FEES)
+            .unwrap()
+    );
+}
+
+#[test]
+fn test_dry_distribution_holders() {
+    let (mut app, contract_addr, staking_addr, nft_addr) = setup_staking_contract();
+
+    stake_nft(&mut app, nft_addr.clone(), staking_addr.clone(), 20);
+    stake_nft(&mut app, nft_addr.clone(), staking_addr.clone(), 21);
+    stake_nft(&mut app, nft_addr.clone(), staking_addr.clone(), 650);
+    stake_nft(&mut app, nft_addr, staking_addr, 728);
+
+    add_10_todo_flips(&mut app, contract_addr.clone());
+    execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+
+    let payouts =
+        query_dry_distribution_holders(&app, contract_addr.clone(), None, Some(2)).unwrap();
+    assert_eq!(payouts.len(), 2);
+    assert_eq!(payouts[0].address, "flipper-20");
+    assert_eq!(payouts[0].token_ids, vec!["20".to_string()]);
+    assert_eq!(payouts[0].shares, Decimal::one());
+    assert_eq!(payouts[1].address, "flipper-21");
+
+    let next_payouts = query_dry_distribution_holders(
+        &app,
+        contract_addr.clone(),
+        Some(payouts[1].address.clone()),
+        None,
+    )
+    .unwrap();
+    assert_eq!(next_payouts.len(), 2);
+    assert_eq!(next_payouts[0].address, "flipper-650");
+    assert_eq!(next_payouts[0].shares, Decimal::percent(150));
+    assert_eq!(next_payouts[1].address, "flipper-728");
+    assert_eq!(next_payouts[1].token_ids, vec!["728".to_string()]);
+
+    // Preview matches what we actually pay
+    sudo_distribute(&mut app, contract_addr).unwrap();
+    for payout in payouts.into_iter().chain(next_payouts) {
+        let balance = app
+            .wrap()
+            .query_balance(payout.address, NATIVE_DENOM)
+            .unwrap();
+        assert_eq!(
+            balance.amount,
+            Uint128::new(100000000).checked_add(payout.amount).unwrap()
+        );
+    }
+}
+
+#[test]
+fn test_excluded_holders() {
+    let (mut app, contract_addr) = setup_contract();
+
+    add_10_todo_flips(&mut app, contract_addr.clone());
+    execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+
+    // Exclude a normal token holder, a rare token holder and an address that holds nothing
+    sudo_update_excluded_holders(
+        &mut app,
+        contract_addr.clone(),
+        vec![
+            "flipper-100".to_string(),
+            "flipper-728".to_string(),
+            "random".to_string(),
+        ],
+        vec![],
+    )
+    .unwrap();
+
+    let excluded = query_excluded_holders(&app, contract_addr.clone()).unwrap();
+    assert_eq!(excluded.len(), 3);
+
+    let dry_dist = query_dry_distribution(&app, contract_addr.clone()).unwrap();
+    // 866 shares - 1 (flipper-100) - 2 (flipper-728)
+    assert_eq!(
+        dry_dist.holders_total_shares,
+        Decimal::from_atomics(Uint128::new(863), 0).unwrap()
+    );
+    assert_eq!(dry_dist.number_of_holders, 775);
+    assert_eq!(dry_dist.number_of_excluded_holders, 2);
+
+    let res = sudo_distribute(&mut app, contract_addr.clone()).unwrap();
+    let res_data = get_dist_result(res);
+    assert_eq!(res_data.total_shares, Uint128::new(863));
+
+    // Excluded holders got nothing
+    let balance = app
+        .wrap()
+        .query_balance("flipper-100", NATIVE_DENOM)
+        .unwrap();
+    assert_eq!(balance.amount, Uint128::new(100000000));
+    let balance = app
+        .wrap()
+        .query_balance("flipper-728", NATIVE_DENOM)
+        .unwrap();
+    assert_eq!(balance.amount, Uint128::new(100000000));
+
+    // Remove exclusion
+    sudo_update_excluded_holders(
+        &mut app,
+        contract_addr.clone(),
+        vec![],
+        vec!["flipper-728".to_string()],
+    )
+    .unwrap();
+    let excluded = query_excluded_holders(&app, contract_addr).unwrap();
+    assert_eq!(
+        excluded,
+        vec![Addr::unchecked("flipper-100"), Addr::unchecked("random")]
+    );
+}
+
+#[test]
+fn test_min_holder_payout() {
+    let (mut app, contract_addr, staking_addr, nft_addr) = setup_staking_contract();
+
+    stake_nft(&mut app, nft_addr.clone(), staking_addr.clone(), 20);
+    stake_nft(&mut app, nft_addr, staking_addr, 728);
+
+    // flipper-20 should get 408333 and flipper-728 should get 816666
+    sudo_update_min_holder_payout(&mut app, contract_addr.clone(), Uint128::new(500000)).unwrap();
+
+    add_10_todo_flips(&mut app, contract_addr.clone());
+    execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+
+    let payouts = query_dry_distribution_holders(&app, contract_addr.clone(), None, None).unwrap();
+    assert_eq!(payouts[0].amount, Uint128::new(408333));
+    assert_eq!(payouts[0].to_pay, Uint128::zero());
+    assert_eq!(payouts[1].to_pay, Uint128::new(816666));
+
+    let res = sudo_distribute(&mut app, contract_addr.clone()).unwrap();
+    let res_data = get_dist_result(res);
+    assert_eq!(res_data.holders_paid, Uint128::new(816666));
+    assert_eq!(res_data.holders_pending, Uint128::new(408333));
+    assert_eq!(res_data.rounding_remainder, Uint128::one());
+
+    // Remaining fees are exactly the pending amounts, rounding went to the team
+    let fees = query_fees(&app, contract_addr.clone()).unwrap();
+    assert_eq!(fees, res_data.holders_pending);
+
+    let balance = app
+        .wrap()
+        .query_balance("flipper-20", NATIVE_DENOM)
+        .unwrap();
+    assert_eq!(balance.amount, Uint128::new(100000000));
+
+    // On next distribution flipper-20 crosses the min payout and gets everything
+    add_10_todo_flips(&mut app, contract_addr.clone());
+    execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+
+    let payouts = query_dry_distribution_holders(&app, contract_addr.clone(), None, None).unwrap();
+    assert_eq!(payouts[0].carried, Uint128::new(408333));
+    assert_eq!(payouts[0].to_pay, Uint128::new(816666));
+
+    let res = sudo_distribute(&mut app, contract_addr.clone()).unwrap();
+    let res_data = get_dist_result(res);
+    assert_eq!(res_data.holders_pending, Uint128::zero());
+
+    let balance = app
+        .wrap()
+        .query_balance("flipper-20", NATIVE_DENOM)
+        .unwrap();
+    assert_eq!(balance.amount, Uint128::new(100000000 + 816666));
+
+    let fees = query_fees(&app, contract_addr).unwrap();
+    assert_eq!(fees, Uint128::zero());
+}
+
+#[test]
+fn test_pending_released_when_holder_sells() {
+    let (mut app, contract_addr) = setup_contract();
+    let nft_addr = query_config(&app, contract_addr.clone())
+        .unwrap()
+        .sg721_addr
+        .unwrap();
+
+    // Nobody reaches the min payout, everything is kept pending
+    sudo_update_min_holder_payout(&mut app, contract_addr.clone(), Uint128::new(1_000_000_000))
+        .unwrap();
+
+    add_10_todo_flips(&mut app, contract_addr.clone());
+    execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+
+    let payouts = query_dry_distribution_holders(
+        &app,
+        contract_addr.clone(),
+        Some("flipper-2".to_string()),
+        Some(1),
+    )
+    .unwrap();
+    assert_eq!(payouts[0].address, "flipper-20");
+    let flipper_20_pending = payouts[0].amount;
+
+    let res = sudo_distribute(&mut app, contract_addr.clone()).unwrap();
+    let res_data = get_dist_result(res);
+    assert_eq!(res_data.holders_paid, Uint128::zero());
+    assert_eq!(res_data.holders_released, Uint128::zero());
+
+    // flipper-20 sells its only NFT before crossing the min payout
+    app.execute_contract(
+        Addr::unchecked("flipper-20"),
+        nft_addr,
+        &sg721::ExecuteMsg::TransferNft::<Empty, Empty> {
+            recipient: "buyer".to_string(),
+            token_id: "20".to_string(),
+        },
+        &[],
+    )
+    .unwrap();
+
+    add_10_todo_flips(&mut app, contract_addr.clone());
+    execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+
+    let dry_dist = query_dry_distribution(&app, contract_addr.clone()).unwrap();
+    assert_eq!(dry_dist.holders_released, flipper_20_pending);
+    let team = dry_dist
+        .recipients
+        .iter()
+        .find(|recipient| recipient.name == "team")
+        .unwrap();
+    assert_eq!(
+        team.amount,
+        Uint128::new(262500)
+            .checked_add(dry_dist.rounding_remainder)
+            .unwrap()
+            .checked_add(flipper_20_pending)
+            .unwrap()
+    );
+
+    // Released pending goes to the team (remainder recipient) and is not kept in FEES
+    let res = sudo_distribute(&mut app, contract_addr.clone()).unwrap();
+    let res_data = get_dist_result(res);
+    assert_eq!(res_data.holders_released, flipper_20_pending);
+    assert_eq!(res_data.team_paid, team.amount);
+    let fees = query_fees(&app, contract_addr.clone()).unwrap();
+    assert_eq!(fees, res_data.holders_pending);
+
+    // It's only released once
+    add_10_todo_flips(&mut app, contract_addr.clone());
+    execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+    let dry_dist = query_dry_distribution(&app, contract_addr).unwrap();
+    assert_eq!(dry_dist.holders_released, Uint128::zero());
+}
+
+#[test]
+fn test_scheduled_distribution() {
+    let (mut app, contract_addr) = setup_base_contract();
+    add_balance(&mut app, contract_addr.clone(), 30000000000);
+    let caller = Addr::unchecked("caller");
+
+    // Only admin can distribute when there is no schedule
+    let err = execute_distribute(&mut app, contract_addr.clone(), caller.clone()).unwrap_err();
+    assert_eq!(err, ContractError::DistributionNotScheduled);
+
+    let err = sudo_update_distribution_schedule(
+        &mut app,
+        contract_addr.clone(),
+        Some(DistributionSchedule {
+            interval: Duration::Time(0),
+            min_fees: Uint128::zero(),
+            caller_tip_bps: 0,
+        }),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidDistributionInterval);
+
+    let schedule = DistributionSchedule {
+        interval: Duration::Time(3600),
+        min_fees: Uint128::new(1_000_000),
+        caller_tip_bps: 100, // 1%
+    };
+    sudo_update_distribution_schedule(&mut app, contract_addr.clone(), Some(schedule.clone()))
+        .unwrap();
+
+    let next = query_next_distribution(&app, contract_addr.clone()).unwrap();
+    assert_eq!(next.schedule, Some(schedule.clone()));
+    assert_eq!(next.next_distribution, None);
+    assert!(!next.is_due);
+
+    let err = execute_distribute(&mut app, contract_addr.clone(), caller.clone()).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::NotEnoughFeesToDistribute {
+            min_fees: schedule.min_fees
+        }
+    );
+
+    add_10_todo_flips(&mut app, contract_addr.clone());
+    execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+
+    let next = query_next_distribution(&app, contract_addr.clone()).unwrap();
+    assert_eq!(next.distributable_fees, Uint128::new(1750000));
+    assert!(next.is_due);
+
+    // Anyone can distribute, and get 1% of the fees as a tip
+    let res = execute_distribute(&mut app, contract_addr.clone(), caller.clone()).unwrap();
+    let res_data = get_dist_result(res);
+    assert_eq!(res_data.caller_tip, Uint128::new(17500));
+    assert_eq!(res_data.team_paid, Uint128::new(866250));
+    assert_eq!(res_data.reserve_paid, Uint128::new(866250));
+    assert_eq!(
+        query_fees(&app, contract_addr.clone()).unwrap(),
+        Uint128::zero()
+    );
+    let caller_balance = app.wrap().query_balance(&caller, NATIVE_DENOM).unwrap();
+    assert_eq!(caller_balance.amount, res_data.caller_tip);
+
+    let next = query_next_distribution(&app, contract_addr.clone()).unwrap();
+    let last_time = next.last_distribution.unwrap().time;
+    assert_eq!(
+        next.next_distribution,
+        Some(Expiration::AtTime(last_time.plus_seconds(3600)))
+    );
+    assert!(!next.is_due);
+
+    add_10_todo_flips(&mut app, contract_addr.clone());
+    execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+
+    let err = execute_distribute(&mut app, contract_addr.clone(), caller.clone()).unwrap_err();
+    assert_eq!(err, ContractError::DistributionNotDue);
+
+    app.update_block(|block| block.time = last_time.plus_seconds(3600));
+    assert!(
+        query_next_distribution(&app, contract_addr.clone())
+            .unwrap()
+            .is_due
+    );
+    execute_distribute(&mut app, contract_addr, caller).unwrap();
+}
+
+#[test]
+fn test_distribute_custom_recipients() {
+    let (mut app, contract_addr) = setup_base_contract();
+    add_balance(&mut app, contract_addr.clone(), 30000000000);
+
+    let config = query_config(&app, contract_addr.clone()).unwrap();
+    let wallet = |name: &str, bps: u64| FeeRecipient {
+        name: name.to_string(),
+        kind: FeeRecipientKind::Wallet {
+            address: format!("{name}_wallet"),
+        },
+        bps,
+    };
+    sudo_update_fees(
+        &mut app,
+        contract_addr.clone(),
+        Fees {
+            recipients: vec![
+                FeeRecipient {
+                    name: "holders".to_string(),
+                    kind: FeeRecipientKind::Holders,
+                    bps: 5000,
+                },
+                wallet("dev", 2000),
+                wallet("charity", 2000),
+                FeeRecipient {
+                    name: "burn".to_string(),
+                    kind: FeeRecipientKind::Burn,
+                    bps: 1000,
+                },
+            ],
+            remainder_recipient: "dev".to_string(),
+            ..config.fees
+        },
+    )
+    .unwrap();
+
+    add_10_todo_flips(&mut app, contract_addr.clone());
+    execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+
+    // No collection, so holders share is split between the rest
+    let dry_dist = query_dry_distribution(&app, contract_addr.clone()).unwrap();
+    let amounts: Vec<Uint128> = dry_dist.recipients.iter().map(|r| r.amount).collect();
+    assert_eq!(
+        amounts,
+        vec![
+            Uint128::new(700000),
+            Uint128::new(700000),
+            Uint128::new(350000)
+        ]
+    );
+
+    let contract_balance = app
+        .wrap()
+        .query_balance(&contract_addr, NATIVE_DENOM)
+        .unwrap();
+    let res = sudo_distribute(&mut app, contract_addr.clone()).unwrap();
+    let dev_paid = res
+        .events
+        .iter()
+        .flat_map(|e| e.attributes.iter())
+        .find(|attr| attr.key == "dev_paid")
+        .unwrap();
+    assert_eq!(dev_paid.value, "700000");
+
+    let dev_balance = app
+        .wrap()
+        .query_balance("dev_wallet", NATIVE_DENOM)
+        .unwrap();
+    assert_eq!(dev_balance.amount, Uint128::new(700000));
+    let charity_balance = app
+        .wrap()
+        .query_balance("charity_wallet", NATIVE_DENOM)
+        .unwrap();
+    assert_eq!(charity_balance.amount, Uint128::new(700000));
+
+    // Everything left the contract, burn included
+    let new_contract_balance = app
+        .wrap()
+        .query_balance(&contract_addr, NATIVE_DENOM)
+        .unwrap();
+    assert_eq!(
+        contract_balance.amount - new_contract_balance.amount,
+        MIN_FEES * Uint128::new(10)
+    );
+    assert_eq!(query_fees(&app, contract_addr).unwrap(), Uint128::zero());
+}
@@ -0,0 +1,133 @@
+use cosmwasm_std::Addr;
+use cw_utils::Duration;
+
+use crate::{
+    error::ContractError,
+    msg::{ChainSudoMsg, MultisigExecuteMsg, SudoMsg},
+    types::Fees,
+};
+
+use super::utils::{
+    executes::{execute_multisig, execute_sudo, sudo_update_pause},
+    queries::{query_config, query_ownership, query_proposal},
+    setup::{fee_recipients, setup_base_contract, CREATOR_ADDR, FLIPPER_ADDR},
+};
+
+#[test]
+fn test_governance_sudo() {
+    let (mut app, contract_addr) = setup_base_contract();
+
+    // Admin renounced, only governance can act now
+    execute_sudo(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(CREATOR_ADDR),
+        SudoMsg::RenounceAdmin {},
+    )
+    .unwrap();
+
+    app.wasm_sudo(contract_addr.clone(), &ChainSudoMsg::UpdatePause(true))
+        .unwrap();
+    assert!(
+        query_config(&app, contract_addr.clone())
+            .unwrap()
+            .paused
+            .new_flips
+    );
+
+    let fees = Fees {
+        recipients: fee_recipients(2000, 6000, 2000),
+        flip_bps: 300,
+        flip_fee_tiers: vec![],
+        holder_discount_bps: 0,
+        referral_bps: 0,
+        remainder_recipient: "team".to_string(),
+    };
+    app.wasm_sudo(
+        contract_addr.clone(),
+        &ChainSudoMsg::UpdateFees { fees: fees.clone() },
+    )
+    .unwrap();
+    assert_eq!(
+        query_config(&app, contract_addr.clone()).unwrap().fees,
+        fees
+    );
+
+    app.wasm_sudo(
+        contract_addr.clone(),
+        &ChainSudoMsg::ResetAdmin {
+            admin: FLIPPER_ADDR.to_string(),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        query_ownership(&app, contract_addr.clone()).unwrap().admin,
+        Some(Addr::unchecked(FLIPPER_ADDR))
+    );
+
+    execute_sudo(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        SudoMsg::UpdatePause(false),
+    )
+    .unwrap();
+    assert!(!query_config(&app, contract_addr).unwrap().paused.new_flips);
+}
+
+#[test]
+fn test_governance_reset_multisig() {
+    let (mut app, contract_addr) = setup_base_contract();
+    execute_sudo(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(CREATOR_ADDR),
+        SudoMsg::UpdateMultisig {
+            members: vec![FLIPPER_ADDR.to_string()],
+            threshold: 1,
+            voting_period: Duration::Time(60),
+        },
+    )
+    .unwrap();
+    let err = sudo_update_pause(&mut app, contract_addr.clone(), true).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized);
+    execute_multisig(
+        &mut app,
+        contract_addr.clone(),
+        FLIPPER_ADDR,
+        MultisigExecuteMsg::Propose {
+            msg: SudoMsg::UpdatePause(true),
+        },
+    )
+    .unwrap();
+
+    app.wasm_sudo(
+        contract_addr.clone(),
+        &ChainSudoMsg::ResetAdmin {
+            admin: CREATOR_ADDR.to_string(),
+        },
+    )
+    .unwrap();
+    sudo_update_pause(&mut app, contract_addr.clone(), true).unwrap();
+
+    // The multisig is gone with its proposals
+    query_proposal(&app, contract_addr.clone(), 1).unwrap_err();
+    let err = execute_multisig(
+        &mut app,
+        contract_addr.clone(),
+        FLIPPER_ADDR,
+        MultisigExecuteMsg::Execute { id: 1 },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NoProposal { id: 1 });
+    let err = execute_multisig(
+        &mut app,
+        contract_addr,
+        FLIPPER_ADDR,
+        MultisigExecuteMsg::Propose {
+            msg: SudoMsg::UpdatePause(false),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::MultisigNotEnabled);
+}
@@ -0,0 +1,127 @@
+use cosmwasm_std::{Addr, Uint128};
+
+use crate::{contract::MIN_BET, error::ContractError, types::IntegratorFee};
+
+use super::utils::{
+    executes::{
+        execute_claim_integrator_fees, execute_start_flip_with_integrator, sudo_distribute,
+        sudo_update_max_integrator_fee,
+    },
+    helpers::{add_balance, get_dist_result, MIN_FEES, MIN_FUNDS},
+    queries::{query_fees, query_flip_quote, query_integrator_stats, query_integrators},
+    setup::{setup_base_contract, FLIPPER_ADDR, NATIVE_DENOM},
+};
+
+const INTEGRATOR_ADDR: &str = "frontend";
+
+#[test]
+fn test_integrator_fee() {
+    let (mut app, contract_addr) = setup_base_contract();
+    add_balance(&mut app, contract_addr.clone(), 30000000000);
+
+    // Integrators cannot charge anything by default
+    let err = execute_start_flip_with_integrator(
+        &mut app,
+        contract_addr.clone(),
+        MIN_BET,
+        Addr::unchecked(FLIPPER_ADDR),
+        MIN_FUNDS,
+        IntegratorFee {
+            addr: INTEGRATOR_ADDR.to_string(),
+            bps: 100,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::IntegratorFeeTooHigh {
+            bps: 100,
+            max_bps: 0
+        }
+    );
+
+    let err = sudo_update_max_integrator_fee(&mut app, contract_addr.clone(), 10001).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::IntegratorFeeTooHigh {
+            bps: 10001,
+            max_bps: 10000
+        }
+    );
+    sudo_update_max_integrator_fee(&mut app, contract_addr.clone(), 100).unwrap();
+
+    // 1% on top of the flip fee
+    let quote = query_flip_quote(&app, contract_addr.clone(), MIN_BET, None, Some(100)).unwrap();
+    assert_eq!(quote.fee, MIN_FEES);
+    assert_eq!(quote.integrator_fee, Uint128::new(50000));
+    assert_eq!(quote.total, Uint128::new(5225000));
+
+    // Must pay the integrator fee as well
+    let err = execute_start_flip_with_integrator(
+        &mut app,
+        contract_addr.clone(),
+        MIN_BET,
+        Addr::unchecked(FLIPPER_ADDR),
+        MIN_FUNDS,
+        IntegratorFee {
+            addr: INTEGRATOR_ADDR.to_string(),
+            bps: 100,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::WrongPaidAmount);
+
+    execute_start_flip_with_integrator(
+        &mut app,
+        contract_addr.clone(),
+        MIN_BET,
+        Addr::unchecked(FLIPPER_ADDR),
+        quote.total,
+        IntegratorFee {
+            addr: INTEGRATOR_ADDR.to_string(),
+            bps: 100,
+        },
+    )
+    .unwrap();
+
+    let stats = query_integrator_stats(&app, contract_addr.clone(), INTEGRATOR_ADDR).unwrap();
+    assert_eq!(stats.flips, 1);
+    assert_eq!(stats.volume, MIN_BET);
+    assert_eq!(stats.earned, quote.integrator_fee);
+    assert_eq!(stats.claimable, quote.integrator_fee);
+    assert_eq!(
+        query_integrators(&app, contract_addr.clone()).unwrap(),
+        vec![stats]
+    );
+
+    // Integrator fee is not distributed
+    let res = sudo_distribute(&mut app, contract_addr.clone()).unwrap();
+    let res_data = get_dist_result(res);
+    assert_eq!(res_data.team_paid, Uint128::new(87500));
+    assert_eq!(res_data.reserve_paid, Uint128::new(87500));
+    assert_eq!(
+        query_fees(&app, contract_addr.clone()).unwrap(),
+        quote.integrator_fee
+    );
+
+    execute_claim_integrator_fees(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(INTEGRATOR_ADDR),
+    )
+    .unwrap();
+    let balance = app
+        .wrap()
+        .query_balance(INTEGRATOR_ADDR, NATIVE_DENOM)
+        .unwrap();
+    assert_eq!(balance.amount, quote.integrator_fee);
+    assert_eq!(
+        query_fees(&app, contract_addr.clone()).unwrap(),
+        Uint128::zero()
+    );
+
+    let err =
+        execute_claim_integrator_fees(&mut app, contract_addr, Addr::unchecked(INTEGRATOR_ADDR))
+            .unwrap_err();
+    assert_eq!(err, ContractError::NoIntegratorFees);
+}
@@ -0,0 +1,160 @@
+use cosmwasm_std::{Addr, Uint128};
+use cw_multi_test::{AppResponse, Executor};
+
+use crate::{
+    contract::{CONTRACT_NAME, CONTRACT_VERSION, MIN_BET},
+    error::ContractError,
+    migrations::FeesV0_7,
+    msg::MigrateMsg,
+    types::ConfigUpdate,
+};
+
+use super::utils::{
+    executes::unwrap_execute,
+    mocks::{old_flip_contract, MockOldFlipInstantiateMsg},
+    queries::{query_config, query_solvency},
+    setup::{
+        fee_recipients, flip_contract, setup_base_contract, BaseApp, CREATOR_ADDR, NATIVE_DENOM,
+    },
+};
+
+fn setup_old_contract(app: &mut BaseApp, contract: &str, version: &str) -> Addr {
+    setup_old_contract_with_fees(app, contract, version, None)
+}
+
+fn setup_old_contract_with_fees(
+    app: &mut BaseApp,
+    contract: &str,
+    version: &str,
+    fees: Option<FeesV0_7>,
+) -> Addr {
+    let code_id = app.store_code(old_flip_contract());
+    app.instantiate_contract(
+        code_id,
+        Addr::unchecked(CREATOR_ADDR),
+        &MockOldFlipInstantiateMsg {
+            contract: contract.to_string(),
+            version: version.to_string(),
+            fees,
+        },
+        &[],
+        "old flip contract",
+        Some(CREATOR_ADDR.to_string()),
+    )
+    .unwrap()
+}
+
+fn migrate(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    msg: MigrateMsg,
+) -> Result<AppResponse, ContractError> {
+    let code_id = app.store_code(flip_contract());
+    unwrap_execute(app.migrate_contract(
+        Addr::unchecked(CREATOR_ADDR),
+        contract_addr,
+        &msg,
+        code_id,
+    ))
+}
+
+#[test]
+fn test_migrate_from_v0_7() {
+    let (mut app, _) = setup_base_contract();
+    let contract_addr = setup_old_contract(&mut app, CONTRACT_NAME, "0.7.2");
+
+    migrate(
+        &mut app,
+        contract_addr.clone(),
+        MigrateMsg::WithConfig {
+            update: Box::new(ConfigUpdate {
+                flips_per_block_limit: Some(5),
+                ..Default::default()
+            }),
+        },
+    )
+    .unwrap();
+
+    let config = query_config(&app, contract_addr.clone()).unwrap();
+    assert_eq!(config.admin, Some(Addr::unchecked(CREATOR_ADDR)));
+    assert_eq!(config.fees.recipients, fee_recipients(1500, 7000, 1500));
+    assert_eq!(config.fees.flip_bps, 350);
+    assert_eq!(config.flips_per_block_limit, 5);
+
+    // Liabilities are built from the pending flip
+    let solvency = query_solvency(&app, contract_addr.clone(), NATIVE_DENOM).unwrap();
+    assert_eq!(solvency.liabilities, MIN_BET * Uint128::new(2));
+
+    let version = cw2::query_contract_info(&app.wrap(), contract_addr.clone()).unwrap();
+    assert_eq!(version.version, CONTRACT_VERSION);
+
+    // Migrating to the same version again doesn't run the steps again
+    let res = migrate(&mut app, contract_addr, MigrateMsg::Basic {}).unwrap();
+    assert!(!res
+        .events
+        .iter()
+        .flat_map(|event| event.attributes.iter())
+        .any(|attr| attr.key == "migration_step"));
+}
+
+#[test]
+fn test_migrate_repairs_v0_7_fees() {
+    let (mut app, _) = setup_base_contract();
+
+    // Split under 100% is scaled up, the rounding goes to the team
+    let contract_addr = setup_old_contract_with_fees(
+        &mut app,
+        CONTRACT_NAME,
+        "0.7.2",
+        Some(FeesV0_7 {
+            team_bps: 1000,
+            holders_bps: 4000,
+            reserve_bps: 1000,
+            flip_bps: 350,
+        }),
+    );
+    migrate(&mut app, contract_addr.clone(), MigrateMsg::Basic {}).unwrap();
+    let config = query_config(&app, contract_addr).unwrap();
+    assert_eq!(config.fees.recipients, fee_recipients(1668, 6666, 1666));
+
+    // Empty split goes to the team, flip fee is capped
+    let contract_addr = setup_old_contract_with_fees(
+        &mut app,
+        CONTRACT_NAME,
+        "0.7.2",
+        Some(FeesV0_7 {
+            team_bps: 0,
+            holders_bps: 0,
+            reserve_bps: 0,
+            flip_bps: 20000,
+        }),
+    );
+    migrate(&mut app, contract_addr.clone(), MigrateMsg::Basic {}).unwrap();
+    let config = query_config(&app, contract_addr).unwrap();
+    assert_eq!(config.fees.recipients, fee_recipients(10000, 0, 0));
+    assert_eq!(config.fees.flip_bps, 10000);
+}
+
+#[test]
+fn test_migrate_refused() {
+    let (mut app, _) = setup_base_contract();
+
+    let contract_addr = setup_old_contract(&mut app, "other-contract", "0.7.2");
+    let err = migrate(&mut app, contract_addr, MigrateMsg::Basic {}).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::WrongContract {
+            name: "other-contract".to_string()
+        }
+    );
+
+    let contract_addr = setup_old_contract(&mut app, CONTRACT_NAME, "9.9.9");
+    let err = migrate(&mut app, contract_addr, MigrateMsg::Basic {}).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::CannotDowngrade {
+            stored: "9.9.9".to_string(),
+            new: CONTRACT_VERSION.to_string()
+        }
+    );
+}
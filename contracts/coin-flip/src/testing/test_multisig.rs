@@ -0,0 +1,400 @@
+use cosmwasm_std::{Addr, Uint128};
+use cw_utils::Duration;
+
+use crate::{
+    error::ContractError,
+    msg::{MultisigExecuteMsg, SudoMsg},
+    types::{ProposalStatus, Role},
+};
+
+use super::utils::{
+    executes::{
+        execute_multisig, execute_sudo, sudo_fund_bank, sudo_grant_role, sudo_update_pause,
+    },
+    helpers::add_balance,
+    queries::{
+        query_config, query_has_role, query_house_ledger, query_ownership, query_proposal,
+        query_votes,
+    },
+    setup::{setup_base_contract, CREATOR_ADDR, FLIPPER_ADDR, FLIPPER_ADDR2, NATIVE_DENOM},
+};
+
+const MEMBER1: &str = "member1";
+const MEMBER2: &str = "member2";
+const MEMBER3: &str = "member3";
+const DAY: u64 = 24 * 60 * 60;
+
+fn update_multisig_msg(threshold: u64) -> SudoMsg {
+    SudoMsg::UpdateMultisig {
+        members: vec![
+            MEMBER1.to_string(),
+            MEMBER2.to_string(),
+            MEMBER3.to_string(),
+        ],
+        threshold,
+        voting_period: Duration::Time(DAY),
+    }
+}
+
+#[test]
+fn test_multisig() {
+    let (mut app, contract_addr) = setup_base_contract();
+    let admin = Addr::unchecked(CREATOR_ADDR);
+
+    let err = execute_sudo(
+        &mut app,
+        contract_addr.clone(),
+        admin.clone(),
+        update_multisig_msg(4),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidThreshold { members: 3 });
+
+    sudo_grant_role(&mut app, contract_addr.clone(), Role::Pauser, FLIPPER_ADDR).unwrap();
+    execute_sudo(
+        &mut app,
+        contract_addr.clone(),
+        admin,
+        update_multisig_msg(2),
+    )
+    .unwrap();
+
+    // The multisig is now the admin
+    let ownership = query_ownership(&app, contract_addr.clone()).unwrap();
+    assert_eq!(ownership.admin, Some(contract_addr.clone()));
+    let err = sudo_update_pause(&mut app, contract_addr.clone(), true).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized);
+
+    // Granted roles are ignored while the multisig is the admin
+    assert!(!query_has_role(&app, contract_addr.clone(), Role::Pauser, FLIPPER_ADDR).unwrap());
+    let err = execute_sudo(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        SudoMsg::UpdatePause(true),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized);
+
+    // Proposals are executed without funds
+    let err = execute_multisig(
+        &mut app,
+        contract_addr.clone(),
+        MEMBER1,
+        MultisigExecuteMsg::Propose {
+            msg: SudoMsg::FundBank {},
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::ProposalWithFunds);
+
+    let propose_pause = MultisigExecuteMsg::Propose {
+        msg: SudoMsg::UpdatePause(true),
+    };
+    let err = execute_multisig(
+        &mut app,
+        contract_addr.clone(),
+        FLIPPER_ADDR,
+        propose_pause.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized);
+
+    execute_multisig(&mut app, contract_addr.clone(), MEMBER1, propose_pause).unwrap();
+    let proposal = query_proposal(&app, contract_addr.clone(), 1).unwrap();
+    assert_eq!(proposal.status, ProposalStatus::Open);
+    assert_eq!(proposal.yes, 1);
+
+    let err = execute_multisig(
+        &mut app,
+        contract_addr.clone(),
+        FLIPPER_ADDR2,
+        MultisigExecuteMsg::Execute { id: 1 },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::ProposalNotPassed);
+
+    let err = execute_multisig(
+        &mut app,
+        contract_addr.clone(),
+        MEMBER1,
+        MultisigExecuteMsg::Vote {
+            id: 1,
+            approve: true,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::AlreadyVoted);
+
+    execute_multisig(
+        &mut app,
+        contract_addr.clone(),
+        MEMBER2,
+        MultisigExecuteMsg::Vote {
+            id: 1,
+            approve: true,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        query_proposal(&app, contract_addr.clone(), 1)
+            .unwrap()
+            .status,
+        ProposalStatus::Passed
+    );
+    assert_eq!(
+        query_votes(&app, contract_addr.clone(), 1).unwrap().len(),
+        2
+    );
+
+    // Anyone can execute a passed proposal
+    execute_multisig(
+        &mut app,
+        contract_addr.clone(),
+        FLIPPER_ADDR2,
+        MultisigExecuteMsg::Execute { id: 1 },
+    )
+    .unwrap();
+    assert!(
+        query_config(&app, contract_addr.clone())
+            .unwrap()
+            .paused
+            .new_flips
+    );
+    assert_eq!(
+        query_proposal(&app, contract_addr.clone(), 1)
+            .unwrap()
+            .status,
+        ProposalStatus::Executed
+    );
+
+    let err = execute_multisig(
+        &mut app,
+        contract_addr.clone(),
+        MEMBER3,
+        MultisigExecuteMsg::Vote {
+            id: 1,
+            approve: false,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::ProposalNotOpen);
+}
+
+#[test]
+fn test_multisig_rejected() {
+    let (mut app, contract_addr) = setup_base_contract();
+    execute_sudo(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(CREATOR_ADDR),
+        update_multisig_msg(2),
+    )
+    .unwrap();
+
+    let propose_unpause = MultisigExecuteMsg::Propose {
+        msg: SudoMsg::UpdatePause(false),
+    };
+    execute_multisig(
+        &mut app,
+        contract_addr.clone(),
+        MEMBER1,
+        propose_unpause.clone(),
+    )
+    .unwrap();
+    execute_multisig(&mut app, contract_addr.clone(), MEMBER1, propose_unpause).unwrap();
+
+    // 2 no votes out of 3 members, it can't reach the threshold anymore
+    for member in [MEMBER2, MEMBER3] {
+        execute_multisig(
+            &mut app,
+            contract_addr.clone(),
+            member,
+            MultisigExecuteMsg::Vote {
+                id: 1,
+                approve: false,
+            },
+        )
+        .unwrap();
+    }
+    assert_eq!(
+        query_proposal(&app, contract_addr.clone(), 1)
+            .unwrap()
+            .status,
+        ProposalStatus::Rejected
+    );
+
+    // Open proposals are rejected once expired
+    app.update_block(|block| {
+        block.time = block.time.plus_seconds(DAY);
+        block.height += 1;
+    });
+    assert_eq!(
+        query_proposal(&app, contract_addr.clone(), 2)
+            .unwrap()
+            .status,
+        ProposalStatus::Rejected
+    );
+    let err = execute_multisig(
+        &mut app,
+        contract_addr,
+        MEMBER2,
+        MultisigExecuteMsg::Vote {
+            id: 2,
+            approve: true,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::ProposalNotOpen);
+}
+
+#[test]
+fn test_multisig_renounce_admin() {
+    let (mut app, contract_addr) = setup_base_contract();
+    execute_sudo(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(CREATOR_ADDR),
+        update_multisig_msg(1),
+    )
+    .unwrap();
+
+    execute_multisig(
+        &mut app,
+        contract_addr.clone(),
+        MEMBER1,
+        MultisigExecuteMsg::Propose {
+            msg: SudoMsg::RenounceAdmin {},
+        },
+    )
+    .unwrap();
+    execute_multisig(
+        &mut app,
+        contract_addr.clone(),
+        MEMBER1,
+        MultisigExecuteMsg::Execute { id: 1 },
+    )
+    .unwrap();
+    assert_eq!(
+        query_ownership(&app, contract_addr.clone()).unwrap().admin,
+        None
+    );
+
+    // Members can't keep sending sudo msgs once the admin is renounced
+    let err = execute_multisig(
+        &mut app,
+        contract_addr,
+        MEMBER1,
+        MultisigExecuteMsg::Propose {
+            msg: SudoMsg::UpdatePause(true),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::MultisigNotEnabled);
+}
+
+#[test]
+fn test_multisig_fund_bank() {
+    let (mut app, contract_addr) = setup_base_contract();
+    sudo_grant_role(
+        &mut app,
+        contract_addr.clone(),
+        Role::SuperAdmin,
+        FLIPPER_ADDR,
+    )
+    .unwrap();
+    execute_sudo(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(CREATOR_ADDR),
+        update_multisig_msg(2),
+    )
+    .unwrap();
+    let amount = Uint128::new(1000000);
+    add_balance(&mut app, Addr::unchecked(MEMBER1), amount.u128());
+
+    // Members fund the bank without a proposal, granted roles are still ignored
+    let err = sudo_fund_bank(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        amount,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized);
+    sudo_fund_bank(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(MEMBER1),
+        amount,
+    )
+    .unwrap();
+
+    let ledger = query_house_ledger(&app, contract_addr, NATIVE_DENOM).unwrap();
+    assert_eq!(ledger.deposited, amount);
+}
+
+#[test]
+fn test_update_multisig_drops_proposals() {
+    let (mut app, contract_addr) = setup_base_contract();
+    execute_sudo(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(CREATOR_ADDR),
+        update_multisig_msg(2),
+    )
+    .unwrap();
+
+    execute_multisig(
+        &mut app,
+        contract_addr.clone(),
+        MEMBER1,
+        MultisigExecuteMsg::Propose {
+            msg: SudoMsg::UpdatePause(true),
+        },
+    )
+    .unwrap();
+    execute_multisig(
+        &mut app,
+        contract_addr.clone(),
+        MEMBER1,
+        MultisigExecuteMsg::Propose {
+            msg: update_multisig_msg(3),
+        },
+    )
+    .unwrap();
+    execute_multisig(
+        &mut app,
+        contract_addr.clone(),
+        MEMBER2,
+        MultisigExecuteMsg::Vote {
+            id: 2,
+            approve: true,
+        },
+    )
+    .unwrap();
+    execute_multisig(
+        &mut app,
+        contract_addr.clone(),
+        MEMBER1,
+        MultisigExecuteMsg::Execute { id: 2 },
+    )
+    .unwrap();
+
+    // The open proposal was made for the old threshold, it's gone with its votes
+    assert!(query_proposal(&app, contract_addr.clone(), 1).is_err());
+    assert!(query_votes(&app, contract_addr.clone(), 1)
+        .unwrap()
+        .is_empty());
+    let err = execute_multisig(
+        &mut app,
+        contract_addr,
+        MEMBER2,
+        MultisigExecuteMsg::Vote {
+            id: 1,
+            approve: true,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NoProposal { id: 1 });
+}
@@ -0,0 +1,112 @@
+use cosmwasm_std::{Addr, Uint128};
+use cw_multi_test::Executor;
+use cw_utils::Expiration;
+
+use crate::error::ContractError;
+
+use super::utils::{
+    executes::{
+        execute_accept_admin, sudo_propose_admin, sudo_renounce_admin, sudo_update_bank_limit,
+    },
+    queries::query_ownership,
+    setup::{
+        base_instantiate_msg, flip_contract, setup_base_contract, CREATOR_ADDR, FLIPPER_ADDR,
+        FLIPPER_ADDR2,
+    },
+};
+
+#[test]
+fn test_instantiate_admin() {
+    let (mut app, _) = setup_base_contract();
+    let code_id = app.store_code(flip_contract());
+
+    // Admin is the one in the msg, not the sender
+    let contract_addr = app
+        .instantiate_contract(
+            code_id,
+            Addr::unchecked(FLIPPER_ADDR),
+            &base_instantiate_msg(),
+            &[],
+            "flip contract",
+            None,
+        )
+        .unwrap();
+
+    let ownership = query_ownership(&app, contract_addr).unwrap();
+    assert_eq!(ownership.admin, Some(Addr::unchecked(CREATOR_ADDR)));
+    assert_eq!(ownership.pending_admin, None);
+}
+
+#[test]
+fn test_admin_transfer() {
+    let (mut app, contract_addr) = setup_base_contract();
+    let admin = Addr::unchecked(CREATOR_ADDR);
+    let new_admin = Addr::unchecked(FLIPPER_ADDR);
+
+    let err = sudo_propose_admin(
+        &mut app,
+        contract_addr.clone(),
+        new_admin.clone(),
+        FLIPPER_ADDR,
+        None,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized);
+
+    let err = execute_accept_admin(&mut app, contract_addr.clone(), new_admin.clone()).unwrap_err();
+    assert_eq!(err, ContractError::NoPendingAdmin);
+
+    let expires = Expiration::AtHeight(app.block_info().height + 10);
+    sudo_propose_admin(
+        &mut app,
+        contract_addr.clone(),
+        admin.clone(),
+        FLIPPER_ADDR,
+        Some(expires),
+    )
+    .unwrap();
+
+    // Only the proposed admin can accept
+    let err = execute_accept_admin(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR2),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized);
+
+    // Old admin is still the admin until accepted
+    sudo_update_bank_limit(&mut app, contract_addr.clone(), Uint128::new(1)).unwrap();
+
+    app.update_block(|block| block.height += 10);
+    let err = execute_accept_admin(&mut app, contract_addr.clone(), new_admin.clone()).unwrap_err();
+    assert_eq!(err, ContractError::AdminProposalExpired);
+
+    sudo_propose_admin(
+        &mut app,
+        contract_addr.clone(),
+        admin.clone(),
+        FLIPPER_ADDR,
+        None,
+    )
+    .unwrap();
+    execute_accept_admin(&mut app, contract_addr.clone(), new_admin.clone()).unwrap();
+
+    let ownership = query_ownership(&app, contract_addr.clone()).unwrap();
+    assert_eq!(ownership.admin, Some(new_admin.clone()));
+    assert_eq!(ownership.pending_admin, None);
+
+    let err = sudo_renounce_admin(&mut app, contract_addr.clone(), admin).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized);
+
+    sudo_renounce_admin(&mut app, contract_addr.clone(), new_admin.clone()).unwrap();
+    assert_eq!(
+        query_ownership(&app, contract_addr.clone()).unwrap().admin,
+        None
+    );
+
+    // Nobody can call sudo msgs anymore
+    let err =
+        sudo_propose_admin(&mut app, contract_addr, new_admin, FLIPPER_ADDR, None).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized);
+}
@@ -1,12 +1,15 @@
-use cosmwasm_std::Addr;
+use cosmwasm_std::{Addr, Decimal, Uint128};
 
 use crate::{contract::MIN_BET, types::PickTypes};
 
 use super::utils::{
-    executes::{execute_do_flips, execute_start_flip},
-    helpers::{add_balance, add_balances, FLIPPER_PREFIX, MIN_FEES, MIN_FUNDS},
-    queries::{query_fees, query_last_flips},
-    setup::{setup_base_contract, FLIPPER_ADDR, FLIPPER_ADDR2},
+    executes::{execute_do_flips, execute_start_flip, sudo_distribute},
+    helpers::{
+        add_10_todo_flips, add_balance, add_balances, get_dist_result, FLIPPER_PREFIX, MIN_FEES,
+        MIN_FUNDS,
+    },
+    queries::{query_distributions, query_fees, query_holder_earnings, query_last_flips},
+    setup::{setup_base_contract, setup_contract, FLIPPER_ADDR, FLIPPER_ADDR2},
 };
 
 #[test]
@@ -197,3 +200,52 @@ fn test_get_flip_todo() {
     let flips = query_last_flips(&app, contract_addr).unwrap();
     assert_eq!(flips.len(), 1);
 }
+
+#[test]
+fn test_distribution_history() {
+    let (mut app, contract_addr) = setup_contract();
+
+    // No distributions yet
+    let distributions = query_distributions(&app, contract_addr.clone(), None, None).unwrap();
+    assert_eq!(distributions.len(), 0);
+    let earnings =
+        query_holder_earnings(&app, contract_addr.clone(), "flipper-728".to_string()).unwrap();
+    assert_eq!(earnings, Uint128::zero());
+
+    add_10_todo_flips(&mut app, contract_addr.clone());
+    execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+    let res = sudo_distribute(&mut app, contract_addr.clone()).unwrap();
+    let res_data = get_dist_result(res);
+
+    add_10_todo_flips(&mut app, contract_addr.clone());
+    execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+    sudo_distribute(&mut app, contract_addr.clone()).unwrap();
+
+    let distributions = query_distributions(&app, contract_addr.clone(), None, None).unwrap();
+    assert_eq!(distributions.len(), 2);
+    assert_eq!(distributions[0].id, 0);
+    assert_eq!(distributions[0].total_fees, res_data.total_fees);
+    assert_eq!(distributions[0].recipients[0].amount, res_data.team_paid);
+    assert_eq!(distributions[0].recipients[1].amount, res_data.reserve_paid);
+    assert_eq!(distributions[0].holders_paid, res_data.holders_paid);
+    assert_eq!(distributions[0].fees_per_token, res_data.fees_per_token);
+    assert_eq!(distributions[0].number_of_holders, 777);
+    assert_eq!(distributions[1].id, 1);
+
+    // Pagination
+    let distributions = query_distributions(&app, contract_addr.clone(), Some(0), Some(1)).unwrap();
+    assert_eq!(distributions.len(), 1);
+    assert_eq!(distributions[0].id, 1);
+
+    // flipper-728 holds a rare NFT (2 shares), and was paid in both distributions
+    let distributions = query_distributions(&app, contract_addr.clone(), None, None).unwrap();
+    let expected = distributions.iter().fold(Uint128::zero(), |acc, record| {
+        acc + record
+            .fees_per_token
+            .checked_mul(Decimal::from_atomics(Uint128::new(2), 0).unwrap())
+            .unwrap()
+            .to_uint_floor()
+    });
+    let earnings = query_holder_earnings(&app, contract_addr, "flipper-728".to_string()).unwrap();
+    assert_eq!(earnings, expected);
+}
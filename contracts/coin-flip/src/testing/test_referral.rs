@@ -0,0 +1,237 @@
+use cosmwasm_std::{Addr, Uint128};
+
+use crate::{
+    contract::MIN_BET,
+    error::ContractError,
+    msg::SudoMsg,
+    types::{Fees, PickTypes},
+};
+
+use super::utils::{
+    executes::{
+        execute_claim_referral_rewards, execute_register_referrer, execute_start_flip,
+        execute_sudo, sudo_distribute, sudo_update_fees,
+    },
+    helpers::{add_balance, get_dist_result, MIN_FEES, MIN_FUNDS},
+    queries::{
+        query_config, query_fees, query_referral_stats, query_referred_wallets, query_referrer,
+    },
+    setup::{setup_base_contract, CREATOR_ADDR, FLIPPER_ADDR, FLIPPER_ADDR2, NATIVE_DENOM},
+};
+
+const REFERRER_ADDR: &str = "referrer";
+
+#[test]
+fn test_register_referrer() {
+    let (mut app, contract_addr) = setup_base_contract();
+    let flipper = Addr::unchecked(FLIPPER_ADDR);
+
+    // Cannot refer yourself
+    let err = execute_register_referrer(
+        &mut app,
+        contract_addr.clone(),
+        flipper.clone(),
+        FLIPPER_ADDR,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::SelfReferral);
+
+    execute_register_referrer(
+        &mut app,
+        contract_addr.clone(),
+        flipper.clone(),
+        FLIPPER_ADDR2,
+    )
+    .unwrap();
+    assert_eq!(
+        query_referrer(&app, contract_addr.clone(), FLIPPER_ADDR).unwrap(),
+        Some(Addr::unchecked(FLIPPER_ADDR2))
+    );
+
+    // Referrer can only be registered once
+    let err = execute_register_referrer(&mut app, contract_addr.clone(), flipper, REFERRER_ADDR)
+        .unwrap_err();
+    assert_eq!(err, ContractError::ReferrerAlreadySet);
+
+    // Cannot refer the wallet that referred you
+    let err = execute_register_referrer(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR2),
+        FLIPPER_ADDR,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::SelfReferral);
+
+    execute_register_referrer(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(REFERRER_ADDR),
+        FLIPPER_ADDR2,
+    )
+    .unwrap();
+
+    let stats = query_referral_stats(&app, contract_addr.clone(), FLIPPER_ADDR2).unwrap();
+    assert_eq!(stats.referred_wallets, 2);
+    let wallets = query_referred_wallets(&app, contract_addr, FLIPPER_ADDR2).unwrap();
+    assert_eq!(
+        wallets,
+        vec![
+            Addr::unchecked(REFERRER_ADDR),
+            Addr::unchecked(FLIPPER_ADDR)
+        ]
+    );
+}
+
+#[test]
+fn test_referral_rewards() {
+    let (mut app, contract_addr) = setup_base_contract();
+    add_balance(&mut app, contract_addr.clone(), 30000000000);
+
+    let config = query_config(&app, contract_addr.clone()).unwrap();
+    sudo_update_fees(
+        &mut app,
+        contract_addr.clone(),
+        Fees {
+            referral_bps: 1000, // 10% of the flip fee
+            ..config.fees
+        },
+    )
+    .unwrap();
+
+    execute_register_referrer(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        REFERRER_ADDR,
+    )
+    .unwrap();
+
+    // Nothing to claim yet
+    let err = execute_claim_referral_rewards(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(REFERRER_ADDR),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NoReferralRewards);
+
+    execute_start_flip(
+        &mut app,
+        contract_addr.clone(),
+        PickTypes::Heads,
+        MIN_BET,
+        Addr::unchecked(FLIPPER_ADDR),
+        MIN_FUNDS,
+    )
+    .unwrap();
+
+    // Flipper pays the same fee, 10% of it is credited to the referrer
+    let reward = Uint128::new(17500);
+    assert_eq!(query_fees(&app, contract_addr.clone()).unwrap(), MIN_FEES);
+    let stats = query_referral_stats(&app, contract_addr.clone(), REFERRER_ADDR).unwrap();
+    assert_eq!(stats.volume, MIN_BET);
+    assert_eq!(stats.earned, reward);
+    assert_eq!(stats.claimable, reward);
+
+    // Referral reward is taken before the fees split
+    let res = sudo_distribute(&mut app, contract_addr.clone()).unwrap();
+    let res_data = get_dist_result(res);
+    assert_eq!(res_data.total_fees, MIN_FEES);
+    assert_eq!(res_data.team_paid, Uint128::new(78750));
+    assert_eq!(res_data.reserve_paid, Uint128::new(78750));
+    assert_eq!(query_fees(&app, contract_addr.clone()).unwrap(), reward);
+
+    execute_claim_referral_rewards(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(REFERRER_ADDR),
+    )
+    .unwrap();
+
+    let balance = app
+        .wrap()
+        .query_balance(REFERRER_ADDR, NATIVE_DENOM)
+        .unwrap();
+    assert_eq!(balance.amount, reward);
+    assert_eq!(
+        query_fees(&app, contract_addr.clone()).unwrap(),
+        Uint128::zero()
+    );
+
+    let stats = query_referral_stats(&app, contract_addr.clone(), REFERRER_ADDR).unwrap();
+    assert_eq!(stats.claimed, reward);
+    assert_eq!(stats.claimable, Uint128::zero());
+
+    let err =
+        execute_claim_referral_rewards(&mut app, contract_addr, Addr::unchecked(REFERRER_ADDR))
+            .unwrap_err();
+    assert_eq!(err, ContractError::NoReferralRewards);
+}
+
+#[test]
+fn test_refund_keeps_referral_rewards() {
+    let (mut app, contract_addr) = setup_base_contract();
+    add_balance(&mut app, contract_addr.clone(), 30000000000);
+    let flipper = Addr::unchecked(FLIPPER_ADDR);
+
+    let config = query_config(&app, contract_addr.clone()).unwrap();
+    sudo_update_fees(
+        &mut app,
+        contract_addr.clone(),
+        Fees {
+            referral_bps: 1000,
+            ..config.fees
+        },
+    )
+    .unwrap();
+    execute_register_referrer(
+        &mut app,
+        contract_addr.clone(),
+        flipper.clone(),
+        REFERRER_ADDR,
+    )
+    .unwrap();
+
+    let balance_before = app
+        .wrap()
+        .query_balance(&flipper, NATIVE_DENOM)
+        .unwrap()
+        .amount;
+    execute_start_flip(
+        &mut app,
+        contract_addr.clone(),
+        PickTypes::Heads,
+        MIN_BET,
+        flipper.clone(),
+        MIN_FUNDS,
+    )
+    .unwrap();
+    execute_sudo(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(CREATOR_ADDR),
+        SudoMsg::RefundPendingFlips {},
+    )
+    .unwrap();
+
+    // Only the bet is sent back, the fee and the referrer credit stay
+    let balance_after = app
+        .wrap()
+        .query_balance(&flipper, NATIVE_DENOM)
+        .unwrap()
+        .amount;
+    assert_eq!(balance_before - balance_after, MIN_FEES);
+    assert_eq!(query_fees(&app, contract_addr.clone()).unwrap(), MIN_FEES);
+    let reward = Uint128::new(17500);
+    let stats = query_referral_stats(&app, contract_addr.clone(), REFERRER_ADDR).unwrap();
+    assert_eq!(stats.claimable, reward);
+
+    execute_claim_referral_rewards(&mut app, contract_addr, Addr::unchecked(REFERRER_ADDR))
+        .unwrap();
+    let balance = app
+        .wrap()
+        .query_balance(REFERRER_ADDR, NATIVE_DENOM)
+        .unwrap();
+    assert_eq!(balance.amount, reward);
+}
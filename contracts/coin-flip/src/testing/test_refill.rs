@@ -0,0 +1,227 @@
+use cosmwasm_std::{Addr, Empty, Uint128};
+use cw_multi_test::{AppResponse, Executor};
+use cw_utils::Duration;
+
+use crate::{error::ContractError, types::Treasury};
+
+use super::utils::{
+    executes::{
+        execute_do_flips, execute_refill_bank, sudo_distribute, sudo_update_bank_limit,
+        sudo_update_treasury,
+    },
+    helpers::{add_10_todo_flips, add_balance},
+    mocks::treasury_contract,
+    queries::{query_dry_distribution, query_refill_status},
+    setup::{setup_base_contract, BaseApp, CREATOR_ADDR, FLIPPER_ADDR, NATIVE_DENOM},
+};
+
+const DAY: u64 = 24 * 60 * 60;
+
+fn setup_treasury(app: &mut BaseApp, balance: u128) -> Addr {
+    let code_id = app.store_code(treasury_contract());
+    let treasury_addr = app
+        .instantiate_contract(
+            code_id,
+            Addr::unchecked(CREATOR_ADDR),
+            &Empty {},
+            &[],
+            "treasury contract",
+            None,
+        )
+        .unwrap();
+    if balance > 0 {
+        add_balance(app, treasury_addr.clone(), balance);
+    }
+    treasury_addr
+}
+
+#[test]
+fn test_refill_bank() {
+    let (mut app, contract_addr) = setup_base_contract();
+    let keeper = Addr::unchecked(FLIPPER_ADDR);
+    // Bank is 1k under the limit (30k)
+    add_balance(&mut app, contract_addr.clone(), 29000000000);
+
+    let err = execute_refill_bank(&mut app, contract_addr.clone(), keeper.clone()).unwrap_err();
+    assert_eq!(err, ContractError::TreasuryNotSet);
+
+    let treasury_addr = setup_treasury(&mut app, 10000000000);
+    let err = sudo_update_treasury(
+        &mut app,
+        contract_addr.clone(),
+        Some(Treasury {
+            addr: treasury_addr.to_string(),
+            refill_cap: Uint128::new(600000000),
+            period: Duration::Time(0),
+        }),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidRefillPeriod);
+
+    sudo_update_treasury(
+        &mut app,
+        contract_addr.clone(),
+        Some(Treasury {
+            addr: treasury_addr.to_string(),
+            refill_cap: Uint128::new(600000000),
+            period: Duration::Time(DAY),
+        }),
+    )
+    .unwrap();
+
+    let status = query_refill_status(&app, contract_addr.clone()).unwrap();
+    assert_eq!(status.bank, Uint128::new(29000000000));
+    assert_eq!(status.refill_amount, Uint128::new(600000000));
+    assert_eq!(status.period, None);
+
+    // We only get the cap of the period
+    let res = execute_refill_bank(&mut app, contract_addr.clone(), keeper.clone()).unwrap();
+    let event = res
+        .events
+        .iter()
+        .find(|event| event.ty == "wasm-bank_refill")
+        .unwrap();
+    let amount = event
+        .attributes
+        .iter()
+        .find(|attr| attr.key == "amount")
+        .unwrap();
+    assert_eq!(amount.value, "600000000");
+
+    let balance = app
+        .wrap()
+        .query_balance(&contract_addr, NATIVE_DENOM)
+        .unwrap();
+    assert_eq!(balance.amount, Uint128::new(29600000000));
+
+    let status = query_refill_status(&app, contract_addr.clone()).unwrap();
+    assert_eq!(status.refill_amount, Uint128::zero());
+    let period = status.period.unwrap();
+    assert_eq!(period.refilled, Uint128::new(600000000));
+
+    let err = execute_refill_bank(&mut app, contract_addr.clone(), keeper.clone()).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::RefillCapReached {
+            period_ends: period.ends
+        }
+    );
+
+    // Next period we get the rest of what we miss
+    app.update_block(|block| {
+        block.time = block.time.plus_seconds(DAY);
+        block.height += 1;
+    });
+    execute_refill_bank(&mut app, contract_addr.clone(), keeper.clone()).unwrap();
+    let balance = app
+        .wrap()
+        .query_balance(&contract_addr, NATIVE_DENOM)
+        .unwrap();
+    assert_eq!(balance.amount, Uint128::new(30000000000));
+
+    let err = execute_refill_bank(&mut app, contract_addr, keeper).unwrap_err();
+    assert_eq!(err, ContractError::BankAboveLimit);
+}
+
+#[test]
+fn test_pending_flips_are_not_bank() {
+    let (mut app, contract_addr) = setup_base_contract();
+
+    // Bank is at the limit, but we owe 100M to the pending flips
+    add_10_todo_flips(&mut app, contract_addr.clone());
+
+    // 30k + 51.75M sent - 1.75M fees - 100M liabilities
+    let status = query_refill_status(&app, contract_addr.clone()).unwrap();
+    assert_eq!(status.bank, Uint128::new(29950000000));
+
+    // Reserve fees are kept in the bank to get back to the limit
+    let dry_dist = query_dry_distribution(&app, contract_addr).unwrap();
+    let reserve = dry_dist
+        .recipients
+        .iter()
+        .find(|recipient| recipient.name == "reserve")
+        .unwrap();
+    assert!(!reserve.amount.is_zero());
+    assert_eq!(reserve.to_send, Uint128::zero());
+}
+
+fn refill_event_amount(res: &AppResponse, ty: &str) -> Option<String> {
+    res.events
+        .iter()
+        .find(|event| event.ty == ty)
+        .and_then(|event| event.attributes.iter().find(|attr| attr.key == "amount"))
+        .map(|attr| attr.value.clone())
+}
+
+#[test]
+fn test_auto_refill() {
+    let (mut app, contract_addr) = setup_base_contract();
+    let treasury_addr = setup_treasury(&mut app, 10000000000);
+    sudo_update_treasury(
+        &mut app,
+        contract_addr.clone(),
+        Some(Treasury {
+            addr: treasury_addr.to_string(),
+            refill_cap: Uint128::new(600000000),
+            period: Duration::Time(DAY),
+        }),
+    )
+    .unwrap();
+    // Bank is at least 1k under the limit whatever the flips give
+    sudo_update_bank_limit(&mut app, contract_addr.clone(), Uint128::new(31000000000)).unwrap();
+
+    // Settling the flips asks the treasury for the cap of the period
+    add_10_todo_flips(&mut app, contract_addr.clone());
+    let res = execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+    assert_eq!(
+        refill_event_amount(&res, "wasm-bank_refill"),
+        Some("600000000".to_string())
+    );
+    let status = query_refill_status(&app, contract_addr.clone()).unwrap();
+    assert_eq!(status.period.unwrap().refilled, Uint128::new(600000000));
+    assert_eq!(status.refill_amount, Uint128::zero());
+
+    // Cap is reached, the distribution doesn't ask for more
+    let res = sudo_distribute(&mut app, contract_addr.clone()).unwrap();
+    assert_eq!(refill_event_amount(&res, "wasm-bank_refill"), None);
+
+    // Next period the distribution refills the bank
+    app.update_block(|block| {
+        block.time = block.time.plus_seconds(DAY);
+        block.height += 1;
+    });
+    add_10_todo_flips(&mut app, contract_addr.clone());
+    app.update_block(|block| block.height += 1);
+    let res = sudo_distribute(&mut app, contract_addr).unwrap();
+    assert!(refill_event_amount(&res, "wasm-bank_refill").is_some());
+}
+
+#[test]
+fn test_auto_refill_failure_does_not_block_flips() {
+    let (mut app, contract_addr) = setup_base_contract();
+    // Treasury can't pay
+    let treasury_addr = setup_treasury(&mut app, 0);
+    sudo_update_treasury(
+        &mut app,
+        contract_addr.clone(),
+        Some(Treasury {
+            addr: treasury_addr.to_string(),
+            refill_cap: Uint128::new(600000000),
+            period: Duration::Time(DAY),
+        }),
+    )
+    .unwrap();
+    sudo_update_bank_limit(&mut app, contract_addr.clone(), Uint128::new(31000000000)).unwrap();
+
+    add_10_todo_flips(&mut app, contract_addr.clone());
+    let res = execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+    assert_eq!(
+        refill_event_amount(&res, "wasm-bank_refill_failed"),
+        Some("600000000".to_string())
+    );
+
+    // The refill is not booked, we can still request it
+    let status = query_refill_status(&app, contract_addr).unwrap();
+    assert_eq!(status.period.unwrap().refilled, Uint128::zero());
+    assert_eq!(status.refill_amount, Uint128::new(600000000));
+}
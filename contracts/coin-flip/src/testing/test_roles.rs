@@ -0,0 +1,213 @@
+use cosmwasm_std::Addr;
+
+use crate::{
+    error::ContractError,
+    msg::SudoMsg,
+    types::{ConfigChange, Role},
+};
+
+use super::utils::{
+    executes::{execute_sudo, sudo_grant_role, sudo_renounce_admin, sudo_revoke_role},
+    queries::{query_config, query_has_role, query_role_members},
+    setup::{setup_base_contract, CREATOR_ADDR, FLIPPER_ADDR, FLIPPER_ADDR2},
+};
+
+#[test]
+fn test_pauser_role() {
+    let (mut app, contract_addr) = setup_base_contract();
+    let pauser = Addr::unchecked(FLIPPER_ADDR);
+
+    let err = execute_sudo(
+        &mut app,
+        contract_addr.clone(),
+        pauser.clone(),
+        SudoMsg::UpdatePause(true),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized);
+
+    sudo_grant_role(&mut app, contract_addr.clone(), Role::Pauser, FLIPPER_ADDR).unwrap();
+    assert!(query_has_role(&app, contract_addr.clone(), Role::Pauser, FLIPPER_ADDR).unwrap());
+    assert_eq!(
+        query_role_members(&app, contract_addr.clone(), Role::Pauser).unwrap(),
+        vec![pauser.clone()]
+    );
+
+    execute_sudo(
+        &mut app,
+        contract_addr.clone(),
+        pauser.clone(),
+        SudoMsg::UpdatePause(true),
+    )
+    .unwrap();
+    assert!(
+        query_config(&app, contract_addr.clone())
+            .unwrap()
+            .paused
+            .new_flips
+    );
+
+    // Pauser can only pause
+    let err = execute_sudo(
+        &mut app,
+        contract_addr.clone(),
+        pauser.clone(),
+        SudoMsg::UpdatePause(false),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized);
+    let effective_at = app.block_info().time;
+    let err = execute_sudo(
+        &mut app,
+        contract_addr.clone(),
+        pauser.clone(),
+        SudoMsg::ProposeConfigChange {
+            change: ConfigChange::UpdateMaxBetBankBps { bps: None },
+            effective_at,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized);
+
+    sudo_revoke_role(&mut app, contract_addr.clone(), Role::Pauser, FLIPPER_ADDR).unwrap();
+    assert!(!query_has_role(&app, contract_addr.clone(), Role::Pauser, FLIPPER_ADDR).unwrap());
+    let err =
+        execute_sudo(&mut app, contract_addr, pauser, SudoMsg::UpdatePause(true)).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized);
+}
+
+#[test]
+fn test_super_admin_role() {
+    let (mut app, contract_addr) = setup_base_contract();
+    let super_admin = Addr::unchecked(FLIPPER_ADDR);
+
+    // Admin has every role
+    assert!(query_has_role(&app, contract_addr.clone(), Role::Distributor, CREATOR_ADDR).unwrap());
+
+    // Only the admin and super admins can grant roles
+    let err = execute_sudo(
+        &mut app,
+        contract_addr.clone(),
+        super_admin.clone(),
+        SudoMsg::GrantRole {
+            role: Role::SuperAdmin,
+            address: FLIPPER_ADDR.to_string(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized);
+
+    sudo_grant_role(
+        &mut app,
+        contract_addr.clone(),
+        Role::SuperAdmin,
+        FLIPPER_ADDR,
+    )
+    .unwrap();
+    assert!(query_has_role(&app, contract_addr.clone(), Role::FeeManager, FLIPPER_ADDR).unwrap());
+
+    execute_sudo(
+        &mut app,
+        contract_addr.clone(),
+        super_admin.clone(),
+        SudoMsg::GrantRole {
+            role: Role::Distributor,
+            address: FLIPPER_ADDR2.to_string(),
+        },
+    )
+    .unwrap();
+    assert!(query_has_role(
+        &app,
+        contract_addr.clone(),
+        Role::Distributor,
+        FLIPPER_ADDR2
+    )
+    .unwrap());
+    assert!(!query_has_role(&app, contract_addr.clone(), Role::FeeManager, FLIPPER_ADDR2).unwrap());
+
+    // Only the admin can grant or revoke super admin
+    let err = execute_sudo(
+        &mut app,
+        contract_addr.clone(),
+        super_admin.clone(),
+        SudoMsg::GrantRole {
+            role: Role::SuperAdmin,
+            address: FLIPPER_ADDR2.to_string(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized);
+    let err = execute_sudo(
+        &mut app,
+        contract_addr.clone(),
+        super_admin.clone(),
+        SudoMsg::RevokeRole {
+            role: Role::SuperAdmin,
+            address: FLIPPER_ADDR.to_string(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized);
+
+    execute_sudo(
+        &mut app,
+        contract_addr.clone(),
+        super_admin.clone(),
+        SudoMsg::UpdatePause(true),
+    )
+    .unwrap();
+    execute_sudo(
+        &mut app,
+        contract_addr.clone(),
+        super_admin.clone(),
+        SudoMsg::UpdatePause(false),
+    )
+    .unwrap();
+
+    // Transferring the admin stays with the admin
+    let err = execute_sudo(
+        &mut app,
+        contract_addr,
+        super_admin,
+        SudoMsg::RenounceAdmin {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized);
+}
+
+#[test]
+fn test_renounce_admin_removes_roles() {
+    let (mut app, contract_addr) = setup_base_contract();
+
+    sudo_grant_role(
+        &mut app,
+        contract_addr.clone(),
+        Role::SuperAdmin,
+        FLIPPER_ADDR,
+    )
+    .unwrap();
+    sudo_grant_role(&mut app, contract_addr.clone(), Role::Pauser, FLIPPER_ADDR2).unwrap();
+
+    sudo_renounce_admin(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(CREATOR_ADDR),
+    )
+    .unwrap();
+
+    assert!(
+        query_role_members(&app, contract_addr.clone(), Role::SuperAdmin)
+            .unwrap()
+            .is_empty()
+    );
+    assert!(!query_has_role(&app, contract_addr.clone(), Role::Pauser, FLIPPER_ADDR2).unwrap());
+
+    let err = execute_sudo(
+        &mut app,
+        contract_addr,
+        Addr::unchecked(FLIPPER_ADDR),
+        SudoMsg::UpdatePause(true),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized);
+}
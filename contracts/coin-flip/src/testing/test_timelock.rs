@@ -0,0 +1,297 @@
+use cosmwasm_std::{Addr, Uint128};
+use cw_multi_test::Executor;
+
+use crate::{
+    contract::{DEFAULT_TIMELOCK_DELAY, MAX_BET},
+    error::ContractError,
+    msg::{InstantiateMsg, SudoMsg},
+    types::{ConfigChange, ConfigUpdate, Role},
+};
+
+use super::utils::{
+    executes::{
+        execute_pending, execute_sudo, sudo_grant_role, sudo_propose_config_change,
+        sudo_renounce_admin, sudo_revoke_role, sudo_update_bet_limit, sudo_update_pause,
+    },
+    queries::{query_config, query_pending_changes},
+    setup::{base_instantiate_msg, flip_contract, setup_base_contract, CREATOR_ADDR, FLIPPER_ADDR},
+};
+
+#[test]
+fn test_timelocked_changes() {
+    let (mut app, _) = setup_base_contract();
+    let code_id = app.store_code(flip_contract());
+    let contract_addr = app
+        .instantiate_contract(
+            code_id,
+            Addr::unchecked(CREATOR_ADDR),
+            &InstantiateMsg {
+                timelock_delay: None,
+                ..base_instantiate_msg()
+            },
+            &[],
+            "flip contract",
+            None,
+        )
+        .unwrap();
+    let now = app.block_info().time;
+    let new_limit = Uint128::new(1000);
+
+    let err = sudo_propose_config_change(
+        &mut app,
+        contract_addr.clone(),
+        ConfigChange::UpdateBankLimit { limit: new_limit },
+        now.plus_seconds(60 * 60),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::ChangeTooSoon {
+            min_effective_at: now.plus_seconds(DEFAULT_TIMELOCK_DELAY)
+        }
+    );
+
+    let effective_at = now.plus_seconds(DEFAULT_TIMELOCK_DELAY);
+    sudo_propose_config_change(
+        &mut app,
+        contract_addr.clone(),
+        ConfigChange::UpdateBankLimit { limit: new_limit },
+        effective_at,
+    )
+    .unwrap();
+    sudo_propose_config_change(
+        &mut app,
+        contract_addr.clone(),
+        ConfigChange::UpdateBetLimit {
+            min_bet: Uint128::new(1),
+            max_bet: Uint128::new(2),
+        },
+        effective_at,
+    )
+    .unwrap();
+    let pending = query_pending_changes(&app, contract_addr.clone()).unwrap();
+    assert_eq!(pending.len(), 2);
+    assert_eq!(pending[0].effective_at, effective_at);
+
+    let err = execute_pending(&mut app, contract_addr.clone(), 1).unwrap_err();
+    assert_eq!(err, ContractError::ChangeNotEffective { effective_at });
+
+    // Only who can propose a change can cancel it
+    let err = execute_sudo(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        SudoMsg::CancelConfigChange { id: 2 },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized);
+    execute_sudo(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(CREATOR_ADDR),
+        SudoMsg::CancelConfigChange { id: 2 },
+    )
+    .unwrap();
+
+    // Pause is still instant
+    sudo_update_pause(&mut app, contract_addr.clone(), true).unwrap();
+    assert!(
+        query_config(&app, contract_addr.clone())
+            .unwrap()
+            .paused
+            .new_flips
+    );
+
+    app.update_block(|block| {
+        block.time = block.time.plus_seconds(DEFAULT_TIMELOCK_DELAY);
+        block.height += 1;
+    });
+    let err = execute_pending(&mut app, contract_addr.clone(), 2).unwrap_err();
+    assert_eq!(err, ContractError::NoPendingChange { id: 2 });
+    execute_pending(&mut app, contract_addr.clone(), 1).unwrap();
+
+    let config = query_config(&app, contract_addr.clone()).unwrap();
+    assert_eq!(config.bank_limit, new_limit);
+    assert_eq!(config.max_bet_limit, MAX_BET);
+    assert!(query_pending_changes(&app, contract_addr)
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn test_invalid_config_changes() {
+    let (mut app, contract_addr) = setup_base_contract();
+    let code_id = app.store_code(flip_contract());
+    let err = app
+        .instantiate_contract(
+            code_id,
+            Addr::unchecked(CREATOR_ADDR),
+            &InstantiateMsg {
+                timelock_delay: Some(0),
+                ..base_instantiate_msg()
+            },
+            &[],
+            "flip contract",
+            None,
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::InvalidTimelockDelay
+    );
+
+    // Changes are checked against the current config when proposed
+    let now = app.block_info().time;
+    let err = sudo_propose_config_change(
+        &mut app,
+        contract_addr.clone(),
+        ConfigChange::UpdateBetLimit {
+            min_bet: Uint128::new(2),
+            max_bet: Uint128::new(1),
+        },
+        now.plus_seconds(1),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidBetLimits {
+            min: Uint128::new(2),
+            max: Uint128::new(1)
+        }
+    );
+    let err = sudo_propose_config_change(
+        &mut app,
+        contract_addr.clone(),
+        ConfigChange::UpdateConfig {
+            update: Box::new(ConfigUpdate {
+                min_bet_limit: Some(MAX_BET + Uint128::one()),
+                ..Default::default()
+            }),
+        },
+        now.plus_seconds(1),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidBetLimits {
+            min: MAX_BET + Uint128::one(),
+            max: MAX_BET
+        }
+    );
+    let err = sudo_propose_config_change(
+        &mut app,
+        contract_addr.clone(),
+        ConfigChange::UpdateConfig {
+            update: Box::new(ConfigUpdate {
+                flips_per_block_limit: Some(0),
+                ..Default::default()
+            }),
+        },
+        now.plus_seconds(1),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidFlipsPerBlockLimit);
+    let err = sudo_propose_config_change(
+        &mut app,
+        contract_addr.clone(),
+        ConfigChange::UpdateTimelockDelay { delay: 0 },
+        now.plus_seconds(1),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidTimelockDelay);
+
+    // And again when applied, the config may have changed in between
+    sudo_propose_config_change(
+        &mut app,
+        contract_addr.clone(),
+        ConfigChange::UpdateConfig {
+            update: Box::new(ConfigUpdate {
+                min_bet_limit: Some(Uint128::new(10)),
+                ..Default::default()
+            }),
+        },
+        now.plus_seconds(1),
+    )
+    .unwrap();
+    sudo_update_bet_limit(
+        &mut app,
+        contract_addr.clone(),
+        Uint128::new(1),
+        Uint128::new(5),
+    )
+    .unwrap();
+    let err = execute_pending(&mut app, contract_addr, 1).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidBetLimits {
+            min: Uint128::new(10),
+            max: Uint128::new(5)
+        }
+    );
+}
+
+#[test]
+fn test_pending_changes_need_proposer_role() {
+    let (mut app, contract_addr) = setup_base_contract();
+    let delay = query_config(&app, contract_addr.clone())
+        .unwrap()
+        .timelock_delay;
+    let effective_at = app.block_info().time.plus_seconds(delay);
+
+    sudo_grant_role(
+        &mut app,
+        contract_addr.clone(),
+        Role::FeeManager,
+        FLIPPER_ADDR,
+    )
+    .unwrap();
+    execute_sudo(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        SudoMsg::ProposeConfigChange {
+            change: ConfigChange::UpdateMinHolderPayout {
+                amount: Uint128::new(1),
+            },
+            effective_at,
+        },
+    )
+    .unwrap();
+    sudo_propose_config_change(
+        &mut app,
+        contract_addr.clone(),
+        ConfigChange::UpdateBankLimit {
+            limit: Uint128::new(1000),
+        },
+        effective_at,
+    )
+    .unwrap();
+
+    // The fee manager lost the role before the delay was over
+    sudo_revoke_role(
+        &mut app,
+        contract_addr.clone(),
+        Role::FeeManager,
+        FLIPPER_ADDR,
+    )
+    .unwrap();
+    app.update_block(|block| {
+        block.time = block.time.plus_seconds(delay);
+        block.height += 1;
+    });
+    let err = execute_pending(&mut app, contract_addr.clone(), 1).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized);
+
+    // Changes queued by the admin are dropped when it renounces
+    sudo_renounce_admin(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(CREATOR_ADDR),
+    )
+    .unwrap();
+    assert!(query_pending_changes(&app, contract_addr.clone())
+        .unwrap()
+        .is_empty());
+    let err = execute_pending(&mut app, contract_addr, 2).unwrap_err();
+    assert_eq!(err, ContractError::NoPendingChange { id: 2 });
+}
@@ -0,0 +1,202 @@
+use cosmwasm_std::{Addr, Uint128};
+
+use crate::{
+    contract::{DEFAULT_VAULT_COOLDOWN, MIN_BET},
+    error::ContractError,
+};
+
+use super::utils::{
+    executes::{
+        execute_do_flips, execute_vault_cancel_withdraw, execute_vault_deposit,
+        execute_vault_request_withdraw, execute_vault_withdraw,
+    },
+    helpers::{add_10_todo_flips, add_balance, MIN_FEES},
+    queries::{query_lp_position, query_vault},
+    setup::{setup_base_contract, NATIVE_DENOM},
+};
+
+const LP_ADDR: &str = "lp_wallet";
+const LP_ADDR2: &str = "lp_wallet2";
+const LP_DEPOSIT: Uint128 = Uint128::new(10_000_000_000);
+
+#[test]
+fn test_vault_takes_house_pnl() {
+    let (mut app, contract_addr) = setup_base_contract();
+    add_balance(&mut app, contract_addr.clone(), 30000000000);
+    add_balance(&mut app, Addr::unchecked(LP_ADDR), LP_DEPOSIT.u128());
+    add_balance(&mut app, Addr::unchecked(LP_ADDR2), LP_DEPOSIT.u128());
+
+    execute_vault_deposit(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(LP_ADDR),
+        LP_DEPOSIT,
+    )
+    .unwrap();
+    let vault = query_vault(&app, contract_addr.clone()).unwrap();
+    assert_eq!(vault.total_shares, LP_DEPOSIT);
+    assert_eq!(vault.assets, LP_DEPOSIT);
+    assert_eq!(vault.cooldown, DEFAULT_VAULT_COOLDOWN);
+
+    add_10_todo_flips(&mut app, contract_addr.clone());
+    let balance_before = app
+        .wrap()
+        .query_balance(&contract_addr, NATIVE_DENOM)
+        .unwrap()
+        .amount;
+    execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+    let balance_after = app
+        .wrap()
+        .query_balance(&contract_addr, NATIVE_DENOM)
+        .unwrap()
+        .amount;
+
+    // Winners get double their bet
+    let wins = ((balance_before - balance_after) / (MIN_BET * Uint128::new(2))).u128();
+    let house_profit = MIN_BET * Uint128::new(10 - wins);
+    let house_loss = MIN_BET * Uint128::new(wins);
+
+    // LP owns 1/4 of the bank (10k of 40k), so it gets 1/4 of the PnL
+    let vault = query_vault(&app, contract_addr.clone()).unwrap();
+    let expected_assets =
+        LP_DEPOSIT + house_profit / Uint128::new(4) - house_loss / Uint128::new(4);
+    assert_eq!(vault.assets, expected_assets);
+    assert_eq!(vault.total_shares, LP_DEPOSIT);
+    assert_eq!(
+        query_lp_position(&app, contract_addr.clone(), LP_ADDR)
+            .unwrap()
+            .value,
+        expected_assets
+    );
+    // Fees are not part of the vault
+    assert_eq!(
+        balance_after,
+        Uint128::new(30000000000) + LP_DEPOSIT + MIN_FEES * Uint128::new(10) + house_profit
+            - house_loss
+    );
+
+    // New LP gets shares by the current share value
+    execute_vault_deposit(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(LP_ADDR2),
+        LP_DEPOSIT,
+    )
+    .unwrap();
+    let position = query_lp_position(&app, contract_addr, LP_ADDR2).unwrap();
+    assert_eq!(
+        position.shares,
+        LP_DEPOSIT.multiply_ratio(LP_DEPOSIT, expected_assets)
+    );
+}
+
+#[test]
+fn test_vault_withdraw_cooldown() {
+    let (mut app, contract_addr) = setup_base_contract();
+    add_balance(&mut app, contract_addr.clone(), 30000000000);
+    add_balance(&mut app, Addr::unchecked(LP_ADDR), LP_DEPOSIT.u128());
+    let lp = Addr::unchecked(LP_ADDR);
+
+    execute_vault_deposit(&mut app, contract_addr.clone(), lp.clone(), LP_DEPOSIT).unwrap();
+
+    let err = execute_vault_withdraw(&mut app, contract_addr.clone(), lp.clone()).unwrap_err();
+    assert_eq!(err, ContractError::NoWithdrawRequest);
+
+    let err = execute_vault_request_withdraw(
+        &mut app,
+        contract_addr.clone(),
+        lp.clone(),
+        LP_DEPOSIT + Uint128::one(),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::NotEnoughVaultShares { shares: LP_DEPOSIT }
+    );
+
+    let half = LP_DEPOSIT / Uint128::new(2);
+    execute_vault_request_withdraw(&mut app, contract_addr.clone(), lp.clone(), half).unwrap();
+    let err = execute_vault_request_withdraw(&mut app, contract_addr.clone(), lp.clone(), half)
+        .unwrap_err();
+    assert_eq!(err, ContractError::WithdrawAlreadyRequested);
+
+    // Cannot withdraw before the cooldown is over
+    let request = query_lp_position(&app, contract_addr.clone(), LP_ADDR)
+        .unwrap()
+        .withdraw_request
+        .unwrap();
+    let err = execute_vault_withdraw(&mut app, contract_addr.clone(), lp.clone()).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::WithdrawNotReady {
+            release_at: request.release_at
+        }
+    );
+
+    app.update_block(|block| {
+        block.time = block.time.plus_seconds(7 * 24 * 60 * 60);
+        block.height += 1;
+    });
+    execute_vault_withdraw(&mut app, contract_addr.clone(), lp.clone()).unwrap();
+
+    let balance = app.wrap().query_balance(LP_ADDR, NATIVE_DENOM).unwrap();
+    assert_eq!(balance.amount, half);
+    let position = query_lp_position(&app, contract_addr.clone(), LP_ADDR).unwrap();
+    assert_eq!(position.shares, half);
+    assert_eq!(position.withdraw_request, None);
+    let vault = query_vault(&app, contract_addr).unwrap();
+    assert_eq!(vault.total_shares, half);
+    assert_eq!(vault.assets, half);
+}
+
+#[test]
+fn test_vault_stale_withdraw_request() {
+    let (mut app, contract_addr) = setup_base_contract();
+    add_balance(&mut app, contract_addr.clone(), 30000000000);
+    add_balance(&mut app, Addr::unchecked(LP_ADDR), LP_DEPOSIT.u128());
+    let lp = Addr::unchecked(LP_ADDR);
+    let cooldown = 7 * 24 * 60 * 60;
+
+    execute_vault_deposit(&mut app, contract_addr.clone(), lp.clone(), LP_DEPOSIT).unwrap();
+    execute_vault_request_withdraw(&mut app, contract_addr.clone(), lp.clone(), LP_DEPOSIT)
+        .unwrap();
+    let request = query_lp_position(&app, contract_addr.clone(), LP_ADDR)
+        .unwrap()
+        .withdraw_request
+        .unwrap();
+
+    // Waiting out the cooldown once doesn't allow to leave at any later time
+    app.update_block(|block| {
+        block.time = block.time.plus_seconds(2 * cooldown);
+        block.height += 1;
+    });
+    let err = execute_vault_withdraw(&mut app, contract_addr.clone(), lp.clone()).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::WithdrawRequestExpired {
+            expires_at: request.expires_at
+        }
+    );
+    let err =
+        execute_vault_request_withdraw(&mut app, contract_addr.clone(), lp.clone(), LP_DEPOSIT)
+            .unwrap_err();
+    assert_eq!(err, ContractError::WithdrawAlreadyRequested);
+
+    // The stale request is cancelled and the cooldown starts over
+    execute_vault_cancel_withdraw(&mut app, contract_addr.clone(), lp.clone()).unwrap();
+    let err =
+        execute_vault_cancel_withdraw(&mut app, contract_addr.clone(), lp.clone()).unwrap_err();
+    assert_eq!(err, ContractError::NoWithdrawRequest);
+    execute_vault_request_withdraw(&mut app, contract_addr.clone(), lp.clone(), LP_DEPOSIT)
+        .unwrap();
+    let err = execute_vault_withdraw(&mut app, contract_addr.clone(), lp.clone()).unwrap_err();
+    assert!(matches!(err, ContractError::WithdrawNotReady { .. }));
+
+    app.update_block(|block| {
+        block.time = block.time.plus_seconds(cooldown);
+        block.height += 1;
+    });
+    execute_vault_withdraw(&mut app, contract_addr, lp).unwrap();
+    let balance = app.wrap().query_balance(LP_ADDR, NATIVE_DENOM).unwrap();
+    assert_eq!(balance.amount, LP_DEPOSIT);
+}
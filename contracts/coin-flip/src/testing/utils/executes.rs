@@ -1,12 +1,17 @@
-use cosmwasm_std::{coins, Addr, Uint128};
+use cosmwasm_std::{coins, Addr, Timestamp, Uint128};
 use cw_multi_test::{AppResponse, Executor};
+use cw_utils::Expiration;
 
 use crate::{
     error::ContractError,
-    msg::{ExecuteMsg, FlipExecuteMsg, SudoMsg},
-    types::{Fees, PickTypes},
+    msg::{
+        ExecuteMsg, FlipExecuteMsg, IntegratorExecuteMsg, MultisigExecuteMsg, ReferralExecuteMsg,
+        SudoMsg, VaultExecuteMsg,
+    },
+    types::{ConfigChange, DistributionSchedule, Fees, IntegratorFee, PickTypes, Role, Treasury},
 };
 
+use super::queries::query_config;
 use super::setup::{next_block, BaseApp, CREATOR_ADDR, FLIPPER_ADDR, NATIVE_DENOM};
 
 pub(crate) fn unwrap_execute(
@@ -33,6 +38,28 @@ pub fn execute_start_flip(
         &ExecuteMsg::Flip(FlipExecuteMsg::StartFlip {
             pick,
             amount: flip_amount,
+            integrator: None,
+        }),
+        &funds,
+    ))
+}
+
+pub fn execute_start_flip_with_integrator(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    flip_amount: Uint128,
+    flipper: Addr,
+    funds: Uint128,
+    integrator: IntegratorFee,
+) -> Result<AppResponse, ContractError> {
+    let funds = coins(funds.u128(), NATIVE_DENOM);
+    unwrap_execute(app.execute_contract(
+        flipper,
+        contract_addr,
+        &ExecuteMsg::Flip(FlipExecuteMsg::StartFlip {
+            pick: PickTypes::Heads,
+            amount: flip_amount,
+            integrator: Some(integrator),
         }),
         &funds,
     ))
@@ -51,33 +78,79 @@ pub fn execute_do_flips(
     ))
 }
 
-pub fn sudo_update_fees(
+pub fn sudo_propose_config_change(
     app: &mut BaseApp,
     contract_addr: Addr,
-    fees: Fees,
+    change: ConfigChange,
+    effective_at: Timestamp,
 ) -> Result<AppResponse, ContractError> {
     unwrap_execute(app.execute_contract(
         Addr::unchecked(CREATOR_ADDR),
         contract_addr,
-        &ExecuteMsg::Sudo(SudoMsg::UpdateFees { fees }),
+        &ExecuteMsg::Sudo(SudoMsg::ProposeConfigChange {
+            change,
+            effective_at,
+        }),
         &[],
     ))
 }
 
-pub fn sudo_update_bet_limit(
+pub fn execute_pending(
     app: &mut BaseApp,
     contract_addr: Addr,
-    min_bet: Uint128,
-    max_bet: Uint128,
+    id: u64,
 ) -> Result<AppResponse, ContractError> {
     unwrap_execute(app.execute_contract(
-        Addr::unchecked(CREATOR_ADDR),
+        Addr::unchecked(FLIPPER_ADDR),
         contract_addr,
-        &ExecuteMsg::Sudo(SudoMsg::UpdateBetLimit { min_bet, max_bet }),
+        &ExecuteMsg::ExecutePending { id },
         &[],
     ))
 }
 
+/// Propose a change effective now and apply it, expects no timelock delay
+pub fn apply_config_change(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    change: ConfigChange,
+) -> Result<AppResponse, ContractError> {
+    let delay = query_config(app, contract_addr.clone())?.timelock_delay;
+    let effective_at = app.block_info().time.plus_seconds(delay);
+    let res = sudo_propose_config_change(app, contract_addr.clone(), change, effective_at)?;
+    let id = res
+        .events
+        .iter()
+        .flat_map(|event| event.attributes.iter())
+        .find(|attr| attr.key == "change_id")
+        .unwrap()
+        .value
+        .parse()
+        .unwrap();
+    app.update_block(|block| block.time = block.time.plus_seconds(delay));
+    execute_pending(app, contract_addr, id)
+}
+
+pub fn sudo_update_fees(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    fees: Fees,
+) -> Result<AppResponse, ContractError> {
+    apply_config_change(app, contract_addr, ConfigChange::UpdateFees { fees })
+}
+
+pub fn sudo_update_bet_limit(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    min_bet: Uint128,
+    max_bet: Uint128,
+) -> Result<AppResponse, ContractError> {
+    apply_config_change(
+        app,
+        contract_addr,
+        ConfigChange::UpdateBetLimit { min_bet, max_bet },
+    )
+}
+
 pub fn sudo_update_pause(
     app: &mut BaseApp,
     contract_addr: Addr,
@@ -95,36 +168,373 @@ pub fn sudo_update_sg721(
     app: &mut BaseApp,
     contract_addr: Addr,
     addr: String,
+) -> Result<AppResponse, ContractError> {
+    apply_config_change(app, contract_addr, ConfigChange::UpdateSg721 { addr })
+}
+
+pub fn sudo_update_staking(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    addr: Option<String>,
+) -> Result<AppResponse, ContractError> {
+    apply_config_change(app, contract_addr, ConfigChange::UpdateStaking { addr })
+}
+
+pub fn sudo_update_bank_limit(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    limit: Uint128,
+) -> Result<AppResponse, ContractError> {
+    apply_config_change(app, contract_addr, ConfigChange::UpdateBankLimit { limit })
+}
+
+pub fn sudo_distribute(
+    app: &mut BaseApp,
+    contract_addr: Addr,
 ) -> Result<AppResponse, ContractError> {
     unwrap_execute(app.execute_contract(
         Addr::unchecked(CREATOR_ADDR),
         contract_addr,
-        &ExecuteMsg::Sudo(SudoMsg::UpdateSg721 { addr }),
+        &ExecuteMsg::Sudo(SudoMsg::Distribute {}),
         &[],
     ))
 }
 
-pub fn sudo_update_bank_limit(
+pub fn sudo_start_distribution(
     app: &mut BaseApp,
     contract_addr: Addr,
-    limit: Uint128,
 ) -> Result<AppResponse, ContractError> {
     unwrap_execute(app.execute_contract(
         Addr::unchecked(CREATOR_ADDR),
         contract_addr,
-        &ExecuteMsg::Sudo(SudoMsg::UpdateBankLimit { limit }),
+        &ExecuteMsg::Sudo(SudoMsg::StartDistribution {}),
         &[],
     ))
 }
 
-pub fn sudo_distribute(
+pub fn sudo_continue_distribution(
     app: &mut BaseApp,
     contract_addr: Addr,
+    limit: Option<u32>,
 ) -> Result<AppResponse, ContractError> {
     unwrap_execute(app.execute_contract(
         Addr::unchecked(CREATOR_ADDR),
         contract_addr,
-        &ExecuteMsg::Sudo(SudoMsg::Distribute {}),
+        &ExecuteMsg::Sudo(SudoMsg::ContinueDistribution { limit }),
+        &[],
+    ))
+}
+
+pub fn sudo_update_excluded_holders(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    add: Vec<String>,
+    remove: Vec<String>,
+) -> Result<AppResponse, ContractError> {
+    unwrap_execute(app.execute_contract(
+        Addr::unchecked(CREATOR_ADDR),
+        contract_addr,
+        &ExecuteMsg::Sudo(SudoMsg::UpdateExcludedHolders { add, remove }),
+        &[],
+    ))
+}
+
+pub fn sudo_update_min_holder_payout(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    amount: Uint128,
+) -> Result<AppResponse, ContractError> {
+    apply_config_change(
+        app,
+        contract_addr,
+        ConfigChange::UpdateMinHolderPayout { amount },
+    )
+}
+
+pub fn execute_register_referrer(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    sender: Addr,
+    referrer: &str,
+) -> Result<AppResponse, ContractError> {
+    unwrap_execute(app.execute_contract(
+        sender,
+        contract_addr,
+        &ExecuteMsg::Referral(ReferralExecuteMsg::RegisterReferrer {
+            referrer: referrer.to_string(),
+        }),
+        &[],
+    ))
+}
+
+pub fn execute_claim_referral_rewards(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    sender: Addr,
+) -> Result<AppResponse, ContractError> {
+    unwrap_execute(app.execute_contract(
+        sender,
+        contract_addr,
+        &ExecuteMsg::Referral(ReferralExecuteMsg::ClaimReferralRewards {}),
+        &[],
+    ))
+}
+
+pub fn execute_claim_integrator_fees(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    sender: Addr,
+) -> Result<AppResponse, ContractError> {
+    unwrap_execute(app.execute_contract(
+        sender,
+        contract_addr,
+        &ExecuteMsg::Integrator(IntegratorExecuteMsg::ClaimIntegratorFees {}),
+        &[],
+    ))
+}
+
+pub fn sudo_update_max_integrator_fee(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    max_bps: u64,
+) -> Result<AppResponse, ContractError> {
+    apply_config_change(
+        app,
+        contract_addr,
+        ConfigChange::UpdateMaxIntegratorFee { max_bps },
+    )
+}
+
+pub fn execute_distribute(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    sender: Addr,
+) -> Result<AppResponse, ContractError> {
+    unwrap_execute(app.execute_contract(sender, contract_addr, &ExecuteMsg::Distribute {}, &[]))
+}
+
+pub fn sudo_update_distribution_schedule(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    schedule: Option<DistributionSchedule>,
+) -> Result<AppResponse, ContractError> {
+    apply_config_change(
+        app,
+        contract_addr,
+        ConfigChange::UpdateDistributionSchedule { schedule },
+    )
+}
+
+pub fn execute_vault_deposit(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    sender: Addr,
+    amount: Uint128,
+) -> Result<AppResponse, ContractError> {
+    unwrap_execute(app.execute_contract(
+        sender,
+        contract_addr,
+        &ExecuteMsg::Vault(VaultExecuteMsg::Deposit {}),
+        &coins(amount.u128(), NATIVE_DENOM),
+    ))
+}
+
+pub fn execute_vault_request_withdraw(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    sender: Addr,
+    shares: Uint128,
+) -> Result<AppResponse, ContractError> {
+    unwrap_execute(app.execute_contract(
+        sender,
+        contract_addr,
+        &ExecuteMsg::Vault(VaultExecuteMsg::RequestWithdraw { shares }),
+        &[],
+    ))
+}
+
+pub fn execute_vault_withdraw(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    sender: Addr,
+) -> Result<AppResponse, ContractError> {
+    unwrap_execute(app.execute_contract(
+        sender,
+        contract_addr,
+        &ExecuteMsg::Vault(VaultExecuteMsg::Withdraw {}),
+        &[],
+    ))
+}
+
+pub fn execute_vault_cancel_withdraw(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    sender: Addr,
+) -> Result<AppResponse, ContractError> {
+    unwrap_execute(app.execute_contract(
+        sender,
+        contract_addr,
+        &ExecuteMsg::Vault(VaultExecuteMsg::CancelWithdraw {}),
+        &[],
+    ))
+}
+
+pub fn sudo_update_max_bet_bank_bps(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    bps: Option<u64>,
+) -> Result<AppResponse, ContractError> {
+    apply_config_change(
+        app,
+        contract_addr,
+        ConfigChange::UpdateMaxBetBankBps { bps },
+    )
+}
+
+pub fn execute_refill_bank(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    sender: Addr,
+) -> Result<AppResponse, ContractError> {
+    unwrap_execute(app.execute_contract(sender, contract_addr, &ExecuteMsg::RefillBank {}, &[]))
+}
+
+pub fn sudo_update_treasury(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    treasury: Option<Treasury>,
+) -> Result<AppResponse, ContractError> {
+    apply_config_change(
+        app,
+        contract_addr,
+        ConfigChange::UpdateTreasury { treasury },
+    )
+}
+
+pub fn sudo_fund_bank(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    sender: Addr,
+    amount: Uint128,
+) -> Result<AppResponse, ContractError> {
+    unwrap_execute(app.execute_contract(
+        sender,
+        contract_addr,
+        &ExecuteMsg::Sudo(SudoMsg::FundBank {}),
+        &coins(amount.u128(), NATIVE_DENOM),
+    ))
+}
+
+pub fn sudo_withdraw_bank(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    amount: Uint128,
+    denom: &str,
+) -> Result<AppResponse, ContractError> {
+    unwrap_execute(app.execute_contract(
+        Addr::unchecked(CREATOR_ADDR),
+        contract_addr,
+        &ExecuteMsg::Sudo(SudoMsg::WithdrawBank {
+            amount,
+            denom: denom.to_string(),
+        }),
+        &[],
+    ))
+}
+
+pub fn sudo_propose_admin(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    sender: Addr,
+    admin: &str,
+    expires: Option<Expiration>,
+) -> Result<AppResponse, ContractError> {
+    unwrap_execute(app.execute_contract(
+        sender,
+        contract_addr,
+        &ExecuteMsg::Sudo(SudoMsg::ProposeAdmin {
+            admin: admin.to_string(),
+            expires,
+        }),
+        &[],
+    ))
+}
+
+pub fn execute_accept_admin(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    sender: Addr,
+) -> Result<AppResponse, ContractError> {
+    unwrap_execute(app.execute_contract(sender, contract_addr, &ExecuteMsg::AcceptAdmin {}, &[]))
+}
+
+pub fn sudo_renounce_admin(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    sender: Addr,
+) -> Result<AppResponse, ContractError> {
+    unwrap_execute(app.execute_contract(
+        sender,
+        contract_addr,
+        &ExecuteMsg::Sudo(SudoMsg::RenounceAdmin {}),
+        &[],
+    ))
+}
+
+/// Call a sudo msg from any sender, to check who is allowed to
+pub fn execute_sudo(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    sender: Addr,
+    msg: SudoMsg,
+) -> Result<AppResponse, ContractError> {
+    unwrap_execute(app.execute_contract(sender, contract_addr, &ExecuteMsg::Sudo(msg), &[]))
+}
+
+pub fn sudo_grant_role(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    role: Role,
+    address: &str,
+) -> Result<AppResponse, ContractError> {
+    unwrap_execute(app.execute_contract(
+        Addr::unchecked(CREATOR_ADDR),
+        contract_addr,
+        &ExecuteMsg::Sudo(SudoMsg::GrantRole {
+            role,
+            address: address.to_string(),
+        }),
+        &[],
+    ))
+}
+
+pub fn sudo_revoke_role(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    role: Role,
+    address: &str,
+) -> Result<AppResponse, ContractError> {
+    unwrap_execute(app.execute_contract(
+        Addr::unchecked(CREATOR_ADDR),
+        contract_addr,
+        &ExecuteMsg::Sudo(SudoMsg::RevokeRole {
+            role,
+            address: address.to_string(),
+        }),
+        &[],
+    ))
+}
+
+pub fn execute_multisig(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    sender: &str,
+    msg: MultisigExecuteMsg,
+) -> Result<AppResponse, ContractError> {
+    unwrap_execute(app.execute_contract(
+        Addr::unchecked(sender),
+        contract_addr,
+        &ExecuteMsg::Multisig(msg),
         &[],
     ))
 }
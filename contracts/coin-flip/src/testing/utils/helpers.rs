@@ -1,6 +1,6 @@
 use std::str::FromStr;
 
-use cosmwasm_std::{coins, Addr, Decimal, Empty, Uint128};
+use cosmwasm_std::{coins, Addr, Binary, Decimal, Empty, Uint128};
 use cosmwasm_storage::PrefixedStorage;
 use cw_multi_test::{AppResponse, Executor};
 
@@ -90,12 +90,30 @@ pub fn mint_777_nfts(app: &mut BaseApp, nft_contract_addr: Addr, sender: Addr) {
     }
 }
 
+pub fn stake_nft(app: &mut BaseApp, nft_contract_addr: Addr, staking_addr: Addr, token_id: u32) {
+    unwrap_execute(app.execute_contract(
+        Addr::unchecked(format!("{FLIPPER_PREFIX}{token_id}")),
+        nft_contract_addr,
+        &sg721::ExecuteMsg::SendNft::<Empty, Empty> {
+            contract: staking_addr.to_string(),
+            token_id: token_id.to_string(),
+            msg: Binary::default(),
+        },
+        &[],
+    ))
+    .unwrap();
+}
+
 #[derive(Debug)]
 pub struct DistResponse {
     pub total_fees: Uint128,
     pub reserve_paid: Uint128,
     pub team_paid: Uint128,
     pub holders_paid: Uint128,
+    pub holders_pending: Uint128,
+    pub holders_released: Uint128,
+    pub rounding_remainder: Uint128,
+    pub caller_tip: Uint128,
     pub fees_per_token: Decimal,
     pub total_shares: Uint128,
 }
@@ -106,6 +124,10 @@ pub fn get_dist_result(res: AppResponse) -> DistResponse {
     let mut reserve_paid = Uint128::zero();
     let mut team_paid = Uint128::zero();
     let mut holders_paid = Uint128::zero();
+    let mut holders_pending = Uint128::zero();
+    let mut holders_released = Uint128::zero();
+    let mut rounding_remainder = Uint128::zero();
+    let mut caller_tip = Uint128::zero();
     let mut fees_per_token = Decimal::zero();
     let mut total_shares = Uint128::zero();
 
@@ -118,6 +140,14 @@ pub fn get_dist_result(res: AppResponse) -> DistResponse {
             team_paid = Uint128::from_str(&attr.value).unwrap();
         } else if attr.key == "holders_paid" {
             holders_paid = Uint128::from_str(&attr.value).unwrap();
+        } else if attr.key == "holders_pending" {
+            holders_pending = Uint128::from_str(&attr.value).unwrap();
+        } else if attr.key == "holders_released" {
+            holders_released = Uint128::from_str(&attr.value).unwrap();
+        } else if attr.key == "rounding_remainder" {
+            rounding_remainder = Uint128::from_str(&attr.value).unwrap();
+        } else if attr.key == "caller_tip" {
+            caller_tip = Uint128::from_str(&attr.value).unwrap();
         } else if attr.key == "fees_per_token" {
             fees_per_token = Decimal::from_str(&attr.value).unwrap();
         } else if attr.key == "total_shares" {
@@ -130,6 +160,10 @@ pub fn get_dist_result(res: AppResponse) -> DistResponse {
         reserve_paid,
         team_paid,
         holders_paid,
+        holders_pending,
+        holders_released,
+        rounding_remainder,
+        caller_tip,
         fees_per_token,
         total_shares,
     }
@@ -0,0 +1,259 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    coin, to_binary, Addr, BankMsg, Binary, Deps, DepsMut, Empty, Env, MessageInfo, Order,
+    StdResult, Uint128, WasmMsg,
+};
+use cw2::set_contract_version;
+use cw721::Cw721ReceiveMsg;
+use cw_multi_test::{Contract, ContractWrapper};
+use cw_storage_plus::{Item, Map};
+use sg_std::{Response, StargazeMsgWrapper};
+
+use crate::contract::{MAX_BET, MIN_BANK_AMOUNT, MIN_BET};
+use crate::migrations::{ConfigV0_7, FeesV0_7, WalletsV0_7, CONFIG_V0_7};
+use crate::msg::{StakeChangedHookMsg, StakingQueryMsg, TreasuryExecuteMsg};
+use crate::state::{FEES, FLIPS, TODO_FLIPS};
+use crate::types::{PickTypes, TodoFlip};
+
+use super::setup::{CREATOR_ADDR, FLIPPER_ADDR, NATIVE_DENOM, RESERVE_ADDR, TEAM_ADDR};
+
+/// Staked token ids per staker
+const STAKED: Map<&str, Vec<String>> = Map::new("staked");
+/// Contracts called on stake changes
+const HOOKS: Map<&Addr, Empty> = Map::new("hooks");
+/// NFT contract of the staked tokens
+const NFT_ADDR: Item<Addr> = Item::new("nft_addr");
+
+/// Subset of the cw721-staked execute msgs
+#[cw_serde]
+pub enum MockStakingExecuteMsg {
+    ReceiveNft(Cw721ReceiveMsg),
+    Unstake { token_ids: Vec<String> },
+    AddHook { addr: String },
+}
+
+/// How cw721-staked wraps the hook msgs
+#[cw_serde]
+enum StakeChangedExecuteMsg {
+    StakeChangeHook(StakeChangedHookMsg),
+}
+
+fn staking_instantiate(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _msg: Empty,
+) -> StdResult<Response> {
+    Ok(Response::default())
+}
+
+fn hook_msgs(deps: &DepsMut, msg: StakeChangedHookMsg) -> StdResult<Vec<WasmMsg>> {
+    HOOKS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .map(|hook| {
+            Ok(WasmMsg::Execute {
+                contract_addr: hook?.to_string(),
+                msg: to_binary(&StakeChangedExecuteMsg::StakeChangeHook(msg.clone()))?,
+                funds: vec![],
+            })
+        })
+        .collect()
+}
+
+fn staking_execute(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: MockStakingExecuteMsg,
+) -> StdResult<Response> {
+    match msg {
+        MockStakingExecuteMsg::ReceiveNft(Cw721ReceiveMsg {
+            sender, token_id, ..
+        }) => {
+            NFT_ADDR.save(deps.storage, &info.sender)?;
+            STAKED.update(deps.storage, &sender, |tokens| -> StdResult<_> {
+                let mut tokens = tokens.unwrap_or_default();
+                tokens.push(token_id.clone());
+                Ok(tokens)
+            })?;
+            let hooks = hook_msgs(
+                &deps,
+                StakeChangedHookMsg::Stake {
+                    addr: Addr::unchecked(sender),
+                    token_id,
+                },
+            )?;
+            Ok(Response::default().add_messages(hooks))
+        }
+        MockStakingExecuteMsg::Unstake { token_ids } => {
+            let sender = info.sender.to_string();
+            let mut tokens = STAKED.may_load(deps.storage, &sender)?.unwrap_or_default();
+            tokens.retain(|token_id| !token_ids.contains(token_id));
+            STAKED.save(deps.storage, &sender, &tokens)?;
+
+            let nft_addr = NFT_ADDR.load(deps.storage)?;
+            let mut msgs = token_ids
+                .iter()
+                .map(|token_id| {
+                    Ok(WasmMsg::Execute {
+                        contract_addr: nft_addr.to_string(),
+                        msg: to_binary(&sg721::ExecuteMsg::TransferNft::<Empty, Empty> {
+                            recipient: sender.clone(),
+                            token_id: token_id.clone(),
+                        })?,
+                        funds: vec![],
+                    })
+                })
+                .collect::<StdResult<Vec<_>>>()?;
+            msgs.extend(hook_msgs(
+                &deps,
+                StakeChangedHookMsg::Unstake {
+                    addr: info.sender,
+                    token_ids,
+                },
+            )?);
+            Ok(Response::default().add_messages(msgs))
+        }
+        MockStakingExecuteMsg::AddHook { addr } => {
+            HOOKS.save(deps.storage, &Addr::unchecked(addr), &Empty {})?;
+            Ok(Response::default())
+        }
+    }
+}
+
+fn staking_query(deps: Deps, _env: Env, msg: StakingQueryMsg) -> StdResult<Binary> {
+    match msg {
+        StakingQueryMsg::StakedNfts {
+            address,
+            start_after,
+            limit,
+        } => {
+            let limit = limit.unwrap_or(30) as usize;
+            let tokens = STAKED.may_load(deps.storage, &address)?.unwrap_or_default();
+            let tokens: Vec<String> = tokens
+                .into_iter()
+                .skip_while(|token_id| match &start_after {
+                    Some(start_after) => token_id != start_after,
+                    None => false,
+                })
+                .skip(usize::from(start_after.is_some()))
+                .take(limit)
+                .collect();
+            to_binary(&tokens)
+        }
+    }
+}
+
+/// Staking contract that behaves like DAO DAO cw721-staked for the msgs we use
+pub fn staking_contract() -> Box<dyn Contract<StargazeMsgWrapper>> {
+    let contract = ContractWrapper::new(staking_execute, staking_instantiate, staking_query);
+    Box::new(contract)
+}
+
+fn treasury_instantiate(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _msg: Empty,
+) -> StdResult<Response> {
+    Ok(Response::default())
+}
+
+fn treasury_execute(
+    _deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: TreasuryExecuteMsg,
+) -> StdResult<Response> {
+    match msg {
+        TreasuryExecuteMsg::Refill { amount } => {
+            Ok(Response::default().add_message(BankMsg::Send {
+                to_address: info.sender.to_string(),
+                amount: vec![amount],
+            }))
+        }
+    }
+}
+
+fn treasury_query(_deps: Deps, _env: Env, _msg: Empty) -> StdResult<Binary> {
+    to_binary(&Empty {})
+}
+
+pub fn treasury_contract() -> Box<dyn Contract<StargazeMsgWrapper>> {
+    let contract = ContractWrapper::new(treasury_execute, treasury_instantiate, treasury_query);
+    Box::new(contract)
+}
+
+/// Which cw2 contract and version the old flip contract stores
+#[cw_serde]
+pub struct MockOldFlipInstantiateMsg {
+    pub contract: String,
+    pub version: String,
+    /// Fees to store, the mainnet ones if not set
+    pub fees: Option<FeesV0_7>,
+}
+
+/// Stores the v0.7 state layout, with 1 pending flip
+fn old_flip_instantiate(
+    deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+    msg: MockOldFlipInstantiateMsg,
+) -> StdResult<Response> {
+    set_contract_version(deps.storage, msg.contract, msg.version)?;
+    CONFIG_V0_7.save(
+        deps.storage,
+        &ConfigV0_7 {
+            admin: CREATOR_ADDR.to_string(),
+            denoms: vec![NATIVE_DENOM.to_string()],
+            bank_limit: MIN_BANK_AMOUNT,
+            min_bet_limit: MIN_BET,
+            max_bet_limit: MAX_BET,
+            flips_per_block_limit: 10,
+            wallets: WalletsV0_7 {
+                team: TEAM_ADDR.to_string(),
+                reserve: RESERVE_ADDR.to_string(),
+            },
+            fees: msg.fees.unwrap_or(FeesV0_7 {
+                team_bps: 1500,
+                holders_bps: 7000,
+                reserve_bps: 1500,
+                flip_bps: 350,
+            }),
+            sg721_addr: None,
+            is_paused: false,
+        },
+    )?;
+    FEES.save(deps.storage, &Uint128::zero())?;
+    FLIPS.save(deps.storage, &vec![])?;
+    TODO_FLIPS.save(
+        deps.storage,
+        &vec![TodoFlip {
+            id: 0,
+            wallet: Addr::unchecked(FLIPPER_ADDR),
+            amount: coin(MIN_BET.u128(), NATIVE_DENOM),
+            pick: PickTypes::Heads,
+            block: env.block.height,
+            timestamp: env.block.time,
+        }],
+    )?;
+    Ok(Response::default())
+}
+
+fn old_flip_execute(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _msg: Empty,
+) -> StdResult<Response> {
+    Ok(Response::default())
+}
+
+fn old_flip_query(_deps: Deps, _env: Env, _msg: Empty) -> StdResult<Binary> {
+    to_binary(&Empty {})
+}
+
+pub fn old_flip_contract() -> Box<dyn Contract<StargazeMsgWrapper>> {
+    let contract = ContractWrapper::new(old_flip_execute, old_flip_instantiate, old_flip_query);
+    Box::new(contract)
+}
@@ -3,4 +3,5 @@ pub mod setup;
 
 pub mod executes;
 pub mod helpers;
+pub mod mocks;
 pub mod queries;
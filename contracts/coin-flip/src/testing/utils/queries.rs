@@ -1,8 +1,16 @@
 use cosmwasm_std::{Addr, StdError, Uint128};
 
 use crate::{
-    msg::{DryDistributionResponse, QueryMsg},
-    types::{Config, Flip, FlipScore},
+    msg::{
+        DryDistributionResponse, EffectiveLimitsResponse, FlipQuoteResponse,
+        IntegratorStatsResponse, LpPositionResponse, NextDistributionResponse, OwnershipResponse,
+        QueryMsg, ReferralStatsResponse, RefillStatusResponse, SolvencyResponse, VaultResponse,
+        VoteResponse,
+    },
+    types::{
+        Config, DistributionRecord, DistributionRun, Flip, FlipScore, HolderPayout, HouseLedger,
+        PendingChange, Proposal, Role,
+    },
 };
 
 use super::setup::BaseApp;
@@ -38,3 +46,279 @@ pub fn query_dry_distribution(
     app.wrap()
         .query_wasm_smart(contract_addr, &QueryMsg::DryDistribution {})
 }
+
+pub fn query_distribution_status(
+    app: &BaseApp,
+    contract_addr: Addr,
+) -> Result<Option<DistributionRun>, StdError> {
+    app.wrap()
+        .query_wasm_smart(contract_addr, &QueryMsg::GetDistributionStatus {})
+}
+
+pub fn query_distributions(
+    app: &BaseApp,
+    contract_addr: Addr,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> Result<Vec<DistributionRecord>, StdError> {
+    app.wrap().query_wasm_smart(
+        contract_addr,
+        &QueryMsg::GetDistributions { start_after, limit },
+    )
+}
+
+pub fn query_holder_earnings(
+    app: &BaseApp,
+    contract_addr: Addr,
+    address: String,
+) -> Result<Uint128, StdError> {
+    app.wrap()
+        .query_wasm_smart(contract_addr, &QueryMsg::GetHolderEarnings { address })
+}
+
+pub fn query_dry_distribution_holders(
+    app: &BaseApp,
+    contract_addr: Addr,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> Result<Vec<HolderPayout>, StdError> {
+    app.wrap().query_wasm_smart(
+        contract_addr,
+        &QueryMsg::DryDistributionHolders { start_after, limit },
+    )
+}
+
+pub fn query_excluded_holders(app: &BaseApp, contract_addr: Addr) -> Result<Vec<Addr>, StdError> {
+    app.wrap().query_wasm_smart(
+        contract_addr,
+        &QueryMsg::GetExcludedHolders {
+            start_after: None,
+            limit: None,
+        },
+    )
+}
+
+pub fn query_flip_quote(
+    app: &BaseApp,
+    contract_addr: Addr,
+    amount: Uint128,
+    address: Option<String>,
+    integrator_bps: Option<u64>,
+) -> Result<FlipQuoteResponse, StdError> {
+    app.wrap().query_wasm_smart(
+        contract_addr,
+        &QueryMsg::GetFlipQuote {
+            amount,
+            address,
+            integrator_bps,
+        },
+    )
+}
+
+pub fn query_integrator_stats(
+    app: &BaseApp,
+    contract_addr: Addr,
+    address: &str,
+) -> Result<IntegratorStatsResponse, StdError> {
+    app.wrap().query_wasm_smart(
+        contract_addr,
+        &QueryMsg::GetIntegratorStats {
+            address: address.to_string(),
+        },
+    )
+}
+
+pub fn query_integrators(
+    app: &BaseApp,
+    contract_addr: Addr,
+) -> Result<Vec<IntegratorStatsResponse>, StdError> {
+    app.wrap().query_wasm_smart(
+        contract_addr,
+        &QueryMsg::ListIntegrators {
+            start_after: None,
+            limit: None,
+        },
+    )
+}
+
+pub fn query_referrer(
+    app: &BaseApp,
+    contract_addr: Addr,
+    address: &str,
+) -> Result<Option<Addr>, StdError> {
+    app.wrap().query_wasm_smart(
+        contract_addr,
+        &QueryMsg::GetReferrer {
+            address: address.to_string(),
+        },
+    )
+}
+
+pub fn query_referral_stats(
+    app: &BaseApp,
+    contract_addr: Addr,
+    address: &str,
+) -> Result<ReferralStatsResponse, StdError> {
+    app.wrap().query_wasm_smart(
+        contract_addr,
+        &QueryMsg::GetReferralStats {
+            address: address.to_string(),
+        },
+    )
+}
+
+pub fn query_referred_wallets(
+    app: &BaseApp,
+    contract_addr: Addr,
+    referrer: &str,
+) -> Result<Vec<Addr>, StdError> {
+    app.wrap().query_wasm_smart(
+        contract_addr,
+        &QueryMsg::GetReferredWallets {
+            referrer: referrer.to_string(),
+            start_after: None,
+            limit: None,
+        },
+    )
+}
+
+pub fn query_next_distribution(
+    app: &BaseApp,
+    contract_addr: Addr,
+) -> Result<NextDistributionResponse, StdError> {
+    app.wrap()
+        .query_wasm_smart(contract_addr, &QueryMsg::NextDistribution {})
+}
+
+pub fn query_vault(app: &BaseApp, contract_addr: Addr) -> Result<VaultResponse, StdError> {
+    app.wrap()
+        .query_wasm_smart(contract_addr, &QueryMsg::GetVault {})
+}
+
+pub fn query_lp_position(
+    app: &BaseApp,
+    contract_addr: Addr,
+    address: &str,
+) -> Result<LpPositionResponse, StdError> {
+    app.wrap().query_wasm_smart(
+        contract_addr,
+        &QueryMsg::GetLpPosition {
+            address: address.to_string(),
+        },
+    )
+}
+
+pub fn query_effective_limits(
+    app: &BaseApp,
+    contract_addr: Addr,
+    denom: &str,
+) -> Result<EffectiveLimitsResponse, StdError> {
+    app.wrap().query_wasm_smart(
+        contract_addr,
+        &QueryMsg::GetEffectiveLimits {
+            denom: denom.to_string(),
+        },
+    )
+}
+
+pub fn query_solvency(
+    app: &BaseApp,
+    contract_addr: Addr,
+    denom: &str,
+) -> Result<SolvencyResponse, StdError> {
+    app.wrap().query_wasm_smart(
+        contract_addr,
+        &QueryMsg::GetSolvency {
+            denom: denom.to_string(),
+        },
+    )
+}
+
+pub fn query_refill_status(
+    app: &BaseApp,
+    contract_addr: Addr,
+) -> Result<RefillStatusResponse, StdError> {
+    app.wrap()
+        .query_wasm_smart(contract_addr, &QueryMsg::GetRefillStatus {})
+}
+
+pub fn query_house_ledger(
+    app: &BaseApp,
+    contract_addr: Addr,
+    denom: &str,
+) -> Result<HouseLedger, StdError> {
+    app.wrap().query_wasm_smart(
+        contract_addr,
+        &QueryMsg::GetHouseLedger {
+            denom: denom.to_string(),
+        },
+    )
+}
+
+pub fn query_ownership(app: &BaseApp, contract_addr: Addr) -> Result<OwnershipResponse, StdError> {
+    app.wrap()
+        .query_wasm_smart(contract_addr, &QueryMsg::GetOwnership {})
+}
+
+pub fn query_has_role(
+    app: &BaseApp,
+    contract_addr: Addr,
+    role: Role,
+    address: &str,
+) -> Result<bool, StdError> {
+    app.wrap().query_wasm_smart(
+        contract_addr,
+        &QueryMsg::HasRole {
+            role,
+            address: address.to_string(),
+        },
+    )
+}
+
+pub fn query_role_members(
+    app: &BaseApp,
+    contract_addr: Addr,
+    role: Role,
+) -> Result<Vec<Addr>, StdError> {
+    app.wrap().query_wasm_smart(
+        contract_addr,
+        &QueryMsg::GetRoleMembers {
+            role,
+            start_after: None,
+            limit: None,
+        },
+    )
+}
+
+pub fn query_pending_changes(
+    app: &BaseApp,
+    contract_addr: Addr,
+) -> Result<Vec<PendingChange>, StdError> {
+    app.wrap().query_wasm_smart(
+        contract_addr,
+        &QueryMsg::GetPendingChanges {
+            start_after: None,
+            limit: None,
+        },
+    )
+}
+
+pub fn query_proposal(app: &BaseApp, contract_addr: Addr, id: u64) -> Result<Proposal, StdError> {
+    app.wrap()
+        .query_wasm_smart(contract_addr, &QueryMsg::GetProposal { id })
+}
+
+pub fn query_votes(
+    app: &BaseApp,
+    contract_addr: Addr,
+    id: u64,
+) -> Result<Vec<VoteResponse>, StdError> {
+    app.wrap().query_wasm_smart(
+        contract_addr,
+        &QueryMsg::ListVotes {
+            id,
+            start_after: None,
+            limit: None,
+        },
+    )
+}
@@ -11,12 +11,14 @@ use sg_std::StargazeMsgWrapper;
 
 use crate::{
     msg::InstantiateMsg,
-    types::{Fees, Wallets},
+    types::{FeeRecipient, FeeRecipientKind, Fees},
 };
 
 use super::{
-    executes::sudo_update_sg721,
+    executes::{sudo_update_sg721, sudo_update_staking},
     helpers::{add_balance, mint_777_nfts},
+    mocks::{staking_contract, MockStakingExecuteMsg},
+    queries::query_config,
 };
 
 pub type BaseApp = App<
@@ -38,6 +40,31 @@ pub const RESERVE_ADDR: &str = "reserve_wallet";
 
 pub const PLUS_NANOS: u64 = 654321;
 
+/// Team, holders and reserve recipients
+pub fn fee_recipients(team_bps: u64, holders_bps: u64, reserve_bps: u64) -> Vec<FeeRecipient> {
+    vec![
+        FeeRecipient {
+            name: "team".to_string(),
+            kind: FeeRecipientKind::Wallet {
+                address: TEAM_ADDR.to_string(),
+            },
+            bps: team_bps,
+        },
+        FeeRecipient {
+            name: "holders".to_string(),
+            kind: FeeRecipientKind::Holders,
+            bps: holders_bps,
+        },
+        FeeRecipient {
+            name: "reserve".to_string(),
+            kind: FeeRecipientKind::Reserve {
+                address: RESERVE_ADDR.to_string(),
+            },
+            bps: reserve_bps,
+        },
+    ]
+}
+
 pub fn nft_contract() -> Box<dyn Contract<StargazeMsgWrapper>> {
     let contract = ContractWrapper::new(
         sg721_base::entry::execute,
@@ -52,7 +79,10 @@ pub fn flip_contract() -> Box<dyn Contract<StargazeMsgWrapper>> {
         crate::contract::execute,
         crate::contract::instantiate,
         crate::contract::query,
-    );
+    )
+    .with_sudo(crate::contract::sudo)
+    .with_migrate(crate::contract::migrate)
+    .with_reply(crate::contract::reply);
     Box::new(contract)
 }
 
@@ -61,6 +91,36 @@ pub fn next_block(block: &mut BlockInfo) {
     block.height += 1;
 }
 
+/// Instantiate msg we use for the flip contract in tests
+pub fn base_instantiate_msg() -> InstantiateMsg {
+    InstantiateMsg {
+        admin: CREATOR_ADDR.to_string(),
+        denoms: vec![NATIVE_DENOM.to_string()],
+        fees: Fees {
+            recipients: fee_recipients(1500, 7000, 1500),
+            flip_bps: 350,
+            flip_fee_tiers: vec![],
+            holder_discount_bps: 0,
+            referral_bps: 0,
+            remainder_recipient: "team".to_string(),
+        },
+        bank_limit: None,
+        min_bet_limit: None,
+        max_bet_limit: None,
+        flips_per_block_limit: None,
+        sg721_addr: None,
+        staking_addr: None,
+        min_holder_payout: None,
+        max_integrator_bps: None,
+        distribution_schedule: None,
+        vault_cooldown: None,
+        max_bet_bank_bps: None,
+        treasury: None,
+        // Shortest delay, `apply_config_change` waits it out
+        timelock_delay: Some(1),
+    }
+}
+
 /// Basic setup for unit test on a single contract
 pub fn setup_base_contract() -> (BaseApp, Addr) {
     let mut app: BaseApp = BasicAppBuilder::<sg_std::StargazeMsgWrapper, Empty>::new_custom()
@@ -94,25 +154,7 @@ pub fn setup_base_contract() -> (BaseApp, Addr) {
 
     let code_id = app.store_code(flip_contract());
 
-    let init_msg = &InstantiateMsg {
-        admin: CREATOR_ADDR.to_string(),
-        denoms: vec![NATIVE_DENOM.to_string()],
-        wallets: Wallets {
-            team: TEAM_ADDR.to_string(),
-            reserve: RESERVE_ADDR.to_string(),
-        },
-        fees: Fees {
-            team_bps: 1500,
-            holders_bps: 7000,
-            reserve_bps: 1500,
-            flip_bps: 350,
-        },
-        bank_limit: None,
-        min_bet_limit: None,
-        max_bet_limit: None,
-        flips_per_block_limit: None,
-        sg721_addr: None,
-    };
+    let init_msg = &base_instantiate_msg();
 
     let contract_addr = app
         .instantiate_contract(
@@ -164,3 +206,42 @@ pub fn setup_contract() -> (BaseApp, Addr) {
 
     (app, contract_addr)
 }
+
+/// Setup contract with NFTs, and a staking contract holders can stake their NFTs in.
+/// The flip contract is added to the staking contract hooks.
+/// Returns the staking contract and the NFT contract addresses as well.
+pub fn setup_staking_contract() -> (BaseApp, Addr, Addr, Addr) {
+    let (mut app, contract_addr) = setup_contract();
+    let config = query_config(&app, contract_addr.clone()).unwrap();
+    let nft_addr = config.sg721_addr.unwrap();
+
+    let staking_code_id = app.store_code(staking_contract());
+    let staking_addr = app
+        .instantiate_contract(
+            staking_code_id,
+            Addr::unchecked(CREATOR_ADDR),
+            &Empty {},
+            &[],
+            "staking contract",
+            None,
+        )
+        .unwrap();
+
+    sudo_update_staking(
+        &mut app,
+        contract_addr.clone(),
+        Some(staking_addr.to_string()),
+    )
+    .unwrap();
+    app.execute_contract(
+        Addr::unchecked(CREATOR_ADDR),
+        staking_addr.clone(),
+        &MockStakingExecuteMsg::AddHook {
+            addr: contract_addr.to_string(),
+        },
+        &[],
+    )
+    .unwrap();
+
+    (app, contract_addr, staking_addr, nft_addr)
+}
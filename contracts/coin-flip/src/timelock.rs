@@ -0,0 +1,101 @@
+use cosmwasm_std::{ensure, DepsMut, Env, MessageInfo, Timestamp};
+use sg_std::Response;
+
+use crate::error::ContractError;
+use crate::helpers::{ensure_role, has_role};
+use crate::state::{PENDING_CHANGES, PENDING_CHANGE_ID};
+use crate::sudo;
+use crate::types::{Config, ConfigChange, PendingChange};
+
+/// Queue a config change, `effective_at` must be at least the timelock delay from now
+pub fn propose_config_change(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    config: &Config,
+    change: ConfigChange,
+    effective_at: Timestamp,
+) -> Result<Response, ContractError> {
+    ensure_role(deps.as_ref(), config, &info, change.role())?;
+    // Catch invalid changes when proposed, instead of when the delay is over
+    sudo::apply_config_update(deps.as_ref(), config.clone(), change.clone().into_update())?;
+
+    let min_effective_at = env.block.time.plus_seconds(config.timelock_delay);
+    ensure!(
+        effective_at >= min_effective_at,
+        ContractError::ChangeTooSoon { min_effective_at }
+    );
+
+    let id = PENDING_CHANGE_ID
+        .may_load(deps.storage)?
+        .unwrap_or_default()
+        + 1;
+    PENDING_CHANGE_ID.save(deps.storage, &id)?;
+    PENDING_CHANGES.save(
+        deps.storage,
+        id,
+        &PendingChange {
+            id,
+            change,
+            proposer: info.sender,
+            effective_at,
+        },
+    )?;
+
+    Ok(Response::default()
+        .add_attribute("method", "propose_config_change")
+        .add_attribute("change_id", id.to_string())
+        .add_attribute("effective_at", effective_at.to_string()))
+}
+
+/// Cancel a queued change, needs the same role as proposing it
+pub fn cancel_config_change(
+    deps: DepsMut,
+    info: MessageInfo,
+    config: &Config,
+    id: u64,
+) -> Result<Response, ContractError> {
+    let pending = PENDING_CHANGES
+        .may_load(deps.storage, id)?
+        .ok_or(ContractError::NoPendingChange { id })?;
+    ensure_role(deps.as_ref(), config, &info, pending.change.role())?;
+
+    PENDING_CHANGES.remove(deps.storage, id);
+
+    Ok(Response::default()
+        .add_attribute("method", "cancel_config_change")
+        .add_attribute("change_id", id.to_string()))
+}
+
+/// Apply a queued change once it's effective, call-able by anyone
+pub fn execute_pending(
+    deps: DepsMut,
+    env: Env,
+    config: Config,
+    id: u64,
+) -> Result<Response, ContractError> {
+    let pending = PENDING_CHANGES
+        .may_load(deps.storage, id)?
+        .ok_or(ContractError::NoPendingChange { id })?;
+    ensure!(
+        env.block.time >= pending.effective_at,
+        ContractError::ChangeNotEffective {
+            effective_at: pending.effective_at
+        }
+    );
+    // The proposer must still be allowed to make the change, roles can be revoked meanwhile
+    ensure!(
+        has_role(
+            deps.as_ref(),
+            &config,
+            &pending.proposer,
+            pending.change.role()
+        )?,
+        ContractError::Unauthorized
+    );
+    PENDING_CHANGES.remove(deps.storage, id);
+
+    let res = sudo::update_config(deps, config, pending.change.into_update())?;
+
+    Ok(res.add_attribute("change_id", id.to_string()))
+}
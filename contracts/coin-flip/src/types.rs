@@ -1,20 +1,503 @@
+use std::collections::{BTreeMap, BTreeSet};
+
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Coin, Decimal, Env, Timestamp, Uint128};
+use cosmwasm_std::{
+    ensure, ensure_eq, Addr, Api, BlockInfo, Coin, Decimal, Env, Timestamp, Uint128,
+};
+use cw_utils::{Duration, Expiration};
 
-use crate::helpers::bps_to_decimal;
+use crate::error::ContractError;
+use crate::helpers::MAX_BPS;
+use crate::msg::SudoMsg;
+use crate::sudo::{
+    validate_max_bet_bank_bps, validate_max_integrator_bps, validate_vault_cooldown,
+};
 
 #[cw_serde]
 pub struct Config {
-    pub admin: String,
+    /// None once the admin renounced
+    pub admin: Option<Addr>,
+    /// Only the first denom can be flipped for now, fees and LPs capital are in it too
     pub denoms: Vec<String>,
     pub bank_limit: Uint128,
     pub min_bet_limit: Uint128,
     pub max_bet_limit: Uint128,
+    /// If set, max bet is this share (bps) of the available bank, capped by `max_bet_limit`
+    pub max_bet_bank_bps: Option<u64>,
     pub flips_per_block_limit: u64,
-    pub wallets: Wallets,
     pub fees: Fees,
     pub sg721_addr: Option<Addr>,
-    pub is_paused: bool,
+    /// NFT staking contract (DAO DAO cw721-staked), if set only stakers are paid holders fees.
+    /// This contract must be added to its stake hooks.
+    pub staking_addr: Option<Addr>,
+    /// Holders amounts under this are kept pending until they reach it
+    pub min_holder_payout: Uint128,
+    /// Max fee (bps of the bet) integrators can charge on top of our flip fee
+    pub max_integrator_bps: u64,
+    /// If set, anyone can trigger a distribution when it's due
+    pub distribution_schedule: Option<DistributionSchedule>,
+    /// Time (seconds) or blocks LPs wait between requesting a withdraw and withdrawing,
+    /// the request can then be used for the same duration before it expires
+    pub vault_cooldown: Duration,
+    /// If set, we request funds from it when flips or distributions leave the bank under the bank limit
+    pub treasury: Option<Treasury>,
+    /// Min seconds between proposing a config change and applying it
+    pub timelock_delay: u64,
+    pub paused: PauseScopes,
+}
+
+impl Config {
+    /// Checks between fields, each field is validated when it's set
+    pub fn validate(&self) -> Result<(), ContractError> {
+        ensure!(
+            self.min_bet_limit <= self.max_bet_limit,
+            ContractError::InvalidBetLimits {
+                min: self.min_bet_limit,
+                max: self.max_bet_limit
+            }
+        );
+        ensure!(
+            self.flips_per_block_limit > 0,
+            ContractError::InvalidFlipsPerBlockLimit
+        );
+        ensure!(self.timelock_delay > 0, ContractError::InvalidTimelockDelay);
+        Ok(())
+    }
+}
+
+/// Parts of the contract that can be paused on their own
+#[cw_serde]
+#[derive(Copy, Default)]
+pub struct PauseScopes {
+    /// New flips and vault deposits
+    pub new_flips: bool,
+    /// Settling pending flips (`DoFlips`)
+    pub settlement: bool,
+    pub distribution: bool,
+    /// Vault withdrawals and referral/integrator claims
+    pub withdrawals: bool,
+}
+
+impl PauseScopes {
+    pub fn all(paused: bool) -> Self {
+        PauseScopes {
+            new_flips: paused,
+            settlement: paused,
+            distribution: paused,
+            withdrawals: paused,
+        }
+    }
+
+    pub fn is_paused(&self, scope: PauseScope) -> bool {
+        match scope {
+            PauseScope::NewFlips => self.new_flips,
+            PauseScope::Settlement => self.settlement,
+            PauseScope::Distribution => self.distribution,
+            PauseScope::Withdrawals => self.withdrawals,
+        }
+    }
+
+    /// If we pause at least everything `other` pauses
+    pub fn includes(&self, other: &PauseScopes) -> bool {
+        (self.new_flips || !other.new_flips)
+            && (self.settlement || !other.settlement)
+            && (self.distribution || !other.distribution)
+            && (self.withdrawals || !other.withdrawals)
+    }
+}
+
+#[cw_serde]
+#[derive(Copy)]
+pub enum PauseScope {
+    NewFlips,
+    Settlement,
+    Distribution,
+    Withdrawals,
+}
+
+/// Admin that was proposed and still needs to accept
+#[cw_serde]
+pub struct PendingAdmin {
+    pub address: Addr,
+    pub expires: Option<Expiration>,
+}
+
+/// Roles the admin can grant, the admin has all of them
+#[cw_serde]
+#[derive(Copy)]
+pub enum Role {
+    /// Can pause the contract, but not unpause it
+    Pauser,
+    /// Can update fees and how they are distributed
+    FeeManager,
+    /// Can distribute fees
+    Distributor,
+    /// Can call every sudo msg, except the ones that transfer the admin
+    /// and granting or revoking super admin
+    SuperAdmin,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Pauser => "pauser",
+            Role::FeeManager => "fee_manager",
+            Role::Distributor => "distributor",
+            Role::SuperAdmin => "super_admin",
+        }
+    }
+}
+
+/// Built-in multisig that replaces the admin, members are stored separately
+#[cw_serde]
+pub struct Multisig {
+    /// Yes votes needed for a proposal to pass
+    pub threshold: u64,
+    /// How long members can vote on a proposal
+    pub voting_period: Duration,
+}
+
+#[cw_serde]
+pub enum ProposalStatus {
+    Open,
+    Passed,
+    Rejected,
+    Executed,
+}
+
+/// Sudo msg proposed by a multisig member
+#[cw_serde]
+pub struct Proposal {
+    pub id: u64,
+    pub proposer: Addr,
+    pub msg: SudoMsg,
+    /// Threshold and members count when proposed
+    pub threshold: u64,
+    pub members: u64,
+    pub yes: u64,
+    pub no: u64,
+    pub status: ProposalStatus,
+    pub expires: Expiration,
+}
+
+impl Proposal {
+    /// Open proposals that expired are rejected
+    pub fn current_status(&self, block: &BlockInfo) -> ProposalStatus {
+        match self.status {
+            ProposalStatus::Open if self.expires.is_expired(block) => ProposalStatus::Rejected,
+            ref status => status.clone(),
+        }
+    }
+
+    /// Update the status after a vote
+    pub fn update_status(&mut self) {
+        if self.yes >= self.threshold {
+            self.status = ProposalStatus::Passed;
+        } else if self.no > self.members.saturating_sub(self.threshold) {
+            // Not enough members left to reach the threshold
+            self.status = ProposalStatus::Rejected;
+        }
+    }
+}
+
+/// Config changes players get notice of, they are queued and applied after the timelock delay
+#[cw_serde]
+pub enum ConfigChange {
+    UpdateFees {
+        fees: Fees,
+    },
+    UpdateBetLimit {
+        min_bet: Uint128,
+        max_bet: Uint128,
+    },
+    UpdateBankLimit {
+        limit: Uint128,
+    },
+    UpdateSg721 {
+        addr: String,
+    },
+    /// Set the min delay (seconds) of config changes
+    UpdateTimelockDelay {
+        delay: u64,
+    },
+    /// Set (or unset) the NFT staking contract used to find holders
+    UpdateStaking {
+        addr: Option<String>,
+    },
+    /// Set the min amount we send to an holder
+    UpdateMinHolderPayout {
+        amount: Uint128,
+    },
+    /// Set the max fee integrators can charge
+    UpdateMaxIntegratorFee {
+        max_bps: u64,
+    },
+    /// Set (or unset) when anyone can trigger a distribution
+    UpdateDistributionSchedule {
+        schedule: Option<DistributionSchedule>,
+    },
+    /// Set (or unset) the max bet as a share of the available bank
+    UpdateMaxBetBankBps {
+        bps: Option<u64>,
+    },
+    /// Set how long LPs wait before they can withdraw
+    UpdateVaultCooldown {
+        cooldown: Duration,
+    },
+    /// Set (or unset) the treasury we refill the bank from
+    UpdateTreasury {
+        treasury: Option<Treasury>,
+    },
+    /// Update any config field at once
+    UpdateConfig {
+        update: Box<ConfigUpdate>,
+    },
+}
+
+impl ConfigChange {
+    /// Role needed to propose or cancel the change
+    pub fn role(&self) -> Role {
+        match self {
+            ConfigChange::UpdateFees { .. }
+            | ConfigChange::UpdateMinHolderPayout { .. }
+            | ConfigChange::UpdateMaxIntegratorFee { .. }
+            | ConfigChange::UpdateDistributionSchedule { .. } => Role::FeeManager,
+            _ => Role::SuperAdmin,
+        }
+    }
+
+    /// Config fields set by the change, they are all applied with `update_config`
+    pub fn into_update(self) -> ConfigUpdate {
+        match self {
+            ConfigChange::UpdateFees { fees } => ConfigUpdate {
+                fees: Some(fees),
+                ..Default::default()
+            },
+            ConfigChange::UpdateBetLimit { min_bet, max_bet } => ConfigUpdate {
+                min_bet_limit: Some(min_bet),
+                max_bet_limit: Some(max_bet),
+                ..Default::default()
+            },
+            ConfigChange::UpdateBankLimit { limit } => ConfigUpdate {
+                bank_limit: Some(limit),
+                ..Default::default()
+            },
+            ConfigChange::UpdateSg721 { addr } => ConfigUpdate {
+                sg721_addr: Some(OptionUpdate::Set(addr)),
+                ..Default::default()
+            },
+            ConfigChange::UpdateTimelockDelay { delay } => ConfigUpdate {
+                timelock_delay: Some(delay),
+                ..Default::default()
+            },
+            ConfigChange::UpdateStaking { addr } => ConfigUpdate {
+                staking_addr: Some(addr.into()),
+                ..Default::default()
+            },
+            ConfigChange::UpdateMinHolderPayout { amount } => ConfigUpdate {
+                min_holder_payout: Some(amount),
+                ..Default::default()
+            },
+            ConfigChange::UpdateMaxIntegratorFee { max_bps } => ConfigUpdate {
+                max_integrator_bps: Some(max_bps),
+                ..Default::default()
+            },
+            ConfigChange::UpdateDistributionSchedule { schedule } => ConfigUpdate {
+                distribution_schedule: Some(schedule.into()),
+                ..Default::default()
+            },
+            ConfigChange::UpdateMaxBetBankBps { bps } => ConfigUpdate {
+                max_bet_bank_bps: Some(bps.into()),
+                ..Default::default()
+            },
+            ConfigChange::UpdateVaultCooldown { cooldown } => ConfigUpdate {
+                vault_cooldown: Some(cooldown),
+                ..Default::default()
+            },
+            ConfigChange::UpdateTreasury { treasury } => ConfigUpdate {
+                treasury: Some(treasury.into()),
+                ..Default::default()
+            },
+            ConfigChange::UpdateConfig { update } => *update,
+        }
+    }
+}
+
+/// Update of an optional config field
+#[cw_serde]
+pub enum OptionUpdate<T> {
+    Set(T),
+    Unset,
+}
+
+impl<T> From<Option<T>> for OptionUpdate<T> {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(OptionUpdate::Unset, OptionUpdate::Set)
+    }
+}
+
+impl<T> OptionUpdate<T> {
+    pub fn into_option(self) -> Option<T> {
+        match self {
+            OptionUpdate::Set(value) => Some(value),
+            OptionUpdate::Unset => None,
+        }
+    }
+
+    pub fn as_option(&self) -> Option<&T> {
+        match self {
+            OptionUpdate::Set(value) => Some(value),
+            OptionUpdate::Unset => None,
+        }
+    }
+}
+
+/// Config fields to update, fields that are not set are kept.
+///
+/// The admin is changed with the 2 steps transfer and pause with `UpdatePause`.
+#[cw_serde]
+#[derive(Default)]
+pub struct ConfigUpdate {
+    pub denoms: Option<Vec<String>>,
+    pub bank_limit: Option<Uint128>,
+    pub min_bet_limit: Option<Uint128>,
+    pub max_bet_limit: Option<Uint128>,
+    pub max_bet_bank_bps: Option<OptionUpdate<u64>>,
+    pub flips_per_block_limit: Option<u64>,
+    pub fees: Option<Fees>,
+    pub sg721_addr: Option<OptionUpdate<String>>,
+    pub staking_addr: Option<OptionUpdate<String>>,
+    pub min_holder_payout: Option<Uint128>,
+    pub max_integrator_bps: Option<u64>,
+    pub distribution_schedule: Option<OptionUpdate<DistributionSchedule>>,
+    pub vault_cooldown: Option<Duration>,
+    pub treasury: Option<OptionUpdate<Treasury>>,
+    pub timelock_delay: Option<u64>,
+}
+
+impl ConfigUpdate {
+    /// Validate the fields that don't depend on the contract state
+    pub fn validate(&self, api: &dyn Api) -> Result<(), ContractError> {
+        if let Some(denoms) = &self.denoms {
+            ensure!(!denoms.is_empty(), ContractError::NoDenoms);
+        }
+        if let Some(bps) = &self.max_bet_bank_bps {
+            validate_max_bet_bank_bps(bps.as_option().copied())?;
+        }
+        if let Some(fees) = &self.fees {
+            fees.validate(api)?;
+        }
+        for addr in [&self.sg721_addr, &self.staking_addr]
+            .into_iter()
+            .flatten()
+            .filter_map(OptionUpdate::as_option)
+        {
+            api.addr_validate(addr)?;
+        }
+        if let Some(max_bps) = self.max_integrator_bps {
+            validate_max_integrator_bps(max_bps)?;
+        }
+        if let Some(schedule) = self
+            .distribution_schedule
+            .as_ref()
+            .and_then(OptionUpdate::as_option)
+        {
+            schedule.validate()?;
+        }
+        if let Some(cooldown) = self.vault_cooldown {
+            validate_vault_cooldown(cooldown)?;
+        }
+        if let Some(treasury) = self.treasury.as_ref().and_then(OptionUpdate::as_option) {
+            treasury.validate(api)?;
+        }
+        Ok(())
+    }
+}
+
+#[cw_serde]
+pub struct PendingChange {
+    pub id: u64,
+    pub change: ConfigChange,
+    pub proposer: Addr,
+    /// Change can be applied from this time
+    pub effective_at: Timestamp,
+}
+
+/// Reserve treasury contract that refills the bank
+#[cw_serde]
+pub struct Treasury {
+    pub addr: String,
+    /// Max amount we can request in a single period
+    pub refill_cap: Uint128,
+    /// Time (seconds) or blocks of a period
+    pub period: Duration,
+}
+
+impl Treasury {
+    pub fn validate(&self, api: &dyn Api) -> Result<(), ContractError> {
+        api.addr_validate(&self.addr)?;
+        ensure!(
+            self.period != Duration::Height(0) && self.period != Duration::Time(0),
+            ContractError::InvalidRefillPeriod
+        );
+        Ok(())
+    }
+}
+
+/// How much we refilled in the current period
+#[cw_serde]
+pub struct RefillPeriod {
+    pub ends: Expiration,
+    pub refilled: Uint128,
+}
+
+/// When anyone can trigger a distribution
+#[cw_serde]
+pub struct DistributionSchedule {
+    /// Time (seconds) or blocks between distributions
+    pub interval: Duration,
+    /// Min distributable fees needed to trigger a distribution
+    pub min_fees: Uint128,
+    /// Share of the distributable fees paid to the caller (bps)
+    pub caller_tip_bps: u64,
+}
+
+impl DistributionSchedule {
+    pub fn validate(&self) -> Result<(), ContractError> {
+        ensure!(
+            self.interval != Duration::Height(0) && self.interval != Duration::Time(0),
+            ContractError::InvalidDistributionInterval
+        );
+        ensure!(
+            self.caller_tip_bps <= MAX_BPS,
+            ContractError::InvalidCallerTip {
+                caller_tip_bps: self.caller_tip_bps
+            }
+        );
+        Ok(())
+    }
+
+    /// When the next distribution is due, based on the last one
+    pub fn next_distribution(&self, last: &LastDistribution) -> Expiration {
+        match self.interval {
+            Duration::Height(blocks) => Expiration::AtHeight(last.height + blocks),
+            Duration::Time(seconds) => Expiration::AtTime(last.time.plus_seconds(seconds)),
+        }
+    }
+}
+
+/// Block of the last distribution
+#[cw_serde]
+pub struct LastDistribution {
+    pub height: u64,
+    pub time: Timestamp,
+}
+
+/// Fee a third-party frontend charges on top of the flip fee
+#[cw_serde]
+pub struct IntegratorFee {
+    pub addr: String,
+    pub bps: u64,
 }
 
 #[cw_serde]
@@ -24,53 +507,397 @@ pub enum PickTypes {
 }
 
 #[cw_serde]
-pub struct Wallets {
-    pub team: String,
-    pub reserve: String,
+pub struct Fees {
+    /// How the collected fees are split, bps must add up to 10000
+    pub recipients: Vec<FeeRecipient>,
+    pub flip_bps: u64,
+    /// Lower flip fees for bigger bets, sorted by `min_amount`
+    #[serde(default)]
+    pub flip_fee_tiers: Vec<FlipFeeTier>,
+    /// Discount on the flip fee for NFT holders (bps of the flip fee)
+    #[serde(default)]
+    pub holder_discount_bps: u64,
+    /// Share of the flip fee credited to the flipper referrer (bps of the flip fee)
+    #[serde(default)]
+    pub referral_bps: u64,
+    /// Name of the recipient that gets what is left after flooring the fees split
+    pub remainder_recipient: String,
 }
 
+/// A share of the collected fees
 #[cw_serde]
-pub struct Fees {
-    pub team_bps: u64,
-    pub holders_bps: u64,
-    pub reserve_bps: u64,
+pub struct FeeRecipient {
+    /// Unique name, also used in the distribution attributes (`{name}_paid`)
+    pub name: String,
+    pub kind: FeeRecipientKind,
+    pub bps: u64,
+}
+
+#[cw_serde]
+pub enum FeeRecipientKind {
+    /// NFT holders (or stakers), paid by their shares
+    Holders,
+    /// Kept in the bank while it's under the bank limit, else sent to the address
+    Reserve {
+        address: String,
+    },
+    Wallet {
+        address: String,
+    },
+    /// Burned
+    Burn,
+}
+
+/// Flip fee for bets of `min_amount` and above
+#[cw_serde]
+pub struct FlipFeeTier {
+    pub min_amount: Uint128,
     pub flip_bps: u64,
 }
 
 impl Fees {
-    pub fn calculate(&self, total_fees: Uint128) -> FeesToPay {
-        let total_fees = Decimal::from_atomics(total_fees, 0).unwrap();
-        let team_perc = bps_to_decimal(self.team_bps);
-        let holders_perc = bps_to_decimal(self.holders_bps);
-        let reserve_perc = bps_to_decimal(self.reserve_bps);
+    /// Make sure the fees split adds up to 100% and the recipients are valid
+    pub fn validate(&self, api: &dyn Api) -> Result<(), ContractError> {
+        let sum = self
+            .recipients
+            .iter()
+            .try_fold(0_u64, |sum, recipient| sum.checked_add(recipient.bps))
+            .ok_or(ContractError::InvalidFeesSplit { sum: u64::MAX })?;
+        ensure_eq!(sum, MAX_BPS, ContractError::InvalidFeesSplit { sum });
 
-        let team_decimal_to_pay = total_fees.checked_mul(team_perc).unwrap();
-        let holders_decimal_to_pay = total_fees.checked_mul(holders_perc).unwrap();
-        let reserve_decimal_to_pay = total_fees.checked_mul(reserve_perc).unwrap();
+        let mut names: BTreeSet<&str> = BTreeSet::new();
+        let mut holders = 0;
+        let mut reserves = 0;
+        for recipient in self.recipients.iter() {
+            ensure!(
+                !recipient.name.is_empty() && names.insert(&recipient.name),
+                ContractError::InvalidFeeRecipientName {
+                    name: recipient.name.clone()
+                }
+            );
+            match &recipient.kind {
+                FeeRecipientKind::Holders => holders += 1,
+                FeeRecipientKind::Reserve { address } => {
+                    api.addr_validate(address)?;
+                    reserves += 1;
+                }
+                FeeRecipientKind::Wallet { address } => {
+                    api.addr_validate(address)?;
+                }
+                FeeRecipientKind::Burn => {}
+            }
+        }
+        ensure!(
+            holders <= 1 && reserves <= 1,
+            ContractError::DuplicateFeeRecipientKind
+        );
+        ensure!(
+            self.recipients
+                .iter()
+                .any(|recipient| recipient.name == self.remainder_recipient
+                    && recipient.kind != FeeRecipientKind::Holders),
+            ContractError::InvalidRemainderRecipient {
+                name: self.remainder_recipient.clone()
+            }
+        );
+
+        ensure!(
+            self.flip_bps <= MAX_BPS,
+            ContractError::InvalidFlipFee {
+                flip_bps: self.flip_bps
+            }
+        );
 
-        FeesToPay {
-            team: self.to_uint_floor(team_decimal_to_pay),
-            holders: self.to_uint_floor(holders_decimal_to_pay),
-            reserve: self.to_uint_floor(reserve_decimal_to_pay),
+        let mut last_min_amount: Option<Uint128> = None;
+        for tier in self.flip_fee_tiers.iter() {
+            ensure!(
+                tier.flip_bps <= MAX_BPS,
+                ContractError::InvalidFlipFee {
+                    flip_bps: tier.flip_bps
+                }
+            );
+            ensure!(
+                last_min_amount.is_none_or(|last| tier.min_amount > last),
+                ContractError::InvalidFlipFeeTiers
+            );
+            last_min_amount = Some(tier.min_amount);
         }
-    }
 
-    pub fn to_uint_floor(&self, to_pay: Decimal) -> Uint128 {
-        let decimal_fractional = Uint128::from(
-            10_u128
-                .checked_pow(to_pay.decimal_places())
-                .unwrap_or(1_000_000_000_000_000_000u128),
+        ensure!(
+            self.holder_discount_bps <= MAX_BPS,
+            ContractError::InvalidHolderDiscount {
+                discount_bps: self.holder_discount_bps
+            }
+        );
+        ensure!(
+            self.referral_bps <= MAX_BPS,
+            ContractError::InvalidReferralFee {
+                referral_bps: self.referral_bps
+            }
         );
-        let full_num = to_pay.floor().atomics();
-        full_num.checked_div(decimal_fractional).unwrap()
+        Ok(())
+    }
+
+    /// Get the flip fee bps for a bet, based on the bet size and if the flipper is a holder
+    pub fn flip_fee_bps(&self, amount: Uint128, is_holder: bool) -> u64 {
+        let flip_bps = self
+            .flip_fee_tiers
+            .iter()
+            .rev()
+            .find(|tier| amount >= tier.min_amount)
+            .map_or(self.flip_bps, |tier| tier.flip_bps);
+
+        if is_holder {
+            flip_bps * (MAX_BPS - self.holder_discount_bps) / MAX_BPS
+        } else {
+            flip_bps
+        }
+    }
+
+    /// Split the fees between the recipients by their bps.
+    ///
+    /// If we have no holders to pay, their share is split between the other recipients.
+    pub fn calculate(
+        &self,
+        total_fees: Uint128,
+        pay_holders: bool,
+    ) -> Result<FeesToPay, ContractError> {
+        let recipients: Vec<&FeeRecipient> = self
+            .recipients
+            .iter()
+            .filter(|recipient| pay_holders || recipient.kind != FeeRecipientKind::Holders)
+            .collect();
+        let total_bps: u64 = recipients.iter().map(|recipient| recipient.bps).sum();
+
+        let mut fees_to_pay = FeesToPay::default();
+        for recipient in recipients {
+            let amount = if total_bps == 0 {
+                Uint128::zero()
+            } else {
+                total_fees.multiply_ratio(recipient.bps, total_bps)
+            };
+
+            match recipient.kind {
+                FeeRecipientKind::Holders => fees_to_pay.holders = amount,
+                _ => fees_to_pay.recipients.push(RecipientPayout {
+                    name: recipient.name.clone(),
+                    kind: recipient.kind.clone(),
+                    amount,
+                    to_send: amount,
+                }),
+            }
+        }
+
+        // Give what is left from flooring to the remainder recipient
+        let remainder = total_fees
+            .checked_sub(fees_to_pay.holders)?
+            .checked_sub(fees_to_pay.recipients_total()?)?;
+        fees_to_pay.add_remainder(&self.remainder_recipient, remainder)?;
+
+        Ok(fees_to_pay)
     }
 }
 
 #[cw_serde]
+#[derive(Default)]
 pub struct FeesToPay {
-    pub team: Uint128,
     pub holders: Uint128,
-    pub reserve: Uint128,
+    /// Every recipient except holders
+    pub recipients: Vec<RecipientPayout>,
+}
+
+impl FeesToPay {
+    pub fn add_remainder(&mut self, name: &str, remainder: Uint128) -> Result<(), ContractError> {
+        let recipient = self
+            .recipients
+            .iter_mut()
+            .find(|recipient| recipient.name == name)
+            .ok_or(ContractError::InvalidRemainderRecipient {
+                name: name.to_string(),
+            })?;
+        recipient.amount = recipient.amount.checked_add(remainder)?;
+        recipient.to_send = recipient.to_send.checked_add(remainder)?;
+        Ok(())
+    }
+
+    pub fn recipients_total(&self) -> Result<Uint128, ContractError> {
+        recipients_total(&self.recipients)
+    }
+}
+
+/// How much a (non holders) recipient gets in a distribution
+#[cw_serde]
+pub struct RecipientPayout {
+    pub name: String,
+    pub kind: FeeRecipientKind,
+    pub amount: Uint128,
+    /// Amount we send, can be less then `amount` for the reserve (kept in the bank)
+    pub to_send: Uint128,
+}
+
+pub fn recipients_total(recipients: &[RecipientPayout]) -> Result<Uint128, ContractError> {
+    recipients
+        .iter()
+        .try_fold(Uint128::zero(), |total, recipient| {
+            Ok(total.checked_add(recipient.amount)?)
+        })
+}
+
+/// Calculated distribution of the collected fees
+#[cw_serde]
+pub struct Distribution {
+    pub denom: String,
+    pub total_fees: Uint128,
+    /// Every recipient except holders
+    pub recipients: Vec<RecipientPayout>,
+    pub holders_total_fee: Uint128,
+    pub total_shares: Decimal,
+    pub fees_per_token: Decimal,
+    /// Sorted by holder address
+    pub holders_payouts: Vec<HolderPayout>,
+    /// Amount we send holders now
+    pub pay_to_holders: Uint128,
+    /// Total pending holders amounts after this distribution
+    pub holders_pending: Uint128,
+    /// Holders fees that are left because of flooring, given to the remainder recipient
+    pub rounding_remainder: Uint128,
+    /// Pending amounts of holders that are not in the holders list anymore
+    /// (sold, unstaked or excluded), given to the remainder recipient
+    pub released_pending: Uint128,
+    /// Sorted by holder address
+    pub released_holders: Vec<String>,
+    /// Amount of excluded holders that hold tokens
+    pub excluded_holders: u64,
+    /// Paid to whoever triggered a scheduled distribution
+    pub caller_tip: Uint128,
+}
+
+/// Holders that should be paid in a distribution
+#[cw_serde]
+#[derive(Default)]
+pub struct HoldersList {
+    pub total_shares: Decimal,
+    pub holders: BTreeMap<String, HolderShares>,
+    /// Excluded holders we found holding tokens
+    pub excluded_holders: BTreeSet<String>,
+}
+
+/// Shares of an holder and the tokens that give them
+#[cw_serde]
+#[derive(Default)]
+pub struct HolderShares {
+    pub shares: Decimal,
+    pub token_ids: Vec<String>,
+}
+
+/// How much an holder gets paid in a distribution
+#[cw_serde]
+pub struct HolderPayout {
+    pub address: String,
+    pub shares: Decimal,
+    pub token_ids: Vec<String>,
+    /// Amount the holder earned in this distribution
+    pub amount: Uint128,
+    /// Pending amount from previous distributions
+    pub carried: Uint128,
+    /// Amount we send the holder, 0 if it's under the min payout
+    pub to_pay: Uint128,
+}
+
+/// A distribution that is paid to holders over multiple txs
+#[cw_serde]
+pub struct DistributionRun {
+    pub denom: String,
+    pub total_fees: Uint128,
+    /// Every recipient except holders, paid when the run starts
+    pub recipients: Vec<RecipientPayout>,
+    pub holders_total_fee: Uint128,
+    pub total_shares: Decimal,
+    pub fees_per_token: Decimal,
+    /// Total amount we need to pay holders in this run
+    pub pay_to_holders: Uint128,
+    /// Amount we paid holders so far
+    pub paid_to_holders: Uint128,
+    pub number_of_holders: u64,
+    pub paid_holders: u64,
+    /// Last holder that got paid
+    pub cursor: Option<String>,
+    pub started_at: Timestamp,
+}
+
+impl DistributionRun {
+    pub fn to_record(&self, timestamp: Timestamp) -> DistributionRecord {
+        DistributionRecord {
+            id: 0,
+            denom: self.denom.clone(),
+            total_fees: self.total_fees,
+            recipients: self.recipients.clone(),
+            holders_paid: self.paid_to_holders,
+            fees_per_token: self.fees_per_token,
+            total_shares: self.total_shares,
+            number_of_holders: self.paid_holders,
+            caller_tip: Uint128::zero(),
+            timestamp,
+        }
+    }
+}
+
+/// Record of a finished distribution
+#[cw_serde]
+pub struct DistributionRecord {
+    /// Set when the record is saved
+    pub id: u64,
+    pub denom: String,
+    pub total_fees: Uint128,
+    /// Every recipient except holders
+    pub recipients: Vec<RecipientPayout>,
+    pub holders_paid: Uint128,
+    pub fees_per_token: Decimal,
+    pub total_shares: Decimal,
+    pub number_of_holders: u64,
+    #[serde(default)]
+    pub caller_tip: Uint128,
+    pub timestamp: Timestamp,
+}
+
+/// Stats of an integrator (frontend)
+#[cw_serde]
+#[derive(Default)]
+pub struct IntegratorStats {
+    /// Number of flips done through this integrator
+    pub flips: u64,
+    /// Total bets amount done through this integrator
+    pub volume: Uint128,
+    /// Total fees credited to the integrator
+    pub earned: Uint128,
+    /// Fees the integrator already claimed
+    pub claimed: Uint128,
+}
+
+impl IntegratorStats {
+    pub fn claimable(&self) -> Result<Uint128, ContractError> {
+        Ok(self.earned.checked_sub(self.claimed)?)
+    }
+}
+
+/// Referral stats of a referrer
+#[cw_serde]
+#[derive(Default)]
+pub struct ReferralStats {
+    /// Number of wallets that registered this referrer
+    pub referred_wallets: u64,
+    /// Total bets amount of the referred wallets
+    pub volume: Uint128,
+    /// Total rewards credited to the referrer
+    pub earned: Uint128,
+    /// Rewards the referrer already claimed
+    pub claimed: Uint128,
+}
+
+impl ReferralStats {
+    pub fn claimable(&self) -> Result<Uint128, ContractError> {
+        Ok(self.earned.checked_sub(self.claimed)?)
+    }
 }
 
 #[cw_serde]
@@ -132,3 +959,88 @@ pub struct TodoFlip {
     pub block: u64,
     pub timestamp: Timestamp,
 }
+
+/// Bankroll vault, capital LPs put in the bank
+#[cw_serde]
+#[derive(Default)]
+pub struct Vault {
+    /// Total shares held by LPs
+    pub total_shares: Uint128,
+    /// Part of the bank that belongs to LPs, moves with the house PnL
+    pub assets: Uint128,
+}
+
+impl Vault {
+    /// Shares minted for a deposit of `amount`
+    pub fn shares_for(&self, amount: Uint128) -> Result<Uint128, ContractError> {
+        if self.total_shares.is_zero() {
+            return Ok(amount);
+        }
+        // Shares left but all assets are lost, new deposits would be taken by old LPs
+        ensure!(!self.assets.is_zero(), ContractError::VaultInsolvent);
+        Ok(amount.multiply_ratio(self.total_shares, self.assets))
+    }
+
+    /// Current value of `shares`
+    pub fn value_of(&self, shares: Uint128) -> Uint128 {
+        if self.total_shares.is_zero() {
+            return Uint128::zero();
+        }
+        shares.multiply_ratio(self.assets, self.total_shares)
+    }
+
+    /// Apply the house PnL of a `do_flips` to the vault by its share of the bank.
+    ///
+    /// `bank` is the bank before the flips (without the flips amounts).
+    /// Returns the profit and loss that went to the vault.
+    pub fn apply_pnl(
+        &mut self,
+        bank: Uint128,
+        profit: Uint128,
+        loss: Uint128,
+    ) -> Result<(Uint128, Uint128), ContractError> {
+        if bank.is_zero() || self.assets.is_zero() {
+            return Ok((Uint128::zero(), Uint128::zero()));
+        }
+        let vault_profit = profit.multiply_ratio(self.assets, bank);
+        let vault_loss = loss.multiply_ratio(self.assets, bank);
+        self.assets = self
+            .assets
+            .checked_add(vault_profit)?
+            .checked_sub(vault_loss)?;
+        Ok((vault_profit, vault_loss))
+    }
+}
+
+/// House capital movements and realized PnL of a denom
+#[cw_serde]
+#[derive(Default)]
+pub struct HouseLedger {
+    /// Admin deposits (`FundBank`)
+    pub deposited: Uint128,
+    /// Admin withdrawals (`WithdrawBank`)
+    pub withdrawn: Uint128,
+    /// Funds requested from the treasury
+    pub refilled: Uint128,
+    /// Lost bets, without the LPs share
+    pub flip_profit: Uint128,
+    /// Won bets we paid, without the LPs share
+    pub flip_loss: Uint128,
+}
+
+/// Vault shares of an LP
+#[cw_serde]
+#[derive(Default)]
+pub struct LpPosition {
+    pub shares: Uint128,
+    pub withdraw_request: Option<WithdrawRequest>,
+}
+
+/// Shares an LP wants to withdraw, they keep taking PnL until withdrawn
+#[cw_serde]
+pub struct WithdrawRequest {
+    pub shares: Uint128,
+    pub release_at: Expiration,
+    /// Withdraw must happen before this, a stale request has to be cancelled
+    pub expires_at: Expiration,
+}
@@ -0,0 +1,157 @@
+use cosmwasm_std::{coins, ensure, BankMsg, DepsMut, Env, MessageInfo, Uint128};
+use cw_utils::must_pay;
+use sg_std::Response;
+
+use crate::error::ContractError;
+use crate::helpers::ensure_solvent;
+use crate::state::{get_liabilities, FEES, LP_POSITIONS, VAULT};
+use crate::types::{Config, WithdrawRequest};
+
+/// Deposit into the bank, the sender gets vault shares by the current share value
+pub fn deposit(
+    deps: DepsMut,
+    info: MessageInfo,
+    config: &Config,
+) -> Result<Response, ContractError> {
+    // TODO: Currently we only accept 1 denom
+    let amount = must_pay(&info, &config.denoms[0])?;
+
+    let mut vault = VAULT.may_load(deps.storage)?.unwrap_or_default();
+    let shares = vault.shares_for(amount)?;
+    ensure!(!shares.is_zero(), ContractError::VaultDepositTooSmall);
+
+    vault.total_shares = vault.total_shares.checked_add(shares)?;
+    vault.assets = vault.assets.checked_add(amount)?;
+    VAULT.save(deps.storage, &vault)?;
+
+    let mut position = LP_POSITIONS
+        .may_load(deps.storage, &info.sender)?
+        .unwrap_or_default();
+    position.shares = position.shares.checked_add(shares)?;
+    LP_POSITIONS.save(deps.storage, &info.sender, &position)?;
+
+    Ok(Response::default()
+        .add_attribute("method", "vault_deposit")
+        .add_attribute("amount", amount)
+        .add_attribute("shares", shares))
+}
+
+/// Start the cooldown to withdraw shares.
+///
+/// Shares keep taking the house PnL until they are withdrawn,
+/// so LPs can't run away just before a losing streak.
+/// Once released, the request can be used for another cooldown, then it's stale.
+pub fn request_withdraw(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    config: &Config,
+    shares: Uint128,
+) -> Result<Response, ContractError> {
+    let mut position = LP_POSITIONS
+        .may_load(deps.storage, &info.sender)?
+        .unwrap_or_default();
+
+    ensure!(
+        position.withdraw_request.is_none(),
+        ContractError::WithdrawAlreadyRequested
+    );
+    ensure!(
+        !shares.is_zero() && shares <= position.shares,
+        ContractError::NotEnoughVaultShares {
+            shares: position.shares
+        }
+    );
+
+    let release_at = config.vault_cooldown.after(&env.block);
+    let expires_at = (config.vault_cooldown * 2).after(&env.block);
+    position.withdraw_request = Some(WithdrawRequest {
+        shares,
+        release_at,
+        expires_at,
+    });
+    LP_POSITIONS.save(deps.storage, &info.sender, &position)?;
+
+    Ok(Response::default()
+        .add_attribute("method", "vault_request_withdraw")
+        .add_attribute("shares", shares)
+        .add_attribute("release_at", release_at.to_string())
+        .add_attribute("expires_at", expires_at.to_string()))
+}
+
+/// Drop the withdraw request, needed to request again once it's stale
+pub fn cancel_withdraw(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let mut position = LP_POSITIONS
+        .may_load(deps.storage, &info.sender)?
+        .unwrap_or_default();
+    let request = position
+        .withdraw_request
+        .take()
+        .ok_or(ContractError::NoWithdrawRequest)?;
+    LP_POSITIONS.save(deps.storage, &info.sender, &position)?;
+
+    Ok(Response::default()
+        .add_attribute("method", "vault_cancel_withdraw")
+        .add_attribute("shares", request.shares))
+}
+
+/// Withdraw the requested shares by their current value, between release and expiry
+pub fn withdraw(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    config: &Config,
+) -> Result<Response, ContractError> {
+    let mut position = LP_POSITIONS
+        .may_load(deps.storage, &info.sender)?
+        .unwrap_or_default();
+    let request = position
+        .withdraw_request
+        .take()
+        .ok_or(ContractError::NoWithdrawRequest)?;
+    ensure!(
+        request.release_at.is_expired(&env.block),
+        ContractError::WithdrawNotReady {
+            release_at: request.release_at
+        }
+    );
+    ensure!(
+        !request.expires_at.is_expired(&env.block),
+        ContractError::WithdrawRequestExpired {
+            expires_at: request.expires_at
+        }
+    );
+
+    let mut vault = VAULT.load(deps.storage)?;
+    let amount = vault.value_of(request.shares);
+    vault.total_shares = vault.total_shares.checked_sub(request.shares)?;
+    vault.assets = vault.assets.checked_sub(amount)?;
+    VAULT.save(deps.storage, &vault)?;
+
+    position.shares = position.shares.checked_sub(request.shares)?;
+    if position.shares.is_zero() {
+        LP_POSITIONS.remove(deps.storage, &info.sender);
+    } else {
+        LP_POSITIONS.save(deps.storage, &info.sender, &position)?;
+    }
+
+    // Make sure we can still pay the flips that are waiting
+    let fees = FEES.load(deps.storage)?;
+    let liabilities = get_liabilities(deps.storage, &config.denoms[0])?;
+    let balance = deps
+        .querier
+        .query_balance(&env.contract.address, config.denoms[0].clone())?;
+    ensure_solvent(balance.amount, fees, liabilities.checked_add(amount)?)?;
+
+    let mut response = Response::default()
+        .add_attribute("method", "vault_withdraw")
+        .add_attribute("shares", request.shares)
+        .add_attribute("amount", amount);
+    if !amount.is_zero() {
+        response = response.add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: coins(amount.into(), config.denoms[0].clone()),
+        });
+    }
+    Ok(response)
+}